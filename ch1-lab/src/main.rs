@@ -60,6 +60,7 @@ impl rcore_console::Console for Console {
 #[panic_handler]
 fn panic(info: &core::panic::PanicInfo) -> ! {
     println!("{info}");
+    rcore_console::flush();
     system_reset(Shutdown, SystemFailure);
     loop {}
 }
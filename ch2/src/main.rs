@@ -69,6 +69,7 @@ extern "C" fn rust_main() -> ! {
 #[panic_handler]
 fn panic(info: &core::panic::PanicInfo) -> ! {
     println!("{info}");
+    rcore_console::flush();
     system_reset(Shutdown, SystemFailure);
     loop {}
 }
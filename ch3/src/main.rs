@@ -20,6 +20,16 @@ use task::TaskControlBlock;
 core::arch::global_asm!(include_str!(env!("APP_ASM")));
 // 应用程序数量。
 const APP_CAPACITY: usize = 32;
+// 时钟频率（QEMU virt 的 timebase-frequency）。
+const TIMEBASE_FREQ: u64 = 12_500_000;
+// 抢占的时间片（毫秒），编译时用环境变量 `QUANTUM_MS` 指定，默认 1 毫秒。
+const QUANTUM_MS: u64 = parse_quantum(option_env!("QUANTUM_MS"));
+// 一个时间片对应的时钟周期数。
+//
+// 固定节拍模式下它就是节拍间隔，即每秒 `1000 / QUANTUM_MS` 次时钟中断；
+// 无节拍模式（`tickless`）下它是任务被调度后最多能连续运行的时间。
+#[cfg(not(feature = "coop"))]
+const QUANTUM_TICKS: u64 = TIMEBASE_FREQ / 1000 * QUANTUM_MS;
 
 // M-Mode 入口汇编（仅在 nobios 模式下）
 // 根据目标架构选择正确的汇编文件
@@ -58,21 +68,25 @@ extern "C" fn rust_main() -> ! {
     println!();
     // 打开中断
     unsafe { sie::set_stimer() };
+    #[cfg(not(feature = "coop"))]
+    let mut timer = Timer::new();
     // 多道执行
     let mut remain = index_mod;
     let mut i = 0usize;
     while remain > 0 {
         let tcb = &mut tcbs[i];
         if !tcb.finish {
+            // 只在切换到任务时设置一次，处理完系统调用回到同一个任务不重新计时
+            #[cfg(not(feature = "coop"))]
+            timer.arm(remain > 1);
             loop {
-                #[cfg(not(feature = "coop"))]
-                sbi_rt::set_timer(time::read64() + 12500);
                 unsafe { tcb.execute() };
 
                 use scause::*;
                 let finish = match scause::read().cause() {
                     Trap::Interrupt(Interrupt::SupervisorTimer) => {
-                        sbi_rt::set_timer(u64::MAX);
+                        #[cfg(not(feature = "coop"))]
+                        timer.fire();
                         log::trace!("app{i} timeout");
                         false
                     }
@@ -113,14 +127,100 @@ extern "C" fn rust_main() -> ! {
         }
         i = (i + 1) % index_mod;
     }
+    #[cfg(not(feature = "coop"))]
+    log::info!("{} timer interrupts taken", timer.interrupts);
     system_reset(Shutdown, NoReason);
     unreachable!()
 }
 
+/// 抢占时钟。
+///
+/// 默认是固定节拍：时钟中断按 `QUANTUM_TICKS` 等间隔到来，与任务何时切换无关，
+/// 任务主动让出后，下一个任务只能用完当前节拍的剩余部分。
+///
+/// 打开 `tickless` 特性后只在需要调度时设置中断：每次进入任务时设置一个时间片之后的中断，
+/// 如果没有别的任务可以切换，就不设置中断。
+#[cfg(not(feature = "coop"))]
+struct Timer {
+    /// 固定节拍模式下下一个节拍的时刻。
+    #[cfg(not(feature = "tickless"))]
+    next_tick: u64,
+    /// 已经处理的时钟中断数量。
+    interrupts: usize,
+}
+
+#[cfg(not(feature = "coop"))]
+impl Timer {
+    fn new() -> Self {
+        #[cfg(not(feature = "tickless"))]
+        {
+            let next_tick = time::read64() + QUANTUM_TICKS;
+            sbi_rt::set_timer(next_tick);
+            Self {
+                next_tick,
+                interrupts: 0,
+            }
+        }
+        #[cfg(feature = "tickless")]
+        Self { interrupts: 0 }
+    }
+
+    /// 切换到任务时调用，`others` 表示是否还有别的任务可以切换。
+    #[inline]
+    fn arm(&mut self, others: bool) {
+        #[cfg(not(feature = "tickless"))]
+        let _ = others;
+        #[cfg(feature = "tickless")]
+        sbi_rt::set_timer(if others {
+            time::read64() + QUANTUM_TICKS
+        } else {
+            u64::MAX
+        });
+    }
+
+    /// 处理一次时钟中断。
+    fn fire(&mut self) {
+        self.interrupts += 1;
+        #[cfg(not(feature = "tickless"))]
+        {
+            // 按节拍推进，错过的节拍不补。
+            let now = time::read64();
+            self.next_tick += QUANTUM_TICKS;
+            if self.next_tick <= now {
+                self.next_tick = now + QUANTUM_TICKS;
+            }
+            sbi_rt::set_timer(self.next_tick);
+        }
+        #[cfg(feature = "tickless")]
+        sbi_rt::set_timer(u64::MAX);
+    }
+}
+
+/// 解析十进制的时间片长度，格式错误或为 0 时编译失败。
+const fn parse_quantum(env: Option<&str>) -> u64 {
+    let s = match env {
+        Some(s) => s.as_bytes(),
+        None => return 1,
+    };
+    let mut ms = 0u64;
+    let mut i = 0;
+    while i < s.len() {
+        assert!(
+            s[i].is_ascii_digit(),
+            "QUANTUM_MS should be a decimal number"
+        );
+        ms = ms * 10 + (s[i] - b'0') as u64;
+        i += 1;
+    }
+    assert!(ms > 0, "QUANTUM_MS should be positive");
+    ms
+}
+
 /// Rust 异常处理函数，以异常方式关机。
 #[panic_handler]
 fn panic(info: &core::panic::PanicInfo) -> ! {
     println!("{info}");
+    rcore_console::flush();
     system_reset(Shutdown, SystemFailure);
     loop {}
 }
@@ -176,6 +276,15 @@ mod impls {
         fn sched_yield(&self, _caller: syscall::Caller) -> isize {
             0
         }
+
+        #[inline]
+        fn sched_getquantum(&self, _caller: syscall::Caller) -> isize {
+            if cfg!(feature = "coop") {
+                0
+            } else {
+                crate::QUANTUM_MS as _
+            }
+        }
     }
 
     impl Clock for SyscallContext {
@@ -198,7 +307,7 @@ mod impls {
     static LINE_START: AtomicBool = AtomicBool::new(true);
 
     fn monotonic_time_ns() -> usize {
-        (time::read64() as u64 * 10000 / 125) as usize
+        (time::read64() * (1_000_000_000 / crate::TIMEBASE_FREQ)) as usize
     }
 
     #[inline]
@@ -0,0 +1,73 @@
+//! 内核命令行。
+//!
+//! 启动时从设备树的 `/chosen/bootargs` 读出以空白分隔的 `key=value` 选项，
+//! 覆盖编译时的默认值。QEMU 用 `-append` 设置这个属性。
+//!
+//! 支持的选项：
+//!
+//! - `log=<level>`：日志级别，覆盖编译时的 `LOG`；
+//! - `memory=<size>`：物理内存容量，可以带 `K`/`M`/`G` 后缀；
+//! - `epoch=<seconds>`：启动时刻的 Unix 时间，`CLOCK_REALTIME` 从它起算。
+
+use core::str::FromStr;
+use rcore_console::log::{self, LevelFilter};
+
+/// 命令行选项，没有给出的项是 `None`。
+#[derive(Default)]
+pub struct Options {
+    /// 日志级别。
+    pub log: Option<LevelFilter>,
+    /// 物理内存容量（字节）。
+    pub memory: Option<usize>,
+    /// 启动时刻的 Unix 时间（秒）。
+    pub epoch: Option<usize>,
+}
+
+impl Options {
+    /// 从物理地址 `dtb` 处的设备树解析命令行。
+    ///
+    /// 必须在启用分页之前调用。找不到设备树或 `bootargs` 时返回全部为空的选项。
+    pub fn from_dtb(dtb: usize) -> Self {
+        match unsafe { linker::bootargs(dtb) } {
+            Some(args) => {
+                log::info!("bootargs: {args}");
+                Self::parse(args)
+            }
+            None => Self::default(),
+        }
+    }
+
+    /// 解析命令行，不认识的选项打印警告后忽略。
+    pub fn parse(args: &str) -> Self {
+        let mut ans = Self::default();
+        for arg in args.split_ascii_whitespace() {
+            match arg.split_once('=') {
+                Some(("log", val)) => match LevelFilter::from_str(val) {
+                    Ok(level) => ans.log = Some(level),
+                    Err(_) => log::warn!("invalid boot option: {arg}"),
+                },
+                Some(("memory", val)) => match parse_size(val) {
+                    Some(size) => ans.memory = Some(size),
+                    None => log::warn!("invalid boot option: {arg}"),
+                },
+                Some(("epoch", val)) => match val.parse() {
+                    Ok(epoch) => ans.epoch = Some(epoch),
+                    Err(_) => log::warn!("invalid boot option: {arg}"),
+                },
+                _ => log::warn!("unknown boot option: {arg}"),
+            }
+        }
+        ans
+    }
+}
+
+/// 解析带可选 `K`/`M`/`G` 后缀的容量。
+fn parse_size(s: &str) -> Option<usize> {
+    let (num, shift) = match s.as_bytes().last()? {
+        b'K' | b'k' => (&s[..s.len() - 1], 10),
+        b'M' | b'm' => (&s[..s.len() - 1], 20),
+        b'G' | b'g' => (&s[..s.len() - 1], 30),
+        _ => (s, 0),
+    };
+    num.parse::<usize>().ok()?.checked_mul(1 << shift)
+}
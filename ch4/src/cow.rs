@@ -0,0 +1,38 @@
+use alloc::collections::BTreeMap;
+
+/// 被多个地址空间共享的物理页的持有者数，以物理页号为键，供写时复制使用。
+///
+/// 只记录共享的页，不在表中的页只有一个持有者。
+pub struct FrameRefs(BTreeMap<usize, usize>);
+
+impl FrameRefs {
+    /// 创建空表。
+    #[inline]
+    pub const fn new() -> Self {
+        Self(BTreeMap::new())
+    }
+
+    /// 物理页多了一个持有者。
+    #[inline]
+    pub fn share(&mut self, ppn: usize) {
+        *self.0.entry(ppn).or_insert(1) += 1;
+    }
+
+    /// 物理页是否还有其他持有者。
+    #[inline]
+    pub fn is_shared(&self, ppn: usize) -> bool {
+        self.0.contains_key(&ppn)
+    }
+
+    /// 一个持有者放弃物理页，返回是否还有其他持有者，没有时由调用者释放。
+    pub fn release(&mut self, ppn: usize) -> bool {
+        let Some(n) = self.0.get_mut(&ppn) else {
+            return false;
+        };
+        *n -= 1;
+        if *n == 1 {
+            self.0.remove(&ppn);
+        }
+        true
+    }
+}
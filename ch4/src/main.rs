@@ -2,7 +2,14 @@
 #![no_main]
 // #![deny(warnings)]
 
+mod cmdline;
+mod cow;
+mod pid;
 mod process;
+mod stats;
+mod timer;
+mod uaccess;
+mod uart;
 
 #[cfg(feature = "nobios")]
 mod msbi;
@@ -13,11 +20,23 @@ extern crate rcore_console;
 extern crate alloc;
 
 use crate::{
+    cow::FrameRefs,
     impls::SyscallContext,
-    process::Process,
+    pid::Pid,
+    process::{FaultKind, Process},
+    stats::{FrameStats, TrapStats},
+    timer::TimerQueue,
+};
+use alloc::{
+    alloc::{alloc, alloc_zeroed, dealloc},
+    boxed::Box,
+    collections::{BTreeMap, VecDeque},
+};
+use core::{
+    alloc::Layout,
+    ops::Range,
+    sync::atomic::{AtomicBool, Ordering},
 };
-use alloc::{alloc::alloc, vec::Vec};
-use core::alloc::Layout;
 use impls::Console;
 use kernel_context::{foreign::MultislotPortal, LocalContext};
 use rcore_console::log;
@@ -55,14 +74,32 @@ core::arch::global_asm!(include_str!("m_entry_rv32.S"));
 
 // 定义内核入口。
 linker::boot0!(rust_main; stack = 6 * 4096);
-// 物理内存容量 = 24 MiB。
+// 默认物理内存容量 = 24 MiB，可以用命令行 `memory=` 覆盖。
 const MEMORY: usize = 24 << 20;
+// 默认的启动时刻 Unix 时间（2024-01-01T00:00:00Z），可以用命令行 `epoch=` 覆盖。
+const EPOCH: usize = 1_704_067_200;
+// 启动时只加载这个序号的应用程序，编译时用环境变量 `INIT_APP` 指定，不指定时加载全部应用程序。
+const INIT_APP: Option<usize> = parse_index(option_env!("INIT_APP"));
 // 传送门所在虚页。
 const PROTAL_TRANSIT: VPN<VmMode> = VPN::MAX;
-// 进程列表。
-static mut PROCESSES: Vec<Process> = Vec::new();
+// 就绪队列，队首是正在运行的进程，轮转时移到队尾。
+static mut PROCESSES: VecDeque<Process> = VecDeque::new();
+// 已退出、等待父进程回收的进程，以进程号为键，值是父进程号和退出码。
+static mut ZOMBIES: BTreeMap<Pid, (Pid, isize)> = BTreeMap::new();
+// 定时唤醒事件，目标是进程号。
+static mut TIMERS: TimerQueue = TimerQueue::new();
+// 各类陷入的次数。
+static mut TRAP_STATS: TrapStats = TrapStats::new();
+// 页管理器分配的物理页数。
+static mut FRAME_STATS: FrameStats = FrameStats::new();
+// 写时复制共享的物理页的持有者数。
+static mut FRAME_REFS: FrameRefs = FrameRefs::new();
+// 调度栈最低处的哨兵值，被改写说明调度栈溢出。
+const STACK_CANARY: usize = 0xdead_beef;
+// 调度栈最低处的字，在内核地址空间中恒等映射，由调度线程每轮检查。
+static mut SCHED_STACK_CANARY: usize = 0;
 
-extern "C" fn rust_main() -> ! {
+extern "C" fn rust_main(_hartid: usize, dtb: usize) -> ! {
     let layout = linker::KernelLayout::locate();
     // bss 段清零
     unsafe { layout.zero_bss() };
@@ -70,13 +107,27 @@ extern "C" fn rust_main() -> ! {
     rcore_console::init_console(&Console);
     rcore_console::set_timestamp(impls::monotonic_time_ms);
     rcore_console::set_log_level(option_env!("LOG"));
+    // 解析命令行，覆盖编译时的配置
+    let options = cmdline::Options::from_dtb(dtb);
+    if let Some(level) = options.log {
+        log::set_max_level(level);
+    }
+    impls::set_boot_epoch(options.epoch.unwrap_or(EPOCH));
+    let memory = match options.memory {
+        Some(memory) if memory > layout.len() => memory,
+        Some(memory) => {
+            log::warn!("memory={memory:#x} is smaller than the kernel, ignored");
+            MEMORY
+        }
+        None => MEMORY,
+    };
     rcore_console::test_log();
     // 初始化内核堆
     kernel_alloc::init(layout.start() as _);
     unsafe {
         kernel_alloc::transfer(core::slice::from_raw_parts_mut(
             layout.end() as _,
-            MEMORY - layout.len(),
+            memory - layout.len(),
         ))
     };
     // 建立异界传送门
@@ -84,25 +135,56 @@ extern "C" fn rust_main() -> ! {
     let portal_layout = Layout::from_size_align(portal_size, 1 << VmMode::PAGE_BITS).unwrap();
     let portal_ptr = unsafe { alloc(portal_layout) };
     assert!(portal_layout.size() < 1 << VmMode::PAGE_BITS);
+    // 恒等映射的内核和堆所在的虚页
+    let heap = VAddr::<VmMode>::new(layout.start()).floor()
+        ..VAddr::<VmMode>::new(layout.start() + memory).ceil();
     // 建立内核地址空间
-    let mut ks = kernel_space(layout, MEMORY, portal_ptr as _);
+    let mut ks = kernel_space(layout, memory, portal_ptr as _);
     let portal_idx = PROTAL_TRANSIT.index_in(VmMode::MAX_LEVEL);
     // 加载应用程序
-    for (i, elf) in linker::AppMeta::locate().iter().enumerate() {
+    let apps = linker::AppMeta::locate();
+    if apps.is_empty() {
+        log::error!("no applications linked");
+        system_reset(Shutdown, NoReason);
+        unreachable!()
+    }
+    if let Some(init) = INIT_APP.filter(|&init| init >= apps.len()) {
+        log::error!(
+            "init app[{init}] does not exist, only {} linked",
+            apps.len()
+        );
+        system_reset(Shutdown, NoReason);
+        unreachable!()
+    }
+    for (i, elf) in apps.iter().enumerate() {
         let base = elf.as_ptr() as usize;
         log::info!("detect app[{i}]: {base:#x}..{:#x}", base + elf.len());
+        // 指定了 init 时其余应用程序不在启动时加载
+        if INIT_APP.is_some_and(|init| init != i) {
+            continue;
+        }
         if let Some(process) = Process::new(ElfFile::new(elf).unwrap()) {
             // 映射异界传送门
             process.address_space.root()[portal_idx] = ks.root()[portal_idx];
-            unsafe { PROCESSES.push(process) };
+            unsafe { PROCESSES.push_back(process) };
+        } else {
+            log::error!("app[{i}]: failed to load");
         }
     }
+    // 启动自检只在调试构建中运行，不拖慢发布构建的启动
+    if cfg!(debug_assertions) {
+        check_reclaim(apps.iter().next().unwrap(), &ks, portal_idx);
+    }
 
     // 建立调度栈
     let page_layout: Layout =
         unsafe { Layout::from_size_align_unchecked(2 << VmMode::PAGE_BITS, 1 << VmMode::PAGE_BITS) };
     let pages = 2;
     let stack = unsafe { alloc(page_layout) };
+    unsafe {
+        (stack as *mut usize).write_volatile(STACK_CANARY);
+        SCHED_STACK_CANARY = stack as usize;
+    }
     
     // RV64: 使用更大的地址空间
     #[cfg(target_pointer_width = "64")]
@@ -111,8 +193,14 @@ extern "C" fn rust_main() -> ! {
     #[cfg(target_pointer_width = "32")]
     let stack_top_vpn = 1usize << 19;
     
+    let sched_stack = VPN::new(stack_top_vpn - pages)..VPN::new(stack_top_vpn);
+    check_layout(heap, sched_stack.clone());
+    if cfg!(debug_assertions) {
+        check_unmap();
+        check_app_meta();
+    }
     ks.map_extern(
-        VPN::new(stack_top_vpn - pages)..VPN::new(stack_top_vpn),
+        sched_stack,
         PPN::new(stack as usize >> VmMode::PAGE_BITS),
         VmFlags::build_from_str("_WRV"),
     );
@@ -142,22 +230,70 @@ extern "C" fn schedule() -> ! {
     syscall::init_io(&SyscallContext);
     syscall::init_process(&SyscallContext);
     syscall::init_scheduling(&SyscallContext);
+    syscall::init_memory(&SyscallContext);
     syscall::init_clock(&SyscallContext);
     while !unsafe { PROCESSES.is_empty() } {
-        let ctx = unsafe { &mut PROCESSES[0].context };
+        check_stack_canary();
+        // 唤醒到期的进程
+        let now = timer::now();
+        // 进程号和唤醒时刻都对得上才是这个进程当前的睡眠，过时的事件直接丢弃
+        while let Some((deadline, pid)) = unsafe { TIMERS.pop_expired(now) } {
+            if let Some(p) =
+                unsafe { PROCESSES.iter_mut() }.find(|p| p.pid == pid && p.wake_at == deadline)
+            {
+                p.wake_at = 0;
+            }
+        }
+        // 睡眠中的进程到达唤醒时间之前不执行，轮转到第一个就绪的进程；都在睡眠时等待下一个定时事件
+        let Some(ready) = unsafe { PROCESSES.iter() }.position(|p| now >= p.wake_at) else {
+            if let Some(deadline) = unsafe { TIMERS.next_deadline() } {
+                timer::idle_until(deadline);
+            }
+            continue;
+        };
+        unsafe { PROCESSES.rotate_left(ready) };
+        let process = unsafe { &mut PROCESSES[0] };
+        #[cfg(feature = "check-sp")]
+        if !process.sp_valid(process.context.context.sp()) {
+            log::error!(
+                "process {:#x} has invalid sp = {:#x}",
+                process.context.satp,
+                process.context.context.sp()
+            );
+            exit_current(-3);
+            continue;
+        }
+        let ctx = &mut process.context;
+        timer::arm_quantum();
         unsafe { ctx.execute(portal, ()) };
-        match scause::read().cause() {
+        timer::disarm();
+        let cause = scause::read().cause();
+        // 系统调用在分发时按调用号计数
+        if !matches!(
+            cause,
+            scause::Trap::Exception(scause::Exception::UserEnvCall)
+        ) {
+            unsafe { TRAP_STATS.count_trap(cause) };
+        }
+        match cause {
             scause::Trap::Exception(scause::Exception::UserEnvCall) => {
                 use syscall::{SyscallId as Id, SyscallResult as Ret};
 
                 let ctx = &mut ctx.context;
                 let id: Id = ctx.a(7).into();
+                unsafe { TRAP_STATS.count_syscall(id) };
                 let args = [ctx.a(0), ctx.a(1), ctx.a(2), ctx.a(3), ctx.a(4), ctx.a(5)];
                 match syscall::handle(Caller { entity: 0, flow: 0 }, id, args) {
                     Ret::Done(ret) => match id {
-                        Id::EXIT => unsafe {
-                            PROCESSES.remove(0);
-                        },
+                        Id::EXIT => exit_current(ret),
+                        // 新映像从入口开始执行，上下文已经设置好
+                        Id::EXECVE if ret == 0 => {}
+                        // 让出的进程排到队尾
+                        Id::SCHED_YIELD => {
+                            *ctx.a_mut(0) = ret as _;
+                            ctx.move_next();
+                            unsafe { PROCESSES.rotate_left(1) };
+                        }
                         _ => {
                             *ctx.a_mut(0) = ret as _;
                             ctx.move_next();
@@ -165,17 +301,79 @@ extern "C" fn schedule() -> ! {
                     },
                     Ret::Unsupported(_) => {
                         log::info!("id = {id:?}");
-                        unsafe { PROCESSES.remove(0) };
+                        exit_current(-2);
                     }
                 }
             }
+            // 时间片用完，当前进程排到队尾
+            scause::Trap::Interrupt(scause::Interrupt::SupervisorTimer) => unsafe {
+                PROCESSES.rotate_left(1);
+            },
+            // 用户程序用 ebreak 设置的检查点
+            scause::Trap::Exception(scause::Exception::Breakpoint) => {
+                log::warn!("ebreak at {:#x}", process.context.context.pc());
+                dump_registers(&process.context.context);
+                if cfg!(feature = "debug-ebreak") {
+                    // 压缩的 c.ebreak 只有 2 字节
+                    let pc = process.context.context.pc();
+                    let len = match process
+                        .address_space
+                        .translate::<u16>(VAddr::new(pc), VmFlags::build_from_str("XV"))
+                    {
+                        Some(inst) if unsafe { *inst.as_ptr() } & 0b11 != 0b11 => 2,
+                        _ => 4,
+                    };
+                    *process.context.context.pc_mut() = pc + len;
+                } else {
+                    exit_current(-3);
+                }
+            }
+            // 写共享的写时复制页，分开之后重新执行
+            scause::Trap::Exception(scause::Exception::StorePageFault)
+                if process.break_cow(stval::read()) => {}
+            // 访问按需分配区域中还没有映射的页，映射零页之后重新执行
+            scause::Trap::Exception(
+                scause::Exception::StorePageFault | scause::Exception::LoadPageFault,
+            ) if process.fill_lazy(stval::read()) => {}
+            // 访问栈底下方的保护页或被释放的栈页，修复之后重新执行。
+            // 原子指令的缺页也报告为读写缺页，同样修复后重新执行，不会重复生效
+            scause::Trap::Exception(
+                scause::Exception::StorePageFault | scause::Exception::LoadPageFault,
+            ) if matches!(
+                process.classify_fault(stval::read()),
+                FaultKind::StackHole | FaultKind::Guard
+            ) && process.grow_stack(stval::read()) => {}
             e => {
                 log::error!(
                     "unsupported trap: {e:?}, stval = {:#x}, sepc = {:#x}",
                     stval::read(),
-                    ctx.context.pc()
+                    process.context.context.pc()
                 );
-                unsafe { PROCESSES.remove(0) };
+                if let scause::Trap::Exception(
+                    scause::Exception::LoadPageFault
+                    | scause::Exception::StorePageFault
+                    | scause::Exception::InstructionPageFault,
+                ) = e
+                {
+                    log::error!(
+                        "fault address is {:?}",
+                        process.classify_fault(stval::read())
+                    );
+                }
+                if let scause::Trap::Exception(
+                    scause::Exception::LoadPageFault
+                    | scause::Exception::StorePageFault
+                    | scause::Exception::LoadFault
+                    | scause::Exception::StoreFault,
+                ) = e
+                {
+                    if process.is_atomic_inst(process.context.context.pc()) {
+                        log::error!("faulting instruction is atomic");
+                    }
+                }
+                #[cfg(feature = "coredump")]
+                coredump(process);
+                exit_current(-3);
             }
         }
     }
@@ -183,14 +381,288 @@ extern "C" fn schedule() -> ! {
     unreachable!()
 }
 
+/// 结束当前进程并释放它的地址空间，只留下退出码等父进程用 `wait` 回收。
+///
+/// 还在运行的子进程成为进程树的根；已退出的子进程不会再有人回收，直接丢弃。
+/// 没有父进程的进程同样不会被回收，不留下退出码。不再需要的进程号随即释放。
+fn exit_current(exit_code: isize) {
+    unsafe {
+        let process = PROCESSES.pop_front().unwrap();
+        for child in PROCESSES.iter_mut() {
+            if child.parent == Some(process.pid) {
+                child.parent = None;
+            }
+        }
+        ZOMBIES.retain(|&child, (parent, _)| {
+            let orphan = *parent == process.pid;
+            if orphan {
+                pid::free(child);
+            }
+            !orphan
+        });
+        match process.parent {
+            Some(parent) => {
+                ZOMBIES.insert(process.pid, (parent, exit_code));
+            }
+            None => pid::free(process.pid),
+        }
+    }
+}
+
+/// 检查手工选定的虚页布局互不重叠，换用新的分页模式时这些常数可能冲突。
+///
+/// 内核地址空间里有恒等映射的内核和堆、调度栈和传送门，用户地址空间里有用户栈和同一个传送门。
+fn check_layout(heap: Range<VPN<VmMode>>, sched_stack: Range<VPN<VmMode>>) {
+    fn overlaps(a: &Range<VPN<VmMode>>, b: &Range<VPN<VmMode>>) -> bool {
+        a.start < b.end && b.start < a.end
+    }
+    let portal = PROTAL_TRANSIT..PROTAL_TRANSIT + 1;
+    let user_stack = Process::stack_limit();
+    let pairs = [
+        ("kernel and heap", &heap, "scheduling stack", &sched_stack),
+        ("kernel and heap", &heap, "portal", &portal),
+        ("scheduling stack", &sched_stack, "portal", &portal),
+        ("user stack", &user_stack, "portal", &portal),
+    ];
+    for (a, ra, b, rb) in pairs {
+        assert!(!overlaps(ra, rb), "{a} {ra:?} overlaps {b} {rb:?}");
+    }
+}
+
+/// 在临时地址空间里映射再移除几个页，检查拥有的页帧归还给了分配器，外部映射的页不受影响，
+/// 只与映射部分重叠的范围只移除交集。
+///
+/// 页数不是 2 的幂，按整块分配、逐页释放时伙伴分配器会漏掉向上取整的尾部，空闲块无法合并，堆的最大空闲块随之变小。
+/// 只检查数据页，临时地址空间的页表页没有回收。
+fn check_unmap() {
+    const PAGES: usize = 3;
+    let flags = VmFlags::build_from_str("_WRV");
+    let mut space = AddressSpace::<VmMode, VmManager>::new();
+    let data = unsafe { FRAME_STATS.data() };
+    let owned = VPN::new(0x10)..VPN::new(0x10 + PAGES);
+    space.map(owned.clone(), &[], 0, flags);
+    assert_eq!(unsafe { FRAME_STATS.data() }, data + PAGES);
+    assert_eq!(space.unmap(owned.clone()), PAGES);
+    assert_eq!(
+        unsafe { FRAME_STATS.data() },
+        data,
+        "unmapped frames leaked"
+    );
+
+    // 范围的两端越过映射的边界时只移除交集，剩下的页仍然可以访问
+    // 页表页已经在上面分配过了，之后堆上只有数据页的变化
+    let largest = kernel_alloc::largest_free_block();
+    space.map(owned.clone(), &[], 0, flags);
+    let head = VPN::new(owned.start.val() - 2)..owned.start + 2;
+    assert_eq!(space.unmap(head), 2);
+    assert_eq!(space.find_mapped(owned.start..owned.start + 2), None);
+    assert_eq!(space.find_mapped(owned.clone()), Some(owned.start + 2));
+    assert_eq!(space.unmap(owned.start + 2..owned.end + 2), PAGES - 2);
+    assert_eq!(space.unmap(owned), 0);
+    assert_eq!(unsafe { FRAME_STATS.data() }, data);
+    assert_eq!(
+        kernel_alloc::largest_free_block(),
+        largest,
+        "unmapped frames leaked from the heap"
+    );
+
+    let layout = Layout::from_size_align(1 << VmMode::PAGE_BITS, 1 << VmMode::PAGE_BITS).unwrap();
+    let page = unsafe { alloc_zeroed(layout) };
+    unsafe { page.write_bytes(0x5a, layout.size()) };
+    let external = VPN::new(0x20)..VPN::new(0x21);
+    space.map_extern(
+        external.clone(),
+        PPN::new(page as usize >> VmMode::PAGE_BITS),
+        flags,
+    );
+    assert_eq!(space.unmap(external), 1);
+    let content = unsafe { core::slice::from_raw_parts(page, layout.size()) };
+    assert!(content.iter().all(|&b| b == 0x5a), "external page freed");
+    unsafe { dealloc(page, layout) };
+}
+
+/// 构造几个损坏的应用程序表，检查遍历时什么也不产生，而不是越界读写。
+///
+/// 表的布局和 [`linker::AppMeta`] 一致：`base`、`step`、`count`、`names`，然后是位置表。
+fn check_app_meta() {
+    fn meta(table: &[usize]) -> &'static linker::AppMeta {
+        unsafe { &*(Box::leak(Box::<[usize]>::from(table)).as_ptr() as *const linker::AppMeta) }
+    }
+    static DATA: [u8; 8] = *b"rcore-ch";
+    let pos = DATA.as_ptr() as usize;
+    let empty = |table: &[usize]| meta(table).iter().next().is_none();
+    // 数量超过上限，位置表只有一项
+    assert!(empty(&[0, 0, usize::MAX, 0, pos]));
+    // 位置表倒序
+    assert!(empty(&[0, 0, 2, 0, pos, pos + 8, pos]));
+    // 拷贝目标覆盖内核
+    let base = linker::KernelLayout::locate().start();
+    assert!(empty(&[base, 0, 1, 0, pos, pos + 8]));
+    // 拷贝目标的结尾溢出
+    assert!(empty(&[usize::MAX, 0, 1, 0, pos, pos + 8]));
+    assert!(empty(&[0x1000, usize::MAX, 2, 0, pos, pos, pos + 8]));
+    // 合法的表照常遍历
+    let apps = meta(&[0, 0, 2, 0, pos, pos + 3, pos + 8]);
+    assert!(apps.iter().eq([&DATA[..3], &DATA[3..]]));
+}
+
+/// 反复创建并销毁进程，检查页帧全部回收，共享的传送门页表不受影响。
+///
+/// 泄漏时几百轮就会耗尽堆，在这里失败比在运行应用程序时失败更容易定位。
+fn check_reclaim(elf: &[u8], ks: &AddressSpace<VmMode, VmManager>, portal_idx: usize) {
+    const ROUNDS: usize = 256;
+    let frames = || unsafe { (FRAME_STATS.table(), FRAME_STATS.data()) };
+    let before = frames();
+    let portal = ks.root()[portal_idx];
+    for _ in 0..ROUNDS {
+        let Some(process) = Process::new(ElfFile::new(elf).unwrap()) else {
+            return;
+        };
+        process.address_space.root()[portal_idx] = portal;
+        pid::free(process.pid);
+    }
+    assert_eq!(frames(), before, "process frames leaked");
+    assert_eq!(ks.root()[portal_idx], portal, "portal page table freed");
+}
+
+/// 检查调度栈的哨兵值，被改写时 panic，而不是带着被破坏的堆继续运行。
+#[inline]
+fn check_stack_canary() {
+    let addr = unsafe { SCHED_STACK_CANARY };
+    let canary = unsafe { (addr as *const usize).read_volatile() };
+    if canary != STACK_CANARY {
+        panic!("scheduling stack overflow: canary at {addr:#x} is {canary:#x}");
+    }
+}
+
+/// 按名字查找链接进来的应用程序。
+fn find_app(name: &[u8]) -> Option<&'static [u8]> {
+    linker::AppMeta::locate()
+        .named()
+        .find(|(app, _)| app.as_bytes() == name)
+        .map(|(_, data)| data)
+}
+
+/// 解析十进制的应用程序序号，格式错误时编译失败。
+const fn parse_index(env: Option<&str>) -> Option<usize> {
+    let s = match env {
+        Some(s) => s.as_bytes(),
+        None => return None,
+    };
+    assert!(!s.is_empty(), "INIT_APP should be a decimal index");
+    let mut index = 0usize;
+    let mut i = 0;
+    while i < s.len() {
+        assert!(s[i].is_ascii_digit(), "INIT_APP should be a decimal index");
+        index = index * 10 + (s[i] - b'0') as usize;
+        i += 1;
+    }
+    Some(index)
+}
+
+/// 打印用户上下文的全部通用寄存器，每行 4 个。
+fn dump_registers(ctx: &LocalContext) {
+    use alloc::string::String;
+    use core::fmt::Write;
+
+    const WIDTH: usize = 2 + 2 * core::mem::size_of::<usize>();
+    for i in (1..32).step_by(4) {
+        let mut line = String::new();
+        for n in i..(i + 4).min(32) {
+            let _ = write!(line, "x{n:<2} = {:#0WIDTH$x}  ", ctx.x(n));
+        }
+        log::warn!("{}", line.trim_end());
+    }
+}
+
+/// 向调试通道打印进程的事后转储：寄存器、出错的指令，以及 pc 和 sp 所在页的内容。
+///
+/// 全部通过进程的地址空间读取，没有映射的页只打印一行说明。
+#[cfg(feature = "coredump")]
+fn coredump(process: &Process) {
+    const USER: VmFlags<VmMode> = VmFlags::build_from_str("U___V");
+    const PAGE_SIZE: usize = 1 << VmMode::PAGE_BITS;
+
+    let ctx = &process.context.context;
+    let read_u16 = |addr: usize| {
+        process
+            .address_space
+            .translate::<u16>(VAddr::new(addr), USER)
+            .map(|p| unsafe { p.as_ptr().read_volatile() })
+    };
+    eprintln!("==== coredump: satp = {:#x} ====", process.context.satp);
+    dump_registers(ctx);
+    // 指令可能是压缩的 2 字节，也可能跨页，分两次读
+    let pc = ctx.pc();
+    match read_u16(pc) {
+        Some(lo) if lo & 0b11 != 0b11 => eprintln!("inst @ {pc:#x}: {lo:04x}"),
+        Some(lo) => match read_u16(pc + 2) {
+            Some(hi) => eprintln!("inst @ {pc:#x}: {:08x}", ((hi as u32) << 16) | lo as u32),
+            None => eprintln!("inst @ {pc:#x}: {lo:04x} <truncated>"),
+        },
+        None => eprintln!("inst @ {pc:#x}: <unmapped>"),
+    }
+    for (name, addr) in [("pc", pc), ("sp", ctx.sp())] {
+        let base = addr & !(PAGE_SIZE - 1);
+        eprintln!("page of {name} @ {base:#x}:");
+        let Some(ptr) = process
+            .address_space
+            .translate::<u8>(VAddr::new(base), USER)
+        else {
+            eprintln!("  <unmapped>");
+            continue;
+        };
+        let page = unsafe { core::slice::from_raw_parts(ptr.as_ptr(), PAGE_SIZE) };
+        // 和 hexdump 一样，连续的全零行折叠成一个 `*`
+        let mut skipping = false;
+        for (i, line) in page.chunks(16).enumerate() {
+            if line.iter().all(|&b| b == 0) {
+                if !skipping {
+                    eprintln!("  *");
+                    skipping = true;
+                }
+                continue;
+            }
+            skipping = false;
+            eprint!("  {:#x}:", base + i * 16);
+            for b in line {
+                eprint!(" {b:02x}");
+            }
+            eprintln!();
+        }
+    }
+    eprintln!("==== end of coredump ====");
+}
+
+/// 是否已经进入过异常处理函数。
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
 /// Rust 异常处理函数，以异常方式关机。
 #[panic_handler]
 fn panic(info: &core::panic::PanicInfo) -> ! {
+    // 日志或关机过程中再次 panic，说明这条路径本身坏了，不能再走一遍
+    if PANICKING.swap(true, Ordering::Relaxed) {
+        double_panic()
+    }
     log::error!("{info}");
+    rcore_console::flush();
     system_reset(Shutdown, SystemFailure);
     loop {}
 }
 
+/// 处理 panic 时再次 panic：绕过控制台和 SBI，直接向串口写一条固定的消息后停机。
+fn double_panic() -> ! {
+    // 内核是恒等映射的，关闭分页后仍能继续执行，主串口也就不需要映射在地址空间里
+    unsafe { satp::set(satp::Mode::Bare, 0, 0) };
+    let base = uart::DEBUG_UART.unwrap_or(uart::CONSOLE_UART);
+    b"\ndouble panic, halted\n"
+        .iter()
+        .for_each(|&c| uart::putchar(base, c));
+    loop {
+        unsafe { riscv::asm::wfi() };
+    }
+}
+
 fn kernel_space(
     layout: linker::KernelLayout,
     memory: usize,
@@ -230,6 +702,16 @@ fn kernel_space(
         PPN::new(portal >> VmMode::PAGE_BITS),
         VmFlags::build_from_str("__G_XWRV"),
     );
+    // 映射调试串口
+    if let Some(base) = uart::DEBUG_UART {
+        log::info!("(uart) ---> {base:#10x}");
+        let vpn = VAddr::<VmMode>::new(base).floor();
+        space.map_device(
+            vpn..vpn + 1,
+            PPN::new(vpn.val()),
+            VmFlags::build_from_str("_WRV"),
+        );
+    }
     println!();
     
     // 根据架构设置 satp
@@ -237,23 +719,72 @@ fn kernel_space(
     unsafe { satp::set(satp::Mode::Sv39, 0, space.root_ppn().val()) };
     #[cfg(target_pointer_width = "32")]
     unsafe { satp::set(satp::Mode::Sv32, 0, space.root_ppn().val()) };
+    debug_assert!(
+        process::satp_mode_matches(satp::read().bits()),
+        "satp = {:#x} mismatches VmMode",
+        satp::read().bits()
+    );
     
     space
 }
 
 /// 各种接口库的实现。
 mod impls {
-    use crate::PROCESSES;
-    use alloc::alloc::alloc_zeroed;
+    use crate::{
+        pid, timer,
+        uaccess::{copy_from_user, copy_to_user, get_user, put_user, read_user_cstr},
+        FRAME_REFS, FRAME_STATS, PROCESSES, PROTAL_TRANSIT, TIMERS, TRAP_STATS, ZOMBIES,
+    };
+    use alloc::{
+        alloc::{alloc_zeroed, dealloc},
+        string::String,
+        vec::Vec,
+    };
     use core::{
         alloc::Layout,
         ptr::NonNull,
-        sync::atomic::{AtomicBool, Ordering},
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
     };
-    use kernel_vm::PageManager;
+    use kernel_vm::{page_table::VmMeta, PageManager};
     use riscv::register::time;
     use rcore_console::log;
     use syscall::*;
+    use xmas_elf::ElfFile;
+
+    /// 调试构建下，回收的页被填充为此字节。
+    ///
+    /// 悬垂映射读到连续的 `0xAA` 即可判断是释放后使用。
+    const POISON: u8 = 0xAA;
+
+    /// 回收前毒化 `size` 字节的页帧，发布构建下为空操作。
+    #[inline]
+    fn poison(ptr: *mut u8, size: usize) {
+        if cfg!(debug_assertions) {
+            unsafe { ptr.write_bytes(POISON, size) };
+        }
+    }
+
+    /// 释放 `level` 级页表 `table` 的各项指向的、属于地址空间的页，不释放 `table` 本身。
+    ///
+    /// 遇到叶子项不再向下。没有 `OWNED` 标记的项（外部映射的页）原样跳过。
+    /// 数据页和页表页都是逐页分配的，见 [`AddressSpace::try_map`]，所以逐页释放与分配时的布局一致。
+    fn free_table<Meta: VmMeta, M: PageManager<Meta>>(
+        manager: &mut M,
+        table: NonNull<Pte<Meta>>,
+        level: usize,
+    ) {
+        let len = 1 << Meta::LEVEL_BITS[level];
+        let entries = unsafe { core::slice::from_raw_parts(table.as_ptr(), len) };
+        for &pte in entries {
+            if !pte.is_valid() || !manager.check_owned(pte) {
+                continue;
+            }
+            if !pte.is_leaf() {
+                free_table(manager, manager.p_to_v(pte.ppn()), level - 1);
+            }
+            manager.deallocate(pte, 1);
+        }
+    }
 
     // ============ RV64 Sv39 支持 ============
     #[cfg(target_pointer_width = "64")]
@@ -265,7 +796,10 @@ mod impls {
 
     #[cfg(target_pointer_width = "64")]
     impl Sv39Manager {
-        const OWNED: VmFlags<Sv39> = unsafe { VmFlags::from_raw(1 << 8) };
+        /// 页由地址空间分配，解除映射时释放。
+        pub const OWNED: VmFlags<Sv39> = unsafe { VmFlags::from_raw(1 << 8) };
+        /// 页与其他地址空间共享，写入前要复制，见 [`FrameRefs`](crate::cow::FrameRefs)。
+        pub const COW: VmFlags<Sv39> = unsafe { VmFlags::from_raw(1 << 9) };
 
         #[inline]
         fn page_alloc<T>(count: usize) -> *mut T {
@@ -283,6 +817,7 @@ mod impls {
     impl PageManager<Sv39> for Sv39Manager {
         #[inline]
         fn new_root() -> Self {
+            unsafe { FRAME_STATS.allocate(true, 1) };
             Self(NonNull::new(Self::page_alloc(1)).unwrap())
         }
 
@@ -313,16 +848,54 @@ mod impls {
 
         #[inline]
         fn allocate(&mut self, len: usize, flags: &mut VmFlags<Sv39>) -> NonNull<u8> {
+            // 中间页表只有 V 位，叶子页带有读、写、执行权限
+            unsafe { FRAME_STATS.allocate(!flags.is_leaf(), len) };
             *flags |= Self::OWNED;
             NonNull::new(Self::page_alloc(len)).unwrap()
         }
 
-        fn deallocate(&mut self, _pte: Pte<Sv39>, _len: usize) -> usize {
-            todo!()
+        /// 不属于地址空间的页（传送门、外部映射的内核区域）保持原样，返回 0。
+        /// 写时复制共享的页只减少持有者数，最后一个持有者才真正释放。
+        fn deallocate(&mut self, pte: Pte<Sv39>, len: usize) -> usize {
+            if !self.check_owned(pte) || unsafe { FRAME_REFS.release(pte.ppn().val()) } {
+                return 0;
+            }
+            unsafe { FRAME_STATS.deallocate(!pte.is_leaf(), len) };
+            let ptr = self.p_to_v::<u8>(pte.ppn()).as_ptr();
+            poison(ptr, len << Sv39::PAGE_BITS);
+            unsafe {
+                dealloc(
+                    ptr,
+                    Layout::from_size_align_unchecked(len << Sv39::PAGE_BITS, 1 << Sv39::PAGE_BITS),
+                )
+            };
+            len
+        }
+
+        /// 可写的页和已经共享的写时复制页增加一个持有者，去掉写权限并标记 [`COW`](Self::COW)。
+        /// 只读的页不共享，否则它被 `mprotect` 改为可写时无法与写时复制页区分。
+        fn share_cow(&mut self, pte: Pte<Sv39>) -> Option<VmFlags<Sv39>> {
+            const WRITE: VmFlags<Sv39> = VmFlags::build_from_str("W");
+            let flags = pte.flags();
+            if !flags.contains(WRITE) && !flags.contains(Self::COW) {
+                return None;
+            }
+            unsafe { FRAME_REFS.share(pte.ppn().val()) };
+            Some((flags ^ (flags & WRITE)) | Self::COW)
         }
 
         fn drop_root(&mut self) {
-            todo!()
+            let root = self.0;
+            free_table(self, root, Sv39::MAX_LEVEL);
+            unsafe { FRAME_STATS.deallocate(true, 1) };
+            let ptr = root.as_ptr().cast::<u8>();
+            poison(ptr, 1 << Sv39::PAGE_BITS);
+            unsafe {
+                dealloc(
+                    ptr,
+                    Layout::from_size_align_unchecked(1 << Sv39::PAGE_BITS, 1 << Sv39::PAGE_BITS),
+                )
+            };
         }
     }
 
@@ -336,7 +909,10 @@ mod impls {
 
     #[cfg(target_pointer_width = "32")]
     impl Sv32Manager {
-        const OWNED: VmFlags<Sv32> = unsafe { VmFlags::from_raw(1 << 8) };
+        /// 页由地址空间分配，解除映射时释放。
+        pub const OWNED: VmFlags<Sv32> = unsafe { VmFlags::from_raw(1 << 8) };
+        /// 页与其他地址空间共享，写入前要复制，见 [`FrameRefs`](crate::cow::FrameRefs)。
+        pub const COW: VmFlags<Sv32> = unsafe { VmFlags::from_raw(1 << 9) };
 
         #[inline]
         fn page_alloc<T>(count: usize) -> *mut T {
@@ -354,6 +930,7 @@ mod impls {
     impl PageManager<Sv32> for Sv32Manager {
         #[inline]
         fn new_root() -> Self {
+            unsafe { FRAME_STATS.allocate(true, 1) };
             Self(NonNull::new(Self::page_alloc(1)).unwrap())
         }
 
@@ -384,16 +961,54 @@ mod impls {
 
         #[inline]
         fn allocate(&mut self, len: usize, flags: &mut VmFlags<Sv32>) -> NonNull<u8> {
+            // 中间页表只有 V 位，叶子页带有读、写、执行权限
+            unsafe { FRAME_STATS.allocate(!flags.is_leaf(), len) };
             *flags |= Self::OWNED;
             NonNull::new(Self::page_alloc(len)).unwrap()
         }
 
-        fn deallocate(&mut self, _pte: Pte<Sv32>, _len: usize) -> usize {
-            todo!()
+        /// 不属于地址空间的页（传送门、外部映射的内核区域）保持原样，返回 0。
+        /// 写时复制共享的页只减少持有者数，最后一个持有者才真正释放。
+        fn deallocate(&mut self, pte: Pte<Sv32>, len: usize) -> usize {
+            if !self.check_owned(pte) || unsafe { FRAME_REFS.release(pte.ppn().val()) } {
+                return 0;
+            }
+            unsafe { FRAME_STATS.deallocate(!pte.is_leaf(), len) };
+            let ptr = self.p_to_v::<u8>(pte.ppn()).as_ptr();
+            poison(ptr, len << Sv32::PAGE_BITS);
+            unsafe {
+                dealloc(
+                    ptr,
+                    Layout::from_size_align_unchecked(len << Sv32::PAGE_BITS, 1 << Sv32::PAGE_BITS),
+                )
+            };
+            len
+        }
+
+        /// 可写的页和已经共享的写时复制页增加一个持有者，去掉写权限并标记 [`COW`](Self::COW)。
+        /// 只读的页不共享，否则它被 `mprotect` 改为可写时无法与写时复制页区分。
+        fn share_cow(&mut self, pte: Pte<Sv32>) -> Option<VmFlags<Sv32>> {
+            const WRITE: VmFlags<Sv32> = VmFlags::build_from_str("W");
+            let flags = pte.flags();
+            if !flags.contains(WRITE) && !flags.contains(Self::COW) {
+                return None;
+            }
+            unsafe { FRAME_REFS.share(pte.ppn().val()) };
+            Some((flags ^ (flags & WRITE)) | Self::COW)
         }
 
         fn drop_root(&mut self) {
-            todo!()
+            let root = self.0;
+            free_table(self, root, Sv32::MAX_LEVEL);
+            unsafe { FRAME_STATS.deallocate(true, 1) };
+            let ptr = root.as_ptr().cast::<u8>();
+            poison(ptr, 1 << Sv32::PAGE_BITS);
+            unsafe {
+                dealloc(
+                    ptr,
+                    Layout::from_size_align_unchecked(1 << Sv32::PAGE_BITS, 1 << Sv32::PAGE_BITS),
+                )
+            };
         }
     }
 
@@ -405,6 +1020,26 @@ mod impls {
             #[allow(deprecated)]
             sbi_rt::legacy::console_putchar(c as _);
         }
+
+        fn put_debug_str(&self, s: &str) {
+            match crate::uart::DEBUG_UART {
+                Some(base) => s.bytes().for_each(|c| crate::uart::putchar(base, c)),
+                None => self.put_str(s),
+            }
+        }
+    }
+
+    /// 等待并读入控制台的一个字符。
+    ///
+    /// 传统 SBI 接口在没有输入时返回 -1；nobios 模式下由 M 态的 SBI 实现查询串口，同样如此。
+    #[allow(deprecated)]
+    fn console_getchar() -> u8 {
+        loop {
+            let c = sbi_rt::legacy::console_getchar();
+            if c != usize::MAX {
+                return c as u8;
+            }
+        }
     }
 
     pub struct SyscallContext;
@@ -416,26 +1051,63 @@ mod impls {
     type VmModeLocal = Sv32;
 
     impl IO for SyscallContext {
+        /// 从标准输入读入 `buf`，至少读到一个字节，读满 `count` 字节或读到换行为止。
+        ///
+        /// 缓冲区可能跨页，各页分别翻译，并且在读取之前全部检查，不会读走了输入才发现缓冲区不可写。
+        fn read(&self, caller: Caller, fd: usize, buf: usize, count: usize) -> isize {
+            const PAGE_MASK: usize = (1 << VmModeLocal::PAGE_BITS) - 1;
+            const WRITABLE: VmFlags<VmModeLocal> = VmFlags::build_from_str("W_V");
+            if fd != STDIN {
+                log::error!("unsupported fd: {fd}");
+                return -1;
+            }
+            let Some(end) = buf.checked_add(count) else {
+                return -1;
+            };
+            let process = unsafe { PROCESSES.get_mut(caller.entity) }.unwrap();
+            let mut pages = Vec::new();
+            let mut addr = buf;
+            while addr < end {
+                let ptr = loop {
+                    if let Some(ptr) = process
+                        .address_space
+                        .translate::<u8>(VAddr::new(addr), WRITABLE)
+                    {
+                        break ptr;
+                    }
+                    // 写时复制页或尚未分配的栈页，处理之后再翻译一次
+                    if !(process.break_cow(addr) || process.fill_lazy(addr)) {
+                        log::error!("ptr not writeable");
+                        return -1;
+                    }
+                };
+                let len = end.min((addr | PAGE_MASK) + 1) - addr;
+                pages.push(unsafe { core::slice::from_raw_parts_mut(ptr.as_ptr(), len) });
+                addr += len;
+            }
+            let mut len = 0;
+            for page in pages {
+                for byte in page {
+                    *byte = console_getchar();
+                    len += 1;
+                    if *byte == b'\n' {
+                        return len;
+                    }
+                }
+            }
+            len
+        }
+
         fn write(&self, caller: Caller, fd: usize, buf: usize, count: usize) -> isize {
             match fd {
                 STDOUT | STDDEBUG => {
-                    const READABLE: VmFlags<VmModeLocal> = VmFlags::build_from_str("RV");
-                    if let Some(ptr) = unsafe { PROCESSES.get_mut(caller.entity) }
-                        .unwrap()
-                        .address_space
-                        .translate(VAddr::new(buf), READABLE)
-                    {
-                        print_with_timestamp(unsafe {
-                            core::str::from_utf8_unchecked(core::slice::from_raw_parts(
-                                ptr.as_ptr(),
-                                count,
-                            ))
-                        });
-                        count as _
-                    } else {
+                    // 缓冲区可能跨页，拷贝出来一次输出，时间戳只打一次
+                    let Some(bytes) = copy_from_user(caller, buf, count) else {
                         log::error!("ptr not readable");
-                        -1
-                    }
+                        return -1;
+                    };
+                    print_with_timestamp(fd, unsafe { core::str::from_utf8_unchecked(&bytes) });
+                    count as _
                 }
                 _ => {
                     log::error!("unsupported fd: {fd}");
@@ -446,55 +1118,443 @@ mod impls {
     }
 
     impl Process for SyscallContext {
+        /// 返回退出码，由调度线程记录。
         #[inline]
-        fn exit(&self, _caller: Caller, _status: usize) -> isize {
+        fn exit(&self, _caller: Caller, status: usize) -> isize {
+            status as isize
+        }
+
+        /// 子进程排在队尾，返回它的进程号。进程号用完时返回 -1。
+        fn fork(&self, caller: Caller) -> isize {
+            let Some(child) = unsafe { &mut PROCESSES[caller.entity] }.fork() else {
+                log::error!("pid exhausted");
+                return -1;
+            };
+            let pid = child.pid;
+            unsafe { PROCESSES.push_back(child) };
+            pid.get() as isize
+        }
+
+        /// 创建运行名为 `path` 的应用程序的子进程，返回子进程号。
+        ///
+        /// 找不到应用程序、加载失败或进程号用完时返回 -1。
+        fn spawn(&self, caller: Caller, path: usize, count: usize) -> isize {
+            let Some(name) = copy_from_user(caller, path, count) else {
+                log::error!("ptr not readable");
+                return -1;
+            };
+            let Some(mut child) = crate::find_app(&name)
+                .and_then(|app| ElfFile::new(app).ok())
+                .and_then(crate::process::Process::new)
+            else {
+                log::error!("cannot spawn {}", String::from_utf8_lossy(&name));
+                return -1;
+            };
+            let current = unsafe { PROCESSES.get(caller.entity) }.unwrap();
+            let portal_idx = PROTAL_TRANSIT.index_in(VmModeLocal::MAX_LEVEL);
+            child.address_space.root()[portal_idx] = current.address_space.root()[portal_idx];
+            child.parent = Some(current.pid);
+            let pid = child.pid;
+            unsafe { PROCESSES.push_back(child) };
+            pid.get() as isize
+        }
+
+        /// 把当前进程换成名为 `path` 的应用程序，`argv` 是以空指针结尾的参数指针数组，可以为空。
+        ///
+        /// 成功时返回 0，调度线程不会把它写回新程序；找不到应用程序或参数不可读时返回 -1。
+        fn execv(&self, caller: Caller, path: usize, count: usize, argv: usize) -> isize {
+            const PTR_SIZE: usize = core::mem::size_of::<usize>();
+            const MAX_ARGS: usize = 32;
+            const MAX_ARG_LEN: usize = 256;
+            let Some(name) = copy_from_user(caller, path, count) else {
+                log::error!("ptr not readable");
+                return -1;
+            };
+            let Some(app) = crate::find_app(&name) else {
+                log::error!("unknown app: {}", String::from_utf8_lossy(&name));
+                return -1;
+            };
+            let mut args = Vec::new();
+            while argv != 0 {
+                let slot = argv + args.len() * PTR_SIZE;
+                let Some(ptr) = (unsafe { get_user::<usize>(caller, slot) }) else {
+                    return -1;
+                };
+                if ptr == 0 {
+                    break;
+                }
+                if args.len() == MAX_ARGS {
+                    return -1;
+                }
+                let Some(arg) = read_user_cstr(caller, ptr, MAX_ARG_LEN) else {
+                    log::error!("bad argument string");
+                    return -1;
+                };
+                args.push(arg);
+            }
+            let args = args.iter().map(String::as_bytes).collect::<Vec<_>>();
+            let process = unsafe { PROCESSES.get_mut(caller.entity) }.unwrap();
+            if process.exec(ElfFile::new(app).unwrap(), &args) {
+                0
+            } else {
+                -1
+            }
+        }
+
+        #[inline]
+        fn getpid(&self, caller: Caller) -> isize {
+            unsafe { PROCESSES[caller.entity].pid.get() as _ }
+        }
+
+        /// 没有父进程时返回 0。
+        #[inline]
+        fn getppid(&self, caller: Caller) -> isize {
+            unsafe { PROCESSES[caller.entity].parent }.map_or(0, |parent| parent.get() as _)
+        }
+
+        /// 回收已退出的子进程 `pid`，把退出码写到 `exit_code_ptr`，返回它的进程号。
+        ///
+        /// `pid` 为 -1 时回收任意一个已退出的子进程。
+        /// 目标还在运行时返回 -2，由用户库让出之后重试；没有这样的子进程时返回 -1。
+        fn wait(&self, caller: Caller, pid: isize, exit_code_ptr: usize) -> isize {
+            let current = unsafe { PROCESSES.get(caller.entity) }.unwrap();
+            let matches = |child: pid::Pid| pid == -1 || child.get() == pid as usize;
+            let zombies = unsafe { &mut ZOMBIES };
+            let dead = zombies
+                .iter()
+                .find(|(&child, &(parent, _))| parent == current.pid && matches(child))
+                .map(|(&child, &(_, exit_code))| (child, exit_code));
+            let Some((dead, exit_code)) = dead else {
+                let running = unsafe { PROCESSES.iter() }
+                    .any(|p| p.parent == Some(current.pid) && matches(p.pid));
+                return if running { -2 } else { -1 };
+            };
+            if exit_code_ptr != 0 && !unsafe { put_user(caller, exit_code_ptr, exit_code as i32) } {
+                log::error!("ptr not writeable");
+                return -1;
+            }
+            zombies.remove(&dead);
+            pid::free(dead);
+            dead.get() as isize
+        }
+
+        fn trapstats(&self, caller: Caller, buf: usize, len: usize) -> isize {
+            let out = alloc::format!("{}", unsafe { &TRAP_STATS });
+            if out.len() <= len && !copy_to_user(caller, buf, out.as_bytes()) {
+                log::error!("ptr not writeable");
+                return -1;
+            }
+            out.len() as _
+        }
+
+        fn sysinfo(&self, caller: Caller, info: usize) -> isize {
+            let info_value = SysInfo {
+                uptime: monotonic_time_ns() / 1_000_000_000,
+                procs: unsafe { PROCESSES.len() },
+                table_frames: unsafe { FRAME_STATS.table() },
+                data_frames: unsafe { FRAME_STATS.data() },
+            };
+            if unsafe { put_user(caller, info, info_value) } {
+                0
+            } else {
+                log::error!("ptr not writeable");
+                -1
+            }
+        }
+
+        fn reboot(&self, _caller: Caller, magic1: usize, magic2: usize, cmd: usize) -> isize {
+            if magic1 != REBOOT_MAGIC1 || magic2 != REBOOT_MAGIC2 {
+                return -1;
+            }
+            rcore_console::flush();
+            let ret = match cmd {
+                REBOOT_CMD_POWER_OFF | REBOOT_CMD_HALT => {
+                    sbi_rt::system_reset(sbi_rt::Shutdown, sbi_rt::NoReason)
+                }
+                REBOOT_CMD_RESTART => sbi_rt::system_reset(sbi_rt::ColdReboot, sbi_rt::NoReason),
+                REBOOT_CMD_RESTART2 => sbi_rt::system_reset(sbi_rt::WarmReboot, sbi_rt::NoReason),
+                _ => return -1,
+            };
+            log::error!("system reset failed, error = {}", ret.error as isize);
+            -1
+        }
+    }
+
+    /// 把 `PROT_*` 的组合转换为页表项的读、写、执行位。
+    ///
+    /// 不允许 `PROT_NONE`（清空 RWX 的页表项会被当作子页表），也不允许同时可写可执行；
+    /// 可写页总是可读，因为 RISC-V 保留了只写的组合。
+    fn prot_flags(prot: i32) -> Option<VmFlags<VmModeLocal>> {
+        if prot & !(PROT_READ | PROT_WRITE | PROT_EXEC) != 0
+            || prot == PROT_NONE
+            || prot & (PROT_WRITE | PROT_EXEC) == PROT_WRITE | PROT_EXEC
+        {
+            return None;
+        }
+        let mut flags = VmFlags::ZERO;
+        if prot & (PROT_READ | PROT_WRITE) != 0 {
+            flags |= VmFlags::build_from_str("R");
+        }
+        if prot & PROT_WRITE != 0 {
+            flags |= VmFlags::build_from_str("W");
+        }
+        if prot & PROT_EXEC != 0 {
+            flags |= VmFlags::build_from_str("X");
+        }
+        Some(flags)
+    }
+
+    impl Memory for SyscallContext {
+        /// 只支持私有的匿名映射，`fd` 和 `offset` 被忽略。
+        ///
+        /// `addr` 必须按页对齐，长度向上取整到页。`addr` 为 0 时由内核选择地址，
+        /// 否则只尝试这个地址，与已有映射重叠时失败，`MAP_FIXED` 也不会替换已有映射。
+        fn mmap(
+            &self,
+            caller: Caller,
+            addr: usize,
+            length: usize,
+            prot: i32,
+            flags: i32,
+            _fd: i32,
+            _offset: usize,
+        ) -> isize {
+            const PAGE_MASK: usize = (1 << VmModeLocal::PAGE_BITS) - 1;
+            const USER: VmFlags<VmModeLocal> = VmFlags::build_from_str("U___V");
+            if addr & PAGE_MASK != 0 || length == 0 {
+                return -1;
+            }
+            if flags & !MAP_FIXED != MAP_PRIVATE | MAP_ANONYMOUS {
+                return -1;
+            }
+            let Some(pages) = length.checked_add(PAGE_MASK) else {
+                return -1;
+            };
+            let Some(prot) = prot_flags(prot) else {
+                return -1;
+            };
+            let vaddr = VAddr::<VmModeLocal>::new(addr);
+            if vaddr.val() != addr || (addr == 0 && flags & MAP_FIXED != 0) {
+                return -1;
+            }
+            let hint = (addr != 0).then(|| vaddr.floor());
+            let process = unsafe { PROCESSES.get_mut(caller.entity) }.unwrap();
+            match process.mmap(hint, pages >> VmModeLocal::PAGE_BITS, prot | USER) {
+                Some(vpn) => vpn.base().val() as _,
+                None => -1,
+            }
+        }
+
+        /// 范围必须按页对齐，长度向上取整到页，其中没有映射的页被跳过。
+        fn munmap(&self, caller: Caller, addr: usize, length: usize) -> isize {
+            const PAGE_MASK: usize = (1 << VmModeLocal::PAGE_BITS) - 1;
+            if addr & PAGE_MASK != 0 || length == 0 {
+                return -1;
+            }
+            let Some(end) = length
+                .checked_add(PAGE_MASK)
+                .and_then(|length| addr.checked_add(length & !PAGE_MASK))
+            else {
+                return -1;
+            };
+            if VAddr::<VmModeLocal>::new(end).val() != end {
+                return -1;
+            }
+            let range = VAddr::<VmModeLocal>::new(addr).floor()..VAddr::new(end).floor();
+            let process = unsafe { PROCESSES.get_mut(caller.entity) }.unwrap();
+            match process.munmap(range) {
+                Some(_) => 0,
+                None => -1,
+            }
+        }
+
+        /// 移动程序断点，返回移动后的断点。`addr` 为 0 或移动失败时断点不变。
+        fn brk(&self, caller: Caller, addr: usize) -> isize {
+            let process = unsafe { PROCESSES.get_mut(caller.entity) }.unwrap();
+            if addr != 0 {
+                process.set_brk(addr);
+            }
+            process.brk() as _
+        }
+
+        fn madvise(&self, caller: Caller, addr: usize, length: usize, advice: usize) -> isize {
+            const PAGE_MASK: usize = (1 << VmModeLocal::PAGE_BITS) - 1;
+            const USER: VmFlags<VmModeLocal> = VmFlags::build_from_str("U___V");
+            let Some(end) = addr.checked_add(length) else {
+                return -1;
+            };
+            if addr & PAGE_MASK != 0 || length & PAGE_MASK != 0 {
+                return -1;
+            }
+            if advice != MADV_DONTNEED {
+                return 0;
+            }
+            let process = unsafe { PROCESSES.get_mut(caller.entity) }.unwrap();
+            let range = VAddr::<VmModeLocal>::new(addr).floor()..VAddr::new(end).floor();
+            // 只允许释放用户页，不能拆掉传送门之类的内核映射
+            let mut vpn = range.start;
+            while vpn < range.end {
+                if process.address_space.find_mapped(vpn..vpn + 1).is_some()
+                    && process
+                        .address_space
+                        .translate::<u8>(vpn.base(), USER)
+                        .is_none()
+                {
+                    return -1;
+                }
+                vpn += 1;
+            }
+            process.address_space.unmap(range);
+            0
+        }
+
+        /// 修改用户页的读、写、执行权限。
+        ///
+        /// 范围必须按页对齐且全部是已映射的用户页，`prot` 的限制见 [`prot_flags`]。
+        /// 不属于地址空间的页可能与其他地址空间共享，只能减少权限。
+        /// 只改写权限位，物理页号和 `OWNED`、`G` 等其他位不变，改写之后逐页刷新快表。
+        fn mprotect(&self, caller: Caller, addr: usize, length: usize, prot: i32) -> isize {
+            const PAGE_MASK: usize = (1 << VmModeLocal::PAGE_BITS) - 1;
+            const USER: VmFlags<VmModeLocal> = VmFlags::build_from_str("U___V");
+            const MASK: VmFlags<VmModeLocal> = VmFlags::build_from_str("XWR");
+            let Some(end) = addr.checked_add(length) else {
+                return -1;
+            };
+            if addr & PAGE_MASK != 0 || length & PAGE_MASK != 0 {
+                return -1;
+            }
+            let Some(flags) = prot_flags(prot) else {
+                return -1;
+            };
+            let process = unsafe { PROCESSES.get_mut(caller.entity) }.unwrap();
+            let range = VAddr::<VmModeLocal>::new(addr).floor()..VAddr::new(end).floor();
+            let mut vpn = range.start;
+            while vpn < range.end {
+                let Some(pte) = process.address_space.pte(vpn) else {
+                    return -1;
+                };
+                let old = pte.flags();
+                if !old.contains(USER)
+                    || (!old.contains(crate::VmManager::OWNED) && !(old & MASK).contains(flags))
+                {
+                    return -1;
+                }
+                vpn += 1;
+            }
+            // 先分开写时复制页，否则共享的物理页可能被改成可写
+            let mut vpn = range.start;
+            while vpn < range.end {
+                process.break_cow(vpn.base().val());
+                vpn += 1;
+            }
+            let Ok(()) = process.address_space.protect(range.clone(), MASK, flags) else {
+                return -1;
+            };
+            let mut vpn = range.start;
+            while vpn < range.end {
+                unsafe { core::arch::asm!("sfence.vma {}, zero", in(reg) vpn.base().val()) };
+                vpn += 1;
+            }
             0
         }
     }
 
     impl Scheduling for SyscallContext {
-        #[inline]
-        fn sched_yield(&self, _caller: Caller) -> isize {
-            0
+        /// 调度线程在系统调用返回后把当前进程轮转到队尾。
+        ///
+        /// 有其他进程不在睡眠、会因此被调度时返回 1，否则返回 0。
+        fn sched_yield(&self, caller: Caller) -> isize {
+            let now = timer::now();
+            unsafe { PROCESSES.iter() }
+                .enumerate()
+                .any(|(i, p)| i != caller.entity && now >= p.wake_at) as _
         }
     }
 
     impl Clock for SyscallContext {
-        #[inline]
+        /// 支持 `CLOCK_MONOTONIC` 和 `CLOCK_REALTIME`，其他时钟返回 -1。
+        ///
+        /// `CLOCK_REALTIME` 是启动时固定下来的 Unix 时间加上单调时钟。
         fn clock_gettime(&self, caller: Caller, clock_id: ClockId, tp: usize) -> isize {
-            const WRITABLE: VmFlags<VmModeLocal> = VmFlags::build_from_str("W_V");
+            let mut ts = TimeSpec::from_nanosecond(monotonic_time_ns());
             match clock_id {
-                ClockId::CLOCK_MONOTONIC => {
-                    if let Some(mut ptr) = unsafe { PROCESSES.get(caller.entity) }
-                        .unwrap()
-                        .address_space
-                        .translate(VAddr::new(tp), WRITABLE)
-                    {
-                        let time = monotonic_time_ns();
-                        *unsafe { ptr.as_mut() } = TimeSpec {
-                            tv_sec: time / 1_000_000_000,
-                            tv_nsec: time % 1_000_000_000,
-                        };
-                        0
-                    } else {
-                        log::error!("ptr not readable");
-                        -1
-                    }
-                }
-                _ => -1,
+                ClockId::CLOCK_MONOTONIC => {}
+                ClockId::CLOCK_REALTIME => ts.tv_sec += BOOT_EPOCH.load(Ordering::Relaxed),
+                _ => return -1,
+            }
+            // `tp` 不一定对齐，结构体可能跨页
+            if unsafe { put_user(caller, tp, ts) } {
+                0
+            } else {
+                log::error!("ptr not writeable");
+                -1
+            }
+        }
+
+        /// 按单调时钟睡眠 `req` 那么长的时间。
+        ///
+        /// 唤醒时刻之前调度线程不会选中这个进程，每次调度都重新比较唤醒时刻，不会忙等。
+        #[inline]
+        fn nanosleep(&self, caller: Caller, req: usize, rem: usize) -> isize {
+            self.clock_nanosleep(caller, ClockId::CLOCK_MONOTONIC, 0, req, rem)
+        }
+
+        fn clock_nanosleep(
+            &self,
+            caller: Caller,
+            clock_id: ClockId,
+            flags: usize,
+            req: usize,
+            _rem: usize,
+        ) -> isize {
+            if clock_id != ClockId::CLOCK_MONOTONIC {
+                return -1;
             }
+            let Some(req) = (unsafe { get_user::<TimeSpec>(caller, req) }) else {
+                log::error!("ptr not readable");
+                return -1;
+            };
+            // 绝对时间直接换算成唤醒时刻，相对时长从当前时刻起算；
+            // 睡眠不会被提前打断，所以不需要写回 `rem`。
+            // 时间不合法或者唤醒时刻超出 64 位计数的范围时拒绝。
+            let wake_at = req.to_nanosecond().map(timer::ns_to_ticks).and_then(|req| {
+                if flags & TIMER_ABSTIME != 0 {
+                    Some(req)
+                } else {
+                    timer::now().checked_add(req)
+                }
+            });
+            let Some(wake_at) = wake_at else {
+                log::error!("invalid sleep time");
+                return -1;
+            };
+            let process = unsafe { PROCESSES.get_mut(caller.entity) }.unwrap();
+            process.wake_at = wake_at;
+            unsafe { TIMERS.push(process.wake_at, process.pid) };
+            0
         }
     }
 
     static LINE_START: AtomicBool = AtomicBool::new(true);
 
+    /// 启动时刻的 Unix 时间（秒），`CLOCK_REALTIME` 是它加上单调时钟。
+    ///
+    /// 没有模拟 RTC，这个值只在启动时设置一次，之后不再校准。
+    static BOOT_EPOCH: AtomicUsize = AtomicUsize::new(0);
+
+    /// 设置启动时刻的 Unix 时间（秒）。
+    #[inline]
+    pub(crate) fn set_boot_epoch(secs: usize) {
+        BOOT_EPOCH.store(secs, Ordering::Relaxed);
+    }
+
     #[inline]
     pub(crate) fn monotonic_time_ms() -> usize {
         monotonic_time_ns() / 1_000_000
     }
 
     #[inline]
-    fn monotonic_time_ns() -> usize {
+    pub(crate) fn monotonic_time_ns() -> usize {
         #[cfg(target_pointer_width = "64")]
         {
             (time::read64() as u64 * 10000 / 125) as usize
@@ -506,14 +1566,23 @@ mod impls {
         }
     }
 
-    fn print_with_timestamp(s: &str) {
+    /// 输出用户程序写到 `fd` 的内容，`STDDEBUG` 走调试通道。
+    fn print_with_timestamp(fd: usize, s: &str) {
+        let out = |args: core::fmt::Arguments| match fd {
+            STDDEBUG => {
+                eprint!("{args}");
+            }
+            _ => {
+                print!("{args}");
+            }
+        };
         let mut at_line_start = LINE_START.load(Ordering::Relaxed);
         for segment in s.split_inclusive('\n') {
             if at_line_start {
                 let ts_ms = monotonic_time_ms();
-                print!("[{ts_ms:>5} ms] ");
+                out(format_args!("[{ts_ms:>5} ms] "));
             }
-            print!("{segment}");
+            out(format_args!("{segment}"));
             at_line_start = segment.ends_with('\n');
         }
         LINE_START.store(at_line_start, Ordering::Relaxed);
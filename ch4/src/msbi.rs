@@ -3,6 +3,8 @@
 //! This module provides a minimal SBI implementation for M-Mode,
 //! handling ecalls from S-Mode when running without external BIOS.
 
+use core::sync::atomic::{AtomicUsize, Ordering};
+
 /// QEMU virt UART base address
 const UART_BASE: usize = 0x1000_0000;
 
@@ -54,8 +56,70 @@ mod eid {
     pub const BASE: usize = 0x10;
     pub const TIMER: usize = 0x54494D45;
     pub const SRST: usize = 0x53525354;
+    pub const SUSP: usize = 0x53555350;
+    pub const IPI: usize = 0x735049;
+    pub const HSM: usize = 0x48534D;
+}
+
+/// SRST reset types and reasons
+mod srst {
+    pub const SHUTDOWN: usize = 0;
+    pub const COLD_REBOOT: usize = 1;
+    pub const WARM_REBOOT: usize = 2;
+    /// Reset types from here on are vendor or platform specific
+    pub const TYPE_VENDOR: usize = 0xF000_0000;
+
+    pub const NO_REASON: usize = 0;
+    pub const SYSTEM_FAILURE: usize = 1;
+    /// Reset reasons from here on are SBI implementation or vendor specific
+    pub const REASON_IMPL: usize = 0xE000_0000;
+}
+
+/// SUSP sleep types
+mod susp {
+    pub const SUSPEND_TO_RAM: usize = 0;
+    /// Sleep types from here on are platform specific
+    pub const TYPE_PLATFORM: usize = 0x8000_0000;
+}
+
+/// HSM hart states
+mod hsm {
+    pub const STARTED: usize = 0;
+    pub const STOPPED: usize = 1;
+    pub const START_PENDING: usize = 2;
+    /// Not a spec state: the hart never reached the park loop, so it does
+    /// not exist on this machine
+    pub const ABSENT: usize = usize::MAX;
 }
 
+/// Number of harts the hart masks and HSM calls may name
+///
+/// QEMU virt is run with at most this many harts (`-smp`), numbered from 0.
+/// `_m_start` halts any hart with a larger ID, so keep the two in sync.
+const HART_COUNT: usize = 8;
+
+/// HSM state of each hart
+///
+/// Hart 0 boots the kernel. The others mark themselves STOPPED when they
+/// reach the park loop in `_m_start`.
+static HART_STATE: [AtomicUsize; HART_COUNT] = {
+    let mut state = [const { AtomicUsize::new(hsm::ABSENT) }; HART_COUNT];
+    state[0] = AtomicUsize::new(hsm::STARTED);
+    state
+};
+/// Entry point passed to `hart_start`, 0 until the hart is asked to start
+static HART_START_ADDR: [AtomicUsize; HART_COUNT] = [const { AtomicUsize::new(0) }; HART_COUNT];
+/// `opaque` passed to `hart_start`
+static HART_OPAQUE: [AtomicUsize; HART_COUNT] = [const { AtomicUsize::new(0) }; HART_COUNT];
+
+/// CLINT software interrupt registers
+///
+/// Each hart has a 32-bit MSIP register at `CLINT_MSIP + 4 * hartid`:
+/// hart 0 at 0x200_0000, hart 1 at 0x200_0004, ..., hart 7 at 0x200_001C.
+/// Writing 1 raises a machine software interrupt on that hart, writing 0
+/// clears it. `mtimecmp` follows at 0x200_4000.
+const CLINT_MSIP: usize = 0x200_0000;
+
 /// SBI error codes
 #[allow(dead_code)]
 mod error {
@@ -63,6 +127,8 @@ mod error {
     pub const ERR_FAILED: isize = -1;
     pub const ERR_NOT_SUPPORTED: isize = -2;
     pub const ERR_INVALID_PARAM: isize = -3;
+    pub const ERR_INVALID_ADDRESS: isize = -5;
+    pub const ERR_ALREADY_AVAILABLE: isize = -6;
 }
 
 /// SBI return value structure
@@ -86,6 +152,27 @@ impl SbiRet {
             value: 0,
         }
     }
+
+    fn invalid_param() -> Self {
+        SbiRet {
+            error: error::ERR_INVALID_PARAM,
+            value: 0,
+        }
+    }
+
+    fn invalid_address() -> Self {
+        SbiRet {
+            error: error::ERR_INVALID_ADDRESS,
+            value: 0,
+        }
+    }
+
+    fn already_available() -> Self {
+        SbiRet {
+            error: error::ERR_ALREADY_AVAILABLE,
+            value: 0,
+        }
+    }
 }
 
 /// Handle legacy console putchar (EID 0x01)
@@ -103,16 +190,33 @@ fn handle_console_getchar() -> SbiRet {
 }
 
 /// Handle system reset (SRST extension)
-fn handle_system_reset(_reset_type: usize, reset_reason: usize) -> SbiRet {
-    // For QEMU, we use the test device to shutdown
+///
+/// Shutdown writes the pass/fail code to the QEMU test finisher. Cold and warm
+/// reboot write its reset code instead, so QEMU resets every hart and reloads
+/// the images, and the boot sequence starts again at `_m_start`. Jumping back
+/// to `_m_start` directly is not enough for a warm reboot, since nothing would
+/// restore the kernel's `.data`.
+fn handle_system_reset(reset_type: usize, reset_reason: usize) -> SbiRet {
+    // For QEMU, we use the test device to shutdown or reset
     const VIRT_TEST: usize = 0x100000;
     const FINISHER_PASS: u32 = 0x5555;
     const FINISHER_FAIL: u32 = 0x3333;
+    const FINISHER_RESET: u32 = 0x7777;
 
-    let code = if reset_reason == 0 {
-        FINISHER_PASS
-    } else {
-        FINISHER_FAIL
+    // Both arguments are 32-bit values, anything else is reserved
+    match reset_reason {
+        srst::NO_REASON | srst::SYSTEM_FAILURE => {}
+        r if (srst::REASON_IMPL..=u32::MAX as usize).contains(&r) => {}
+        _ => return SbiRet::invalid_param(),
+    }
+    let code = match reset_type {
+        srst::SHUTDOWN if reset_reason == srst::NO_REASON => FINISHER_PASS,
+        srst::SHUTDOWN => FINISHER_FAIL,
+        srst::COLD_REBOOT | srst::WARM_REBOOT => FINISHER_RESET,
+        t if (srst::TYPE_VENDOR..=u32::MAX as usize).contains(&t) => {
+            return SbiRet::not_supported()
+        }
+        _ => return SbiRet::invalid_param(),
     };
 
     unsafe {
@@ -124,24 +228,269 @@ fn handle_system_reset(_reset_type: usize, reset_reason: usize) -> SbiRet {
     loop {}
 }
 
+/// Handle system suspend (SUSP extension)
+///
+/// Only suspend-to-RAM on a single hart. QEMU keeps RAM and devices powered,
+/// so the hart just waits in WFI until an interrupt enabled in `mie` becomes
+/// pending. It then resumes at `resume_addr` in S-Mode rather than returning
+/// to the caller, as the spec requires: `satp` and `sstatus.SIE` are cleared,
+/// a0 holds the hart ID and a1 holds `opaque`. Other registers are undefined.
+fn handle_system_suspend(sleep_type: usize, resume_addr: usize, opaque: usize) -> SbiRet {
+    match sleep_type {
+        susp::SUSPEND_TO_RAM => {}
+        t if (susp::TYPE_PLATFORM..=u32::MAX as usize).contains(&t) => {
+            return SbiRet::not_supported()
+        }
+        _ => return SbiRet::invalid_param(),
+    }
+
+    unsafe {
+        loop {
+            core::arch::asm!("wfi");
+            let (mip, mie): (usize, usize);
+            core::arch::asm!("csrr {}, mip", out(reg) mip);
+            core::arch::asm!("csrr {}, mie", out(reg) mie);
+            if mip & mie != 0 {
+                break;
+            }
+        }
+    }
+    resume_at(resume_addr, opaque)
+}
+
+/// Make the current ecall return to `addr` in S-Mode instead of the caller
+///
+/// `satp` and `sstatus.SIE` are cleared, and the returned value puts the hart
+/// ID in a0 and `opaque` in a1, as suspend resume and hart start require.
+fn resume_at(addr: usize, opaque: usize) -> SbiRet {
+    unsafe {
+        core::arch::asm!(
+            "csrw satp, zero",
+            "sfence.vma",
+            "csrc sstatus, {sie}",
+            // m_trap_vector adds 4 to mepc before mret
+            "csrw mepc, {mepc}",
+            sie = in(reg) 1 << 1,
+            mepc = in(reg) addr.wrapping_sub(4),
+        );
+    }
+    // m_trap_vector returns error and value in a0 and a1
+    SbiRet {
+        error: hart_id() as isize,
+        value: opaque,
+    }
+}
+
+/// ID of the current hart
+#[inline]
+fn hart_id() -> usize {
+    let hartid: usize;
+    unsafe { core::arch::asm!("csrr {}, mhartid", out(reg) hartid) };
+    hartid
+}
+
+/// Handle IPI extension (EID 0x735049)
+///
+/// Raises a supervisor software interrupt on every hart in the mask. The
+/// calling hart just sets its own `mip.SSIP`. Other harts get their MSIP bit
+/// set in the CLINT. A hart running in S-Mode keeps `mie.MSIE` set, so it
+/// traps to M-Mode, where [`m_interrupt_handler`] clears MSIP and raises
+/// SSIP. A STOPPED hart only wakes up and goes back to waiting. The whole
+/// mask is checked before any hart is signalled, so an invalid mask has no
+/// effect.
+fn handle_send_ipi(hart_mask: usize, hart_mask_base: usize) -> SbiRet {
+    // A base of -1 means every hart, and hart_mask is ignored
+    let (hart_mask, hart_mask_base) = if hart_mask_base == usize::MAX {
+        ((1 << HART_COUNT) - 1, 0)
+    } else {
+        (hart_mask, hart_mask_base)
+    };
+    let harts = || {
+        (0..usize::BITS as usize)
+            .filter(move |bit| hart_mask & (1 << bit) != 0)
+            .map(move |bit| hart_mask_base.checked_add(bit))
+    };
+    if !harts().all(|hartid| hartid.is_some_and(|id| id < HART_COUNT)) {
+        return SbiRet::invalid_param();
+    }
+
+    let current = hart_id();
+    for hartid in harts().flatten() {
+        if hartid == current {
+            unsafe { core::arch::asm!("csrs mip, {}", in(reg) 1 << 1) };
+        } else {
+            unsafe { ((CLINT_MSIP + 4 * hartid) as *mut u32).write_volatile(1) };
+        }
+    }
+    SbiRet::success(0)
+}
+
+/// Handle hart start (HSM extension)
+///
+/// Only a parked hart can be started. The entry point is stored before MSIP
+/// is raised, and the hart enters S-Mode at `start_addr` from its park loop.
+fn handle_hart_start(hartid: usize, start_addr: usize, opaque: usize) -> SbiRet {
+    let Some(state) = HART_STATE.get(hartid) else {
+        return SbiRet::invalid_param();
+    };
+    // Address 0 marks "no start request" in HART_START_ADDR
+    if start_addr == 0 {
+        return SbiRet::invalid_address();
+    }
+    match state.compare_exchange(
+        hsm::STOPPED,
+        hsm::START_PENDING,
+        Ordering::AcqRel,
+        Ordering::Acquire,
+    ) {
+        Ok(_) => {}
+        Err(hsm::ABSENT) => return SbiRet::invalid_param(),
+        Err(_) => return SbiRet::already_available(),
+    }
+    HART_OPAQUE[hartid].store(opaque, Ordering::Relaxed);
+    HART_START_ADDR[hartid].store(start_addr, Ordering::Release);
+    unsafe { ((CLINT_MSIP + 4 * hartid) as *mut u32).write_volatile(1) };
+    SbiRet::success(0)
+}
+
+/// Handle hart stop (HSM extension)
+///
+/// The calling hart becomes STOPPED and waits in WFI, like a hart parked at
+/// boot, until `hart_start` names it again. It then enters S-Mode at the new
+/// start address, so this never returns to the caller.
+fn handle_hart_stop() -> SbiRet {
+    let hartid = hart_id();
+    m_hart_park(hartid);
+    // MSIE stays set for the restarted hart, see handle_send_ipi
+    let entry = unsafe {
+        core::arch::asm!("csrs mie, {}", in(reg) 1 << 3);
+        loop {
+            core::arch::asm!("wfi");
+            let entry = m_hart_wakeup(hartid);
+            if entry.start_addr != 0 {
+                break entry;
+            }
+        }
+    };
+    resume_at(entry.start_addr, entry.opaque)
+}
+
+/// Handle hart get status (HSM extension)
+fn handle_hart_get_status(hartid: usize) -> SbiRet {
+    let Some(state) = HART_STATE.get(hartid) else {
+        return SbiRet::invalid_param();
+    };
+    match state.load(Ordering::Acquire) {
+        hsm::ABSENT => SbiRet::invalid_param(),
+        state => SbiRet::success(state),
+    }
+}
+
+/// Where a started hart enters S-Mode
+#[repr(C)]
+pub struct HartEntry {
+    start_addr: usize,
+    opaque: usize,
+}
+
+/// Mark hart `hartid` STOPPED, called when it enters the park loop
+#[unsafe(no_mangle)]
+pub extern "C" fn m_hart_park(hartid: usize) {
+    HART_STATE[hartid].store(hsm::STOPPED, Ordering::Release);
+}
+
+/// Called from the park loop each time a parked hart wakes up
+///
+/// Clears the hart's MSIP. If `hart_start` has named this hart, marks it
+/// STARTED and returns its entry point, otherwise returns `start_addr` 0 and
+/// the hart keeps waiting.
+#[unsafe(no_mangle)]
+pub extern "C" fn m_hart_wakeup(hartid: usize) -> HartEntry {
+    unsafe { ((CLINT_MSIP + 4 * hartid) as *mut u32).write_volatile(0) };
+    let start_addr = HART_START_ADDR[hartid].swap(0, Ordering::Acquire);
+    if start_addr != 0 {
+        HART_STATE[hartid].store(hsm::STARTED, Ordering::Release);
+    }
+    HartEntry {
+        start_addr,
+        opaque: HART_OPAQUE[hartid].load(Ordering::Relaxed),
+    }
+}
+
+/// Called from m_trap_vector for an interrupt taken from S-Mode
+///
+/// Only MSIE is enabled, so this is an IPI from `send_ipi` on another hart.
+/// Clear this hart's MSIP and pass the interrupt on to S-Mode as SSIP.
+#[unsafe(no_mangle)]
+pub extern "C" fn m_interrupt_handler() {
+    const MACHINE_SOFT: usize = 3;
+    let mcause: usize;
+    unsafe { core::arch::asm!("csrr {}, mcause", out(reg) mcause) };
+    if mcause & (usize::MAX >> 1) == MACHINE_SOFT {
+        unsafe {
+            ((CLINT_MSIP + 4 * hart_id()) as *mut u32).write_volatile(0);
+            core::arch::asm!("csrs mip, {}", in(reg) 1 << 1);
+        }
+    }
+}
+
 /// Handle legacy shutdown (EID 0x08)
 fn handle_legacy_shutdown() -> SbiRet {
     handle_system_reset(0, 0)
 }
 
+/// SBI specification version implemented here.
+///
+/// SRST first appeared in SBI 0.3 and SUSP in SBI 2.0, so bump this when
+/// adding newer extensions.
+const SPEC_VERSION: usize = spec_version(2, 0);
+/// Implementation ID returned by `get_impl_id`: ASCII "rCor".
+///
+/// Registered IDs are small numbers (0 = BBL, 1 = OpenSBI, 4 = RustSBI, ...),
+/// so this stays clear of them.
+const IMPL_ID: usize = 0x7243_6f72;
+/// Implementation version returned by `get_impl_version`, bumped whenever the
+/// behavior visible to S-Mode changes.
+const IMPL_VERSION: usize = 5;
+
+/// Encode a version in the `get_spec_version` layout: major in bits [30:24],
+/// minor in bits [23:0].
+const fn spec_version(major: usize, minor: usize) -> usize {
+    (major << 24) | minor
+}
+
 /// Handle SBI base extension (EID 0x10)
-fn handle_base(fid: usize) -> SbiRet {
+fn handle_base(fid: usize, extension: usize) -> SbiRet {
+    macro_rules! csr {
+        ($name:literal) => {{
+            let value: usize;
+            unsafe { core::arch::asm!(concat!("csrr {}, ", $name), out(reg) value) };
+            value
+        }};
+    }
     match fid {
-        0 => SbiRet::success(2), // get_spec_version: SBI 0.2
-        1 => SbiRet::success(0), // get_impl_id: custom
-        2 => SbiRet::success(1), // get_impl_version
+        0 => SbiRet::success(SPEC_VERSION),
+        1 => SbiRet::success(IMPL_ID),
+        2 => SbiRet::success(IMPL_VERSION),
         3 => {
-            // probe_extension: check if extension is supported
-            SbiRet::success(1) // We support legacy extensions
+            // probe_extension: only report what m_trap_handler dispatches
+            let supported = matches!(
+                extension,
+                eid::LEGACY_CONSOLE_PUTCHAR
+                    | eid::LEGACY_CONSOLE_GETCHAR
+                    | eid::LEGACY_SHUTDOWN
+                    | eid::BASE
+                    | eid::TIMER
+                    | eid::SRST
+                    | eid::SUSP
+                    | eid::IPI
+                    | eid::HSM
+            );
+            SbiRet::success(supported as usize)
         }
-        4 => SbiRet::success(0), // get_mvendorid
-        5 => SbiRet::success(0), // get_marchid
-        6 => SbiRet::success(0), // get_mimpid
+        4 => SbiRet::success(csr!("mvendorid")),
+        5 => SbiRet::success(csr!("marchid")),
+        6 => SbiRet::success(csr!("mimpid")),
         _ => SbiRet::not_supported(),
     }
 }
@@ -193,7 +542,7 @@ fn handle_timer(time: u64) -> SbiRet {
 pub extern "C" fn m_trap_handler(
     a0: usize,
     a1: usize,
-    _a2: usize,
+    a2: usize,
     _a3: usize,
     _a4: usize,
     _a5: usize,
@@ -220,7 +569,7 @@ pub extern "C" fn m_trap_handler(
         eid::LEGACY_SHUTDOWN => handle_legacy_shutdown(),
 
         // Base extension (SBI v0.2+)
-        eid::BASE => handle_base(fid),
+        eid::BASE => handle_base(fid, a0),
 
         // Timer extension
         eid::TIMER => handle_timer(a0 as u64 | ((a1 as u64) << 32)),
@@ -234,6 +583,32 @@ pub extern "C" fn m_trap_handler(
             }
         }
 
+        // System Suspend extension
+        eid::SUSP => {
+            if fid == 0 {
+                handle_system_suspend(a0, a1, a2)
+            } else {
+                SbiRet::not_supported()
+            }
+        }
+
+        // IPI extension
+        eid::IPI => {
+            if fid == 0 {
+                handle_send_ipi(a0, a1)
+            } else {
+                SbiRet::not_supported()
+            }
+        }
+
+        // Hart State Management extension
+        eid::HSM => match fid {
+            0 => handle_hart_start(a0, a1, a2),
+            1 => handle_hart_stop(),
+            2 => handle_hart_get_status(a0),
+            _ => SbiRet::not_supported(),
+        },
+
         // Unsupported extensions
         _ => SbiRet::not_supported(),
     }
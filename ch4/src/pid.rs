@@ -0,0 +1,49 @@
+use alloc::vec::Vec;
+
+/// 进程号的上限，与 Linux 默认的 `pid_max` 相同。
+const PID_MAX: usize = 32768;
+
+/// 进程号，从 1 开始。
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Pid(usize);
+
+impl Pid {
+    /// 进程号的数值。
+    #[inline]
+    pub const fn get(self) -> usize {
+        self.0
+    }
+}
+
+/// 进程号已经用完。
+#[derive(Debug)]
+pub struct PidExhausted;
+
+/// 从未分配过的最小进程号。
+static mut NEXT: usize = 1;
+/// 释放的进程号，后释放的先复用。
+static mut FREE: Vec<usize> = Vec::new();
+
+/// 分配一个进程号，优先复用释放的。
+///
+/// 分配顺序只取决于之前的分配和释放，同样的执行过程得到同样的进程号。
+pub fn alloc() -> Result<Pid, PidExhausted> {
+    unsafe {
+        if let Some(pid) = FREE.pop() {
+            return Ok(Pid(pid));
+        }
+        if NEXT >= PID_MAX {
+            return Err(PidExhausted);
+        }
+        NEXT += 1;
+        Ok(Pid(NEXT - 1))
+    }
+}
+
+/// 释放进程号。进程退出且不再需要被 `wait` 回收之后才能释放。
+pub fn free(pid: Pid) {
+    unsafe {
+        debug_assert!(pid.0 < NEXT && !FREE.contains(&pid.0));
+        FREE.push(pid.0);
+    }
+}
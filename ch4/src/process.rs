@@ -1,15 +1,18 @@
-﻿use crate::VmManager;
-use alloc::alloc::alloc_zeroed;
-use core::{alloc::Layout, str::FromStr};
+﻿use crate::{
+    pid::{self, Pid},
+    VmManager, FRAME_REFS, PROTAL_TRANSIT,
+};
+use alloc::{vec, vec::Vec};
+use core::{ops::Range, str::FromStr};
 use kernel_context::{foreign::ForeignContext, LocalContext};
 use kernel_vm::{
-    page_table::{MmuMeta, VAddr, VmFlags, PPN, VPN},
+    page_table::{MmuMeta, Pte, VAddr, VmFlags, VmMeta, VPN},
     AddressSpace,
 };
 use rcore_console::log;
 use xmas_elf::{
     header::{self, HeaderPt2, Machine},
-    program, ElfFile,
+    program, sections, ElfFile,
 };
 
 // 根据架构选择页表模式
@@ -18,33 +21,132 @@ use kernel_vm::page_table::Sv39 as VmMode;
 #[cfg(target_pointer_width = "32")]
 use kernel_vm::page_table::Sv32 as VmMode;
 
+/// 用户栈最多增长到的页数。
+const STACK_MAX_PAGES: usize = 16;
+/// 用户栈顶所在虚页的下一页。
+#[cfg(target_pointer_width = "64")]
+const STACK_TOP_VPN: usize = 1 << 26;
+/// 用户栈顶所在虚页的下一页，Sv32 的虚页号只有 20 位。
+#[cfg(target_pointer_width = "32")]
+const STACK_TOP_VPN: usize = 1 << 19;
+/// 位置无关程序的加载偏移。
+const PIE_BIAS: usize = 0x40_0000;
+
+/// 缺页地址相对于进程地址空间的分类。
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FaultKind {
+    /// 地址已经映射，是访问权限不符。
+    Mapped,
+    /// 栈范围内没有映射的页，尚未访问过或被 `madvise` 释放。
+    StackHole,
+    /// 栈底下方紧邻的保护页。
+    Guard,
+    /// 保护页下方、栈最多能增长到的范围内。
+    BelowStack,
+    /// 不属于任何区域。
+    Unmapped,
+}
+
+/// 预留了虚页但不预先分配物理页的区域，首次访问缺页时映射零页。
+#[derive(Clone)]
+pub struct LazyArea {
+    pub range: Range<VPN<VmMode>>,
+    pub flags: VmFlags<VmMode>,
+}
+
 /// 进程。
 pub struct Process {
+    /// 进程号，进程退出并被回收之后释放。
+    pub pid: Pid,
+    /// 父进程的进程号。启动时加载的进程和父进程已经退出的进程没有父进程，是进程树的根。
+    pub parent: Option<Pid>,
     pub context: ForeignContext,
     pub address_space: AddressSpace<VmMode, VmManager>,
+    /// 进程睡眠到的时刻（`time` 寄存器的计数），未到达之前不会被调度。
+    pub wake_at: u64,
+    /// 已映射的最低栈页，它下方紧邻的一页是保护页。
+    stack_bottom: VPN<VmMode>,
+    /// 栈顶之上的第一页。
+    stack_top: VPN<VmMode>,
+    /// 按需分配的区域，用户栈是其中第一个。
+    lazy: Vec<LazyArea>,
+    /// 堆的起始页，紧接在最高的 Load 段之后。
+    heap_bottom: VPN<VmMode>,
+    /// 当前的程序断点，`heap_bottom` 到它所在页的结尾都已映射。
+    brk: usize,
+}
+
+/// 进程结束时回收整个地址空间。
+///
+/// 根页表中传送门所在的项是从内核地址空间复制来的，指向内核的页表，先移除再释放。
+impl Drop for Process {
+    fn drop(&mut self) {
+        let portal_idx = PROTAL_TRANSIT.index_in(VmMode::MAX_LEVEL);
+        self.address_space.root()[portal_idx] = Pte::ZERO;
+        unsafe { self.address_space.free() };
+    }
 }
 
 impl Process {
+    /// 从 ELF 文件创建进程。
+    ///
+    /// 每个 Load 段在文件中的偏移和虚地址模页大小必须相同，否则无法按页映射，返回 `None`。
+    /// 两个段占用同一页时同样返回 `None`。
+    /// 进程号用完时同样返回 `None`。
     pub fn new(elf: ElfFile) -> Option<Self> {
-        // 根据架构检查 ELF 头
+        let Ok(pid) = pid::alloc() else {
+            log::error!("pid exhausted");
+            None?
+        };
+        let process = Self::load(elf, pid);
+        if process.is_none() {
+            pid::free(pid);
+        }
+        process
+    }
+
+    /// 把当前映像换成 `elf`，进程号和父进程不变。
+    ///
+    /// `args` 复制到新的用户栈上，`a0`、`a1` 分别是参数个数和参数字符串指针数组，数组以空指针结尾。
+    /// 加载失败或参数超过一页时不做任何修改，返回 `false`。
+    pub fn exec(&mut self, elf: ElfFile, args: &[&[u8]]) -> bool {
+        let Some(mut image) = Self::load(elf, self.pid) else {
+            return false;
+        };
+        if !image.push_args(args) {
+            return false;
+        }
+        let portal_idx = PROTAL_TRANSIT.index_in(VmMode::MAX_LEVEL);
+        image.address_space.root()[portal_idx] = self.address_space.root()[portal_idx];
+        image.parent = self.parent;
+        // 换下来的旧映像随 `image` 一起释放
+        core::mem::swap(self, &mut image);
+        true
+    }
+
+    /// 从 ELF 文件建立进程号为 `pid` 的映像。
+    fn load(elf: ElfFile, pid: Pid) -> Option<Self> {
+        // 根据架构检查 ELF 头，位置无关的程序加载到 `PIE_BIAS` 处
         #[cfg(target_pointer_width = "64")]
-        let entry = match elf.header.pt2 {
-            HeaderPt2::Header64(pt2)
-                if pt2.type_.as_type() == header::Type::Executable
-                    && pt2.machine.as_machine() == Machine::RISC_V =>
-            {
-                pt2.entry_point as usize
+        let (entry, bias) = match elf.header.pt2 {
+            HeaderPt2::Header64(pt2) if pt2.machine.as_machine() == Machine::RISC_V => {
+                match pt2.type_.as_type() {
+                    header::Type::Executable => (pt2.entry_point as usize, 0),
+                    header::Type::SharedObject => (pt2.entry_point as usize + PIE_BIAS, PIE_BIAS),
+                    _ => None?,
+                }
             }
             _ => None?,
         };
         
         #[cfg(target_pointer_width = "32")]
-        let entry = match elf.header.pt2 {
-            HeaderPt2::Header32(pt2)
-                if pt2.type_.as_type() == header::Type::Executable
-                    && pt2.machine.as_machine() == Machine::RISC_V =>
-            {
-                pt2.entry_point as usize
+        let (entry, bias) = match elf.header.pt2 {
+            HeaderPt2::Header32(pt2) if pt2.machine.as_machine() == Machine::RISC_V => {
+                match pt2.type_.as_type() {
+                    header::Type::Executable => (pt2.entry_point as usize, 0),
+                    header::Type::SharedObject => (pt2.entry_point as usize + PIE_BIAS, PIE_BIAS),
+                    _ => None?,
+                }
             }
             _ => None?,
         };
@@ -53,16 +155,31 @@ impl Process {
         const PAGE_MASK: usize = PAGE_SIZE - 1;
 
         let mut address_space = AddressSpace::new();
+        let mut heap_bottom = VPN::new(0);
         for program in elf.program_iter() {
             if !matches!(program.get_type(), Ok(program::Type::Load)) {
                 continue;
             }
 
+            // 不占内存的段没有需要映射的页
+            if program.mem_size() == 0 {
+                continue;
+            }
+
             let off_file = program.offset() as usize;
             let len_file = program.file_size() as usize;
-            let off_mem = program.virtual_addr() as usize;
+            let off_mem = program.virtual_addr() as usize + bias;
             let end_mem = off_mem + program.mem_size() as usize;
-            assert_eq!(off_file & PAGE_MASK, off_mem & PAGE_MASK);
+            if off_file & PAGE_MASK != off_mem & PAGE_MASK {
+                log::error!("misaligned segment: offset = {off_file:#x}, vaddr = {off_mem:#x}");
+                return discard(address_space);
+            }
+            // 纯 .bss 段只映射清零的页，不从文件拷贝
+            let data = if len_file == 0 {
+                &[][..]
+            } else {
+                &elf.input[off_file..][..len_file]
+            };
 
             let mut flags: [u8; 5] = *b"U___V";
             if program.flags().is_execute() {
@@ -74,43 +191,42 @@ impl Process {
             if program.flags().is_read() {
                 flags[3] = b'R';
             }
-            address_space.map(
+            heap_bottom = heap_bottom.max(VAddr::new(end_mem).ceil());
+            // 段之间有重叠的页时无法分别设置属性，拒绝加载而不是让内核 panic
+            if let Err(vpn) = address_space.try_map(
                 VAddr::new(off_mem).floor()..VAddr::new(end_mem).ceil(),
-                &elf.input[off_file..][..len_file],
+                data,
                 off_mem & PAGE_MASK,
                 VmFlags::from_str(unsafe { core::str::from_utf8_unchecked(&flags) }).unwrap(),
-            );
+            ) {
+                log::error!("overlapping segment at {vpn:?}");
+                return discard(address_space);
+            }
         }
-        
-        let stack = unsafe {
-            alloc_zeroed(Layout::from_size_align_unchecked(
-                2 << VmMode::PAGE_BITS,
-                1 << VmMode::PAGE_BITS,
-            ))
+        if bias != 0 && relocate(&elf, &address_space, bias).is_none() {
+            return discard(address_space);
+        }
+        let stack_top_vpn = STACK_TOP_VPN;
+        // 栈一开始只预留一页，不分配物理页，之后按需向下增长
+        let stack = LazyArea {
+            range: VPN::new(stack_top_vpn - 1)..VPN::new(stack_top_vpn),
+            flags: VmFlags::build_from_str("U_WRV"),
         };
-        
-        // RV64: 使用更大的地址空间
-        #[cfg(target_pointer_width = "64")]
-        let stack_top_vpn = 1usize << 26;
-        // RV32: 使用较小的地址空间 (Sv32: 20-bit VPN)
-        #[cfg(target_pointer_width = "32")]
-        let stack_top_vpn = 1usize << 19;
-        
-        address_space.map_extern(
-            VPN::new(stack_top_vpn - 2)..VPN::new(stack_top_vpn),
-            PPN::new(stack as usize >> VmMode::PAGE_BITS),
-            VmFlags::build_from_str("U_WRV"),
-        );
 
+        // 入口必须落在可执行段里，否则第一次执行就是一个看不出原因的取指异常
+        const EXECUTABLE: VmFlags<VmMode> = VmFlags::build_from_str("U_X_V");
+        if address_space
+            .translate::<u8>(VAddr::new(entry), EXECUTABLE)
+            .is_none()
+        {
+            log::error!("entry {entry:#x} not in executable segment");
+            return discard(address_space);
+        }
         log::info!("process entry = {:#x}", entry);
 
         let mut context = LocalContext::user(entry);
         
-        // 根据架构构建 satp
-        #[cfg(target_pointer_width = "64")]
-        let satp = (8usize << 60) | address_space.root_ppn().val();
-        #[cfg(target_pointer_width = "32")]
-        let satp = (1usize << 31) | address_space.root_ppn().val();
+        let satp = build_satp(&address_space);
         
         // 设置用户栈指针
         #[cfg(target_pointer_width = "64")]
@@ -123,8 +239,411 @@ impl Process {
         }
         
         Some(Self {
+            pid,
+            parent: None,
             context: ForeignContext { context, satp },
             address_space,
+            wake_at: 0,
+            stack_bottom: VPN::new(stack_top_vpn - 1),
+            stack_top: VPN::new(stack_top_vpn),
+            lazy: vec![stack],
+            heap_bottom,
+            brk: heap_bottom.base().val(),
         })
     }
+
+    /// 写时复制地复制出子进程。
+    ///
+    /// 可写的页不复制，父子进程都去掉写权限、标记 [`COW`](VmManager::COW) 并共享物理页，
+    /// 写缺页时由 [`break_cow`](Self::break_cow) 分开。只读的页直接复制一份，
+    /// 否则它被 `mprotect` 改为可写时无法区分。不属于地址空间的页按引用共享。
+    /// 子进程的上下文与父进程相同，从 `ecall` 的下一条指令继续执行，返回值是 0。
+    ///
+    /// 进程号用完时返回 `None`。
+    pub fn fork(&mut self) -> Option<Self> {
+        let pid = pid::alloc().ok()?;
+        let address_space = self.address_space.fork(true);
+        let portal_idx = PROTAL_TRANSIT.index_in(VmMode::MAX_LEVEL);
+        address_space.root()[portal_idx] = self.address_space.root()[portal_idx];
+        // 父进程在系统调用返回后才越过 `ecall`，子进程要自己越过
+        let mut context = self.context.context.fork_child();
+        context.move_next();
+        Some(Self {
+            pid,
+            parent: Some(self.pid),
+            context: ForeignContext {
+                context,
+                satp: build_satp(&address_space),
+            },
+            address_space,
+            wake_at: 0,
+            stack_bottom: self.stack_bottom,
+            stack_top: self.stack_top,
+            lazy: self.lazy.clone(),
+            heap_bottom: self.heap_bottom,
+            brk: self.brk,
+        })
+    }
+
+    /// 处理写时复制页上的写缺页，`addr` 不在写时复制页上时返回 `false`。
+    ///
+    /// 物理页仍被其他进程共享时复制一份换上，否则已是最后一个持有者，直接恢复写权限。
+    pub fn break_cow(&mut self, addr: usize) -> bool {
+        const WRITE: VmFlags<VmMode> = VmFlags::build_from_str("W");
+        let vpn = VAddr::<VmMode>::new(addr).floor();
+        let Some(pte) = self.address_space.pte(vpn) else {
+            return false;
+        };
+        let flags = pte.flags();
+        if !flags.contains(VmManager::COW) {
+            return false;
+        }
+        let range = vpn..vpn + 1;
+        if unsafe { FRAME_REFS.is_shared(pte.ppn().val()) } {
+            let writable = (flags ^ VmManager::COW) | WRITE;
+            let page = self.page(vpn);
+            // 只减少持有者数，物理页仍然有效，可以从中复制
+            self.address_space.unmap(range.clone());
+            if self
+                .address_space
+                .try_map(range, page, 0, writable)
+                .is_err()
+            {
+                return false;
+            }
+        } else {
+            self.address_space
+                .protect(range, WRITE | VmManager::COW, WRITE)
+                .unwrap();
+        }
+        true
+    }
+
+    /// 已映射的虚页 `vpn` 在内核中的内容。
+    fn page(&self, vpn: VPN<VmMode>) -> &'static [u8] {
+        let ptr = self
+            .address_space
+            .translate::<u8>(vpn.base(), VmFlags::VALID)
+            .unwrap();
+        unsafe { core::slice::from_raw_parts(ptr.as_ptr(), 1 << VmMode::PAGE_BITS) }
+    }
+
+    /// 当前的程序断点。
+    pub fn brk(&self) -> usize {
+        self.brk
+    }
+
+    /// 把程序断点移到 `brk`。
+    ///
+    /// 增长时映射清零的可读写页，收缩时移除断点之后的整页并归还页帧。
+    /// 断点不能低于堆的起始地址，也不能碰到栈可能增长到的范围和保护页，
+    /// 新增的页与已有映射或按需分配区域重叠时同样失败。失败时不做任何修改，返回 `false`。
+    pub fn set_brk(&mut self, brk: usize) -> bool {
+        let vaddr = VAddr::<VmMode>::new(brk);
+        if vaddr.val() != brk || vaddr.floor() < self.heap_bottom {
+            return false;
+        }
+        let old = VAddr::<VmMode>::new(self.brk).ceil();
+        let new = vaddr.ceil();
+        if new > old {
+            // 留出栈最低处下方的保护页
+            if new.val() >= Self::stack_limit().start.val() {
+                return false;
+            }
+            let range = old..new;
+            if self.lazy_overlapping(&range).is_some() {
+                return false;
+            }
+            let flags = VmFlags::build_from_str("U_WRV");
+            if self.address_space.try_map(range, &[], 0, flags).is_err() {
+                return false;
+            }
+        } else if new < old {
+            self.address_space.unmap(new..old);
+        }
+        self.brk = brk;
+        true
+    }
+
+    /// 映射 `pages` 页清零的匿名内存，返回映射的起始虚页。
+    ///
+    /// `hint` 不为 `None` 时只尝试从这一页开始映射；否则从栈的保护页下方向下寻找第一段足够大的空闲虚页，
+    /// 不低于当前的程序断点。映射必须位于保护页之下，与已有映射或按需分配区域重叠时返回 `None`。
+    pub fn mmap(
+        &mut self,
+        hint: Option<VPN<VmMode>>,
+        pages: usize,
+        flags: VmFlags<VmMode>,
+    ) -> Option<VPN<VmMode>> {
+        let limit = Self::stack_limit().start.val() - 1;
+        let range = match hint {
+            Some(start) => {
+                let end = start.val().checked_add(pages).filter(|&end| end <= limit)?;
+                start..VPN::new(end)
+            }
+            None => {
+                let bottom = VAddr::<VmMode>::new(self.brk).ceil().val();
+                let mut end = limit;
+                loop {
+                    let start = end.checked_sub(pages).filter(|&start| start >= bottom)?;
+                    let range = VPN::new(start)..VPN::new(end);
+                    if let Some(vpn) = self.address_space.find_mapped(range.clone()) {
+                        end = vpn.val();
+                    } else if let Some(area) = self.lazy_overlapping(&range) {
+                        end = area.range.start.val();
+                    } else {
+                        break range;
+                    }
+                }
+            }
+        };
+        if self.lazy_overlapping(&range).is_some() {
+            return None;
+        }
+        self.address_space
+            .try_map(range.clone(), &[], 0, flags)
+            .ok()?;
+        Some(range.start)
+    }
+
+    /// 移除 `range` 中的映射并归还拥有的页帧，返回移除的页数。
+    ///
+    /// 范围必须位于栈的保护页之下，其中已映射的页必须都是用户页，否则不做任何修改，返回 `None`。
+    pub fn munmap(&mut self, range: Range<VPN<VmMode>>) -> Option<usize> {
+        const USER: VmFlags<VmMode> = VmFlags::build_from_str("U___V");
+        if range.end.val() >= Self::stack_limit().start.val() {
+            return None;
+        }
+        let mut vpn = range.start;
+        while vpn < range.end {
+            if self.address_space.find_mapped(vpn..vpn + 1).is_some()
+                && self
+                    .address_space
+                    .translate::<u8>(vpn.base(), USER)
+                    .is_none()
+            {
+                return None;
+            }
+            vpn += 1;
+        }
+        Some(self.address_space.unmap(range))
+    }
+
+    /// 与 `range` 重叠的第一个按需分配区域。
+    fn lazy_overlapping(&self, range: &Range<VPN<VmMode>>) -> Option<&LazyArea> {
+        self.lazy
+            .iter()
+            .find(|area| area.range.start < range.end && range.start < area.range.end)
+    }
+
+    /// 用户栈可能占用的全部虚页，也就是增长到 [`STACK_MAX_PAGES`] 页时的范围。
+    pub fn stack_limit() -> Range<VPN<VmMode>> {
+        VPN::new(STACK_TOP_VPN - STACK_MAX_PAGES)..VPN::new(STACK_TOP_VPN)
+    }
+
+    /// 判断缺页地址 `addr` 落在进程地址空间的什么位置。
+    ///
+    /// 各种缺页处理都根据这个分类决定是修复后重新执行还是杀死进程。
+    pub fn classify_fault(&self, addr: usize) -> FaultKind {
+        let vaddr = VAddr::<VmMode>::new(addr);
+        if vaddr.val() != addr {
+            return FaultKind::Unmapped;
+        }
+        if self.address_space.translate::<u8>(vaddr, VmFlags::VALID).is_some() {
+            return FaultKind::Mapped;
+        }
+        let vpn = vaddr.floor();
+        if self.stack_bottom <= vpn && vpn < self.stack_top {
+            FaultKind::StackHole
+        } else if vpn.val() + 1 == self.stack_bottom.val() {
+            FaultKind::Guard
+        } else if vpn < self.stack_bottom
+            && self.stack_top.val() - vpn.val() <= STACK_MAX_PAGES
+        {
+            FaultKind::BelowStack
+        } else {
+            FaultKind::Unmapped
+        }
+    }
+
+    /// 判断 `pc` 处是不是 A 扩展的原子指令（LR/SC 和 AMO）。
+    ///
+    /// 原子指令没有独立的异常原因：LR 缺页报告为读缺页，SC 和 AMO 缺页报告为写缺页
+    /// （规范中称为 Store/AMO page fault），只能通过指令编码区分。
+    pub fn is_atomic_inst(&self, pc: usize) -> bool {
+        const OPCODE_AMO: u16 = 0b010_1111;
+        self.address_space
+            .translate::<u16>(VAddr::new(pc), VmFlags::build_from_str("U_X_V"))
+            .is_some_and(|inst| unsafe { inst.as_ptr().read_volatile() } & 0x7f == OPCODE_AMO)
+    }
+
+    /// 把参数字符串和指向它们的指针数组放在栈顶，按 16 字节对齐栈指针。
+    fn push_args(&mut self, args: &[&[u8]]) -> bool {
+        const PAGE_SIZE: usize = 1 << VmMode::PAGE_BITS;
+        const PTR_SIZE: usize = core::mem::size_of::<usize>();
+        let top = self.stack_top.base().val();
+        let strings = args.iter().map(|arg| arg.len() + 1).sum::<usize>();
+        let argv = ((top - strings) & !(PTR_SIZE - 1)) - (args.len() + 1) * PTR_SIZE;
+        let sp = argv & !15;
+        if top - sp > PAGE_SIZE {
+            return false;
+        }
+        // 栈的第一页在这里分配，只用到这一页
+        let base = top - PAGE_SIZE;
+        self.fill_lazy(base);
+        let ptr = self
+            .address_space
+            .translate::<u8>(VAddr::new(base), VmFlags::VALID)
+            .unwrap();
+        let stack = unsafe { core::slice::from_raw_parts_mut(ptr.as_ptr(), PAGE_SIZE) };
+        let mut string = top - strings;
+        for (i, arg) in args.iter().enumerate() {
+            stack[string - base..][..arg.len()].copy_from_slice(arg);
+            stack[string - base + arg.len()] = 0;
+            stack[argv - base + i * PTR_SIZE..][..PTR_SIZE].copy_from_slice(&string.to_ne_bytes());
+            string += arg.len() + 1;
+        }
+        stack[argv - base + args.len() * PTR_SIZE..][..PTR_SIZE].fill(0);
+        let context = &mut self.context.context;
+        *context.sp_mut() = sp;
+        *context.a_mut(0) = args.len();
+        *context.a_mut(1) = argv;
+        true
+    }
+
+    /// 处理按需分配区域中的缺页。
+    ///
+    /// `addr` 落在某个 [`LazyArea`] 中且所在页还没有映射时，映射一个零页。
+    /// 不在任何区域中时返回 `false`。
+    pub fn fill_lazy(&mut self, addr: usize) -> bool {
+        let vaddr = VAddr::<VmMode>::new(addr);
+        if vaddr.val() != addr {
+            return false;
+        }
+        let vpn = vaddr.floor();
+        let Some(area) = self.lazy.iter().find(|area| area.range.contains(&vpn)) else {
+            return false;
+        };
+        self.address_space
+            .try_map(vpn..vpn + 1, &[], 0, area.flags)
+            .is_ok()
+    }
+
+    /// 处理栈上的缺页。
+    ///
+    /// `addr` 落在保护页中时，把保护页并入栈并映射为新的栈页，保护页随之下移；
+    /// 落在尚未访问或被 `madvise` 释放的栈页中时，映射一个零页。
+    ///
+    /// 栈已经达到 [`STACK_MAX_PAGES`] 页或 `addr` 不在上述位置时返回 `false`。
+    pub fn grow_stack(&mut self, addr: usize) -> bool {
+        let vpn = VAddr::<VmMode>::new(addr).floor();
+        match self.classify_fault(addr) {
+            FaultKind::StackHole => self.fill_lazy(addr),
+            FaultKind::Guard if self.stack_top.val() - vpn.val() <= STACK_MAX_PAGES => {
+                self.lazy[0].range.start = vpn;
+                self.stack_bottom = vpn;
+                self.fill_lazy(addr)
+            }
+            _ => false,
+        }
+    }
+
+    /// 检查 `sp` 是否可以作为恢复执行时的栈指针。
+    ///
+    /// 紧邻 `sp` 下方的字节必须已经映射为用户可写，或者落在栈可以增长到的范围内。
+    #[cfg(feature = "check-sp")]
+    pub fn sp_valid(&self, sp: usize) -> bool {
+        const WRITABLE: VmFlags<VmMode> = VmFlags::build_from_str("U_WV");
+        let addr = sp.wrapping_sub(1);
+        let vaddr = VAddr::<VmMode>::new(addr);
+        if vaddr.val() != addr {
+            return false;
+        }
+        let top = self.stack_top.val();
+        let growable = top.saturating_sub(STACK_MAX_PAGES)..top;
+        let mapped = self.address_space.translate::<u8>(vaddr, WRITABLE);
+        growable.contains(&vaddr.floor().val()) || mapped.is_some()
+    }
+}
+
+/// 加载失败时释放已经建立的映射，返回 `None`。
+///
+/// 这时传送门还没有接入根页表，地址空间中的页都可以释放。
+fn discard(mut address_space: AddressSpace<VmMode, VmManager>) -> Option<Process> {
+    unsafe { address_space.free() };
+    None
+}
+
+/// 根据架构构建指向 `address_space` 根页表的 satp。
+fn build_satp(address_space: &AddressSpace<VmMode, VmManager>) -> usize {
+    #[cfg(target_pointer_width = "64")]
+    let satp = (8usize << 60) | address_space.root_ppn().val();
+    #[cfg(target_pointer_width = "32")]
+    let satp = (1usize << 31) | address_space.root_ppn().val();
+    debug_assert!(
+        satp_mode_matches(satp),
+        "satp = {satp:#x} mismatches VmMode"
+    );
+    satp
+}
+
+/// 检查 `satp` 的 MODE 字段和 [`VmMode`] 的页表级数是否一致。
+///
+/// 两种架构分别手写 satp，这里防止它们和选用的分页模式脱节：
+/// RV64 的 MODE 在高 4 位，Sv39、Sv48、Sv57 依次是 8、9、10；RV32 只有最高位，1 表示 Sv32。
+pub fn satp_mode_matches(satp: usize) -> bool {
+    let levels = VmMode::MAX_LEVEL + 1;
+    if VmMode::PAGE_BITS != 12 {
+        return false;
+    }
+    #[cfg(target_pointer_width = "64")]
+    {
+        satp >> 60 == levels + 5
+    }
+    #[cfg(target_pointer_width = "32")]
+    {
+        satp >> 31 == 1 && levels == 2
+    }
+}
+
+/// 对加载到 `bias` 处的位置无关程序应用动态重定位。
+///
+/// 静态链接的 PIE 只有 `R_RISCV_RELATIVE`，遇到其他类型时返回 `None`。
+fn relocate(
+    elf: &ElfFile,
+    address_space: &AddressSpace<VmMode, VmManager>,
+    bias: usize,
+) -> Option<()> {
+    const R_RISCV_NONE: u32 = 0;
+    const R_RISCV_RELATIVE: u32 = 3;
+    const MAPPED: VmFlags<VmMode> = VmFlags::build_from_str("U___V");
+
+    for section in elf.section_iter() {
+        if !matches!(section.get_type(), Ok(sections::ShType::Rela)) {
+            continue;
+        }
+        #[cfg(target_pointer_width = "64")]
+        let Ok(sections::SectionData::Rela64(entries)) = section.get_data(elf) else {
+            return None;
+        };
+        #[cfg(target_pointer_width = "32")]
+        let Ok(sections::SectionData::Rela32(entries)) = section.get_data(elf) else {
+            return None;
+        };
+        for rela in entries {
+            match rela.get_type() {
+                R_RISCV_NONE => {}
+                R_RISCV_RELATIVE => {
+                    let addr = VAddr::new(rela.get_offset() as usize + bias);
+                    let ptr = address_space.translate::<usize>(addr, MAPPED)?;
+                    unsafe { ptr.as_ptr().write_unaligned(rela.get_addend() as usize + bias) };
+                }
+                ty => {
+                    log::error!("unsupported relocation type: {ty}");
+                    None?
+                }
+            }
+        }
+    }
+    Some(())
 }
@@ -0,0 +1,105 @@
+use alloc::vec::Vec;
+use core::fmt;
+use riscv::register::scause::Trap;
+use syscall::SyscallId;
+
+/// 陷入原因统计。
+///
+/// 系统调用按调用号计数，其他陷入按原因计数。种类很少，线性查找就够了。
+pub struct TrapStats {
+    syscalls: Vec<(SyscallId, usize)>,
+    traps: Vec<(Trap, usize)>,
+}
+
+impl TrapStats {
+    /// 创建空的统计表。
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            syscalls: Vec::new(),
+            traps: Vec::new(),
+        }
+    }
+
+    /// 记录一次系统调用。
+    pub fn count_syscall(&mut self, id: SyscallId) {
+        increase(&mut self.syscalls, id);
+    }
+
+    /// 记录一次系统调用以外的陷入。
+    pub fn count_trap(&mut self, trap: Trap) {
+        increase(&mut self.traps, trap);
+    }
+}
+
+fn increase<K: PartialEq>(table: &mut Vec<(K, usize)>, key: K) {
+    match table.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, n)) => *n += 1,
+        None => table.push((key, 1)),
+    }
+}
+
+/// 每行一项，先列系统调用，再列其他陷入。
+impl fmt::Display for TrapStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (id, n) in &self.syscalls {
+            writeln!(f, "syscall {}: {n}", id.0)?;
+        }
+        for (trap, n) in &self.traps {
+            match trap {
+                Trap::Interrupt(i) => writeln!(f, "interrupt {i:?}: {n}")?,
+                Trap::Exception(e) => writeln!(f, "exception {e:?}: {n}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 物理页帧统计，区分页表页和叶子数据页。
+///
+/// 只统计页管理器分配的页，外部映射的页（内核、传送门、设备）不计入。
+pub struct FrameStats {
+    table: usize,
+    data: usize,
+}
+
+impl FrameStats {
+    /// 创建空的统计。
+    #[inline]
+    pub const fn new() -> Self {
+        Self { table: 0, data: 0 }
+    }
+
+    /// 记录分配 `len` 个页。
+    #[inline]
+    pub fn allocate(&mut self, is_table: bool, len: usize) {
+        *self.counter(is_table) += len;
+    }
+
+    /// 记录释放 `len` 个页。
+    #[inline]
+    pub fn deallocate(&mut self, is_table: bool, len: usize) {
+        *self.counter(is_table) -= len;
+    }
+
+    /// 当前用作页表的页数。
+    #[inline]
+    pub const fn table(&self) -> usize {
+        self.table
+    }
+
+    /// 当前用作数据的页数。
+    #[inline]
+    pub const fn data(&self) -> usize {
+        self.data
+    }
+
+    #[inline]
+    fn counter(&mut self, is_table: bool) -> &mut usize {
+        if is_table {
+            &mut self.table
+        } else {
+            &mut self.data
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use crate::pid::Pid;
+use alloc::collections::BinaryHeap;
+use core::cmp::Reverse;
+
+/// 定时事件队列。
+///
+/// 以唤醒时刻（`time` 寄存器的 64 位计数）为键的小根堆，取最近的事件是 O(1)，插入和弹出是 O(log n)。
+/// 事件携带需要唤醒的进程号。进程可能已经退出、进程号被复用，或者又重新睡眠，
+/// 所以调度器只在进程号和唤醒时刻都对得上时才唤醒。
+pub struct TimerQueue(BinaryHeap<Reverse<(u64, Pid)>>);
+
+impl TimerQueue {
+    /// 创建空的定时事件队列。
+    #[inline]
+    pub const fn new() -> Self {
+        Self(BinaryHeap::new())
+    }
+
+    /// 添加一个在 `deadline` 时刻唤醒进程 `pid` 的事件。
+    #[inline]
+    pub fn push(&mut self, deadline: u64, pid: Pid) {
+        self.0.push(Reverse((deadline, pid)));
+    }
+
+    /// 最近一个事件的唤醒时刻。
+    #[inline]
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.0.peek().map(|Reverse((deadline, _))| *deadline)
+    }
+
+    /// 如果最近的事件在 `now` 之前到期，取出它的唤醒时刻和进程号。
+    #[inline]
+    pub fn pop_expired(&mut self, now: u64) -> Option<(u64, Pid)> {
+        match self.0.peek() {
+            Some(Reverse((deadline, _))) if *deadline <= now => {
+                self.0.pop().map(|Reverse(event)| event)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 设置定时器并等待到 `deadline` 时刻（`time` 寄存器的计数）。
+///
+/// 调度线程不开中断，但 `wfi` 仍会被 `sie` 中使能的中断唤醒。
+pub fn idle_until(deadline: u64) {
+    use riscv::{asm::wfi, register::sie};
+    sbi_rt::set_timer(deadline);
+    unsafe {
+        sie::set_stimer();
+        wfi();
+        sie::clear_stimer();
+    }
+    sbi_rt::set_timer(u64::MAX);
+}
+
+/// 时钟频率（QEMU virt 的 timebase-frequency）。
+pub const TIMEBASE_FREQ: u64 = 12_500_000;
+
+/// 时间片长度（毫秒）。
+pub const QUANTUM_MS: u64 = 10;
+
+/// 当前时刻（`time` 寄存器的 64 位计数），RV32 上也不会回绕。
+#[inline]
+pub fn now() -> u64 {
+    riscv::register::time::read64()
+}
+
+/// 把纳秒换算成时钟周期数，不足一个周期的部分向上取整，睡眠不会提前结束。
+#[inline]
+pub const fn ns_to_ticks(ns: u64) -> u64 {
+    ns.div_ceil(1_000_000_000 / TIMEBASE_FREQ)
+}
+
+/// 一个时间片对应的时钟周期数。
+const QUANTUM_TICKS: u64 = TIMEBASE_FREQ / 1000 * QUANTUM_MS;
+
+/// 在进入用户进程之前设置时间片结束时的时钟中断。
+///
+/// 截止时刻直接用 64 位的 `time` 计算，RV32 上也不会回绕。
+/// 调度线程不开中断，时钟中断只会在用户态触发，陷入调度线程后由调度器轮转。
+pub fn arm_quantum() {
+    use riscv::register::sie;
+    sbi_rt::set_timer(now() + QUANTUM_TICKS);
+    unsafe { sie::set_stimer() };
+}
+
+/// 回到调度线程后撤销时钟中断，内核的工作不会被打断，也不会留下挂起的中断。
+pub fn disarm() {
+    use riscv::register::sie;
+    unsafe { sie::clear_stimer() };
+    sbi_rt::set_timer(u64::MAX);
+}
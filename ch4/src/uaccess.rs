@@ -0,0 +1,118 @@
+use crate::{VmMode, PROCESSES};
+use alloc::{string::String, vec::Vec};
+use core::{mem::size_of, ops::Range};
+use kernel_vm::page_table::{MmuMeta, VAddr, VmFlags};
+use syscall::Caller;
+
+const READABLE: VmFlags<VmMode> = VmFlags::build_from_str("U__RV");
+const WRITABLE: VmFlags<VmMode> = VmFlags::build_from_str("U_W_V");
+
+/// 用户缓冲区 `buf..buf + len` 对应的虚地址范围，首尾字节不是合法的虚地址时返回 `None`。
+fn user_range(buf: usize, len: usize) -> Option<Range<VAddr<VmMode>>> {
+    let end = buf.checked_add(len)?;
+    let valid = |addr: usize| VAddr::<VmMode>::new(addr).val() == addr;
+    (valid(buf) && (len == 0 || valid(end - 1))).then(|| VAddr::new(buf)..VAddr::new(end))
+}
+
+/// 从调用者的用户地址 `uaddr` 起读出 `len` 字节。
+///
+/// 缓冲区可以跨页，每一页都必须是用户可读的，否则返回 `None`。
+pub fn copy_from_user(caller: Caller, uaddr: usize, len: usize) -> Option<Vec<u8>> {
+    let process = unsafe { PROCESSES.get(caller.entity) }?;
+    let segments = process
+        .address_space
+        .translate_range(user_range(uaddr, len)?, READABLE)?;
+    let mut bytes = Vec::with_capacity(len);
+    for (ptr, len) in segments {
+        bytes.extend_from_slice(unsafe { core::slice::from_raw_parts(ptr.as_ptr(), len) });
+    }
+    Some(bytes)
+}
+
+/// 从调用者的用户地址 `uaddr` 读出以 NUL 结尾的字符串，结果不含 NUL。
+///
+/// 一边逐页翻译一边查找 NUL，字符串可以跨页。
+/// 超过 `max_len` 字节还没有遇到 NUL、遇到不可读的页或者内容不是 UTF-8 时返回 `None`。
+pub fn read_user_cstr(caller: Caller, uaddr: usize, max_len: usize) -> Option<String> {
+    const PAGE_SIZE: usize = 1 << VmMode::PAGE_BITS;
+    let process = unsafe { PROCESSES.get(caller.entity) }?;
+    let mut bytes = Vec::new();
+    let mut addr = uaddr;
+    loop {
+        if VAddr::<VmMode>::new(addr).val() != addr {
+            return None;
+        }
+        let ptr = process
+            .address_space
+            .translate::<u8>(VAddr::new(addr), READABLE)?;
+        // 最多读到第 `max_len + 1` 个字节，那里必须是 NUL
+        let len = (PAGE_SIZE - addr % PAGE_SIZE).min(max_len.saturating_add(1) - bytes.len());
+        let page = unsafe { core::slice::from_raw_parts(ptr.as_ptr(), len) };
+        if let Some(n) = page.iter().position(|&b| b == 0) {
+            bytes.extend_from_slice(&page[..n]);
+            return String::from_utf8(bytes).ok();
+        }
+        bytes.extend_from_slice(page);
+        if bytes.len() > max_len {
+            return None;
+        }
+        addr = addr.checked_add(len)?;
+    }
+}
+
+/// 把 `data` 写到调用者的用户地址 `uaddr` 起。
+///
+/// 写时复制页和按需分配区域中的页先像用户写缺页一样处理。
+/// 任何一页不可写时一个字节也不写，返回 `false`。
+pub fn copy_to_user(caller: Caller, uaddr: usize, data: &[u8]) -> bool {
+    let Some(process) = (unsafe { PROCESSES.get_mut(caller.entity) }) else {
+        return false;
+    };
+    let Some(range) = user_range(uaddr, data.len()) else {
+        return false;
+    };
+    if !data.is_empty() {
+        let first = uaddr >> VmMode::PAGE_BITS;
+        let last = (uaddr + data.len() - 1) >> VmMode::PAGE_BITS;
+        for page in first..=last {
+            let addr = VAddr::<VmMode>::new(page << VmMode::PAGE_BITS).val();
+            let writable = process
+                .address_space
+                .translate::<u8>(VAddr::new(addr), WRITABLE)
+                .is_some();
+            if !(writable || process.break_cow(addr) || process.fill_lazy(addr)) {
+                return false;
+            }
+        }
+    }
+    let Some(segments) = process.address_space.translate_range(range, WRITABLE) else {
+        return false;
+    };
+    let mut data = data;
+    for (ptr, len) in segments {
+        let (head, rest) = data.split_at(len);
+        unsafe { core::slice::from_raw_parts_mut(ptr.as_ptr(), len) }.copy_from_slice(head);
+        data = rest;
+    }
+    true
+}
+
+/// 从调用者的用户地址 `uaddr` 读出一个 `T`，`uaddr` 不必对齐。
+///
+/// # Safety
+///
+/// `T` 的任何位模式都必须是合法的值。
+pub unsafe fn get_user<T: Copy>(caller: Caller, uaddr: usize) -> Option<T> {
+    let bytes = copy_from_user(caller, uaddr, size_of::<T>())?;
+    Some(bytes.as_ptr().cast::<T>().read_unaligned())
+}
+
+/// 把 `value` 写到调用者的用户地址 `uaddr`，`uaddr` 不必对齐。
+///
+/// # Safety
+///
+/// `T` 不能有填充字节。
+pub unsafe fn put_user<T: Copy>(caller: Caller, uaddr: usize, value: T) -> bool {
+    let bytes = core::slice::from_raw_parts((&value as *const T).cast::<u8>(), size_of::<T>());
+    copy_to_user(caller, uaddr, bytes)
+}
@@ -0,0 +1,50 @@
+//! 调试串口。
+//!
+//! 编译时用环境变量 `DEBUG_UART` 指定第二个 16550 串口的基地址（十六进制，如 `0x10001000`），
+//! 内核日志和用户程序写到 `STDDEBUG` 的内容从这个串口输出，程序的 `STDOUT` 仍然走 SBI 控制台。
+//! 不指定时两者合并到同一个控制台。
+
+/// QEMU virt 主串口的基地址，SBI 控制台就是它。
+pub const CONSOLE_UART: usize = 0x1000_0000;
+
+/// 调试串口的基地址。
+pub const DEBUG_UART: Option<usize> = parse_base(option_env!("DEBUG_UART"));
+
+/// 解析 `0x` 开头的十六进制地址，格式错误时编译失败。
+const fn parse_base(env: Option<&str>) -> Option<usize> {
+    let s = match env {
+        Some(s) => s.as_bytes(),
+        None => return None,
+    };
+    assert!(
+        s.len() > 2 && s[0] == b'0' && (s[1] == b'x' || s[1] == b'X'),
+        "DEBUG_UART should be a hex address like 0x10001000"
+    );
+    let mut base = 0usize;
+    let mut i = 2;
+    while i < s.len() {
+        let digit = match s[i] {
+            b'0'..=b'9' => s[i] - b'0',
+            b'a'..=b'f' => s[i] - b'a' + 10,
+            b'A'..=b'F' => s[i] - b'A' + 10,
+            b'_' => {
+                i += 1;
+                continue;
+            }
+            _ => panic!("DEBUG_UART should be a hex address like 0x10001000"),
+        };
+        base = base * 16 + digit as usize;
+        i += 1;
+    }
+    Some(base)
+}
+
+/// 向 `base` 处的 16550 串口写一个字节，发送寄存器满时忙等。
+pub fn putchar(base: usize, c: u8) {
+    const THR: usize = 0; // Transmit Holding Register
+    const LSR: usize = 5; // Line Status Register
+    unsafe {
+        while ((base + LSR) as *const u8).read_volatile() & 0x20 == 0 {}
+        ((base + THR) as *mut u8).write_volatile(c);
+    }
+}
@@ -11,7 +11,7 @@ extern crate rcore_console;
 extern crate alloc;
 
 use alloc::{alloc::alloc, collections::BTreeMap};
-use core::{alloc::Layout, ffi::CStr, mem::MaybeUninit};
+use core::{alloc::Layout, mem::MaybeUninit};
 use impls::{Console, Sv39Manager, SyscallContext};
 use kernel_context::foreign::MultislotPortal;
 use kernel_vm::{
@@ -25,7 +25,7 @@ use rcore_task_manage::ProcId;
 use riscv::register::*;
 use sbi_rt::*;
 use spin::Lazy;
-use syscall::Caller;
+use syscall::{Caller, RUsage};
 use xmas_elf::ElfFile;
 
 // 应用程序内联进来。
@@ -38,22 +38,11 @@ const MEMORY: usize = 48 << 20;
 const PROTAL_TRANSIT: VPN<Sv39> = VPN::MAX;
 // 内核地址空间。
 static mut KERNEL_SPACE: MaybeUninit<AddressSpace<Sv39, Sv39Manager>> = MaybeUninit::uninit();
+// 已退出、等待父进程回收的进程的资源使用统计。
+static mut EXITED_USAGE: BTreeMap<ProcId, RUsage> = BTreeMap::new();
 /// 加载用户进程。
-static APPS: Lazy<BTreeMap<&'static str, &'static [u8]>> = Lazy::new(|| {
-    extern "C" {
-        static app_names: u8;
-    }
-    unsafe {
-        linker::AppMeta::locate()
-            .iter()
-            .scan(&app_names as *const _ as usize, |addr, data| {
-                let name = CStr::from_ptr(*addr as _).to_str().unwrap();
-                *addr += name.as_bytes().len() + 1;
-                Some((name, data))
-            })
-    }
-    .collect()
-});
+static APPS: Lazy<BTreeMap<&'static str, &'static [u8]>> =
+    Lazy::new(|| linker::AppMeta::locate().named().collect());
 
 extern "C" fn rust_main() -> ! {
     let layout = linker::KernelLayout::locate();
@@ -87,7 +76,7 @@ extern "C" fn rust_main() -> ! {
     syscall::init_clock(&SyscallContext);
     // 加载初始进程
     let initproc_data = APPS.get("initproc").unwrap();
-    if let Some(process) = Process::from_elf(ElfFile::new(initproc_data).unwrap()) {
+    if let Some(process) = Process::from_elf("initproc", ElfFile::new(initproc_data).unwrap()) {
         unsafe {
             PROCESSOR.set_manager(ProcManager::new());
             PROCESSOR.add(process.pid, process, ProcId::from_usize(usize::MAX));
@@ -95,7 +84,9 @@ extern "C" fn rust_main() -> ! {
     }
     loop {
         if let Some(task) = unsafe { PROCESSOR.find_next() } {
+            let start = time::read();
             unsafe { task.context.execute(portal, ()) };
+            task.cpu_time += (time::read() - start) * 10000 / 125;
             match scause::read().cause() {
                 scause::Trap::Exception(scause::Exception::UserEnvCall) => {
                     use syscall::{SyscallId as Id, SyscallResult as Ret};
@@ -105,7 +96,7 @@ extern "C" fn rust_main() -> ! {
                     let args = [ctx.a(0), ctx.a(1), ctx.a(2), ctx.a(3), ctx.a(4), ctx.a(5)];
                     match syscall::handle(Caller { entity: 0, flow: 0 }, id, args) {
                         Ret::Done(ret) => match id {
-                            Id::EXIT => unsafe { PROCESSOR.make_current_exited(ret) },
+                            Id::EXIT => exit_current(task, ret),
                             _ => {
                                 let ctx = &mut task.context.context;
                                 *ctx.a_mut(0) = ret as _;
@@ -113,14 +104,14 @@ extern "C" fn rust_main() -> ! {
                             }
                         },
                         Ret::Unsupported(_) => {
-                            log::info!("id = {id:?}");
-                            unsafe { PROCESSOR.make_current_exited(-2) };
+                            log::info!("{task} unsupported syscall: id = {id:?}");
+                            exit_current(task, -2);
                         }
                     }
                 }
                 e => {
-                    log::error!("unsupported trap: {e:?}");
-                    unsafe { PROCESSOR.make_current_exited(-3) };
+                    log::error!("{task} unsupported trap: {e:?}");
+                    exit_current(task, -3);
                 }
             }
         } else {
@@ -132,10 +123,19 @@ extern "C" fn rust_main() -> ! {
     unreachable!()
 }
 
+/// 记下当前进程的资源使用统计留给父进程回收，然后结束当前进程。
+fn exit_current(task: &Process, exit_code: isize) {
+    unsafe {
+        EXITED_USAGE.insert(task.pid, task.rusage());
+        PROCESSOR.make_current_exited(exit_code);
+    }
+}
+
 /// Rust 异常处理函数，以异常方式关机。
 #[panic_handler]
 fn panic(info: &core::panic::PanicInfo) -> ! {
     println!("{info}");
+    rcore_console::flush();
     system_reset(Shutdown, SystemFailure);
     loop {}
 }
@@ -184,12 +184,12 @@ fn map_portal(space: &AddressSpace<Sv39, Sv39Manager>) {
 
 /// 各种接口库的实现。
 mod impls {
-    use crate::{APPS, PROCESSOR};
-    use alloc::alloc::alloc_zeroed;
-    use core::{alloc::Layout, ptr::NonNull};
+    use crate::{APPS, EXITED_USAGE, PROCESSOR};
+    use alloc::{alloc::alloc_zeroed, string::String, vec::Vec};
+    use core::{alloc::Layout, ops::Range, ptr::NonNull};
     use kernel_vm::{
         page_table::{MmuMeta, Pte, Sv39, VAddr, VmFlags, PPN, VPN},
-        PageManager,
+        AddressSpace, PageManager,
     };
     use rcore_console::log;
     use rcore_task_manage::ProcId;
@@ -305,20 +305,18 @@ mod impls {
         fn read(&self, _caller: Caller, fd: usize, buf: usize, count: usize) -> isize {
             if fd == STDIN {
                 const WRITEABLE: VmFlags<Sv39> = VmFlags::build_from_str("W_V");
-                if let Some(mut ptr) = unsafe { PROCESSOR.current().unwrap() }
-                    .address_space
-                    .translate(VAddr::new(buf), WRITEABLE)
+                let current = unsafe { PROCESSOR.current().unwrap() };
+                // 用户缓冲区可以跨页，先检查每一页都可写再等待输入
+                if let Some(segments) = user_range(buf, count)
+                    .and_then(|range| current.address_space.translate_range(range, WRITEABLE))
                 {
-                    let mut ptr = unsafe { ptr.as_mut() } as *mut u8;
-                    for _ in 0..count {
-                        #[allow(deprecated)]
-                        let c = sbi_rt::legacy::console_getchar() as u8;
-                        unsafe {
-                            *ptr = c;
-                            ptr = ptr.add(1);
-                        }
-                    }
-                    count as _
+                    let buf = segments
+                        .into_iter()
+                        .map(|(ptr, len)| unsafe {
+                            core::slice::from_raw_parts_mut(ptr.as_ptr(), len)
+                        })
+                        .collect();
+                    read_console(buf, current.termios) as _
                 } else {
                     log::error!("ptr not writeable");
                     -1
@@ -328,6 +326,120 @@ mod impls {
                 -1
             }
         }
+
+        fn ioctl(&self, _caller: Caller, fd: usize, request: usize, arg: usize) -> isize {
+            const READABLE: VmFlags<Sv39> = VmFlags::build_from_str("RV");
+            const WRITEABLE: VmFlags<Sv39> = VmFlags::build_from_str("W_V");
+            let current = unsafe { PROCESSOR.current().unwrap() };
+            if fd > STDDEBUG {
+                log::error!("fd {fd} is not a terminal");
+                return -1;
+            }
+            match request {
+                TCGETS => {
+                    if let Some(mut ptr) =
+                        current.address_space.translate(VAddr::new(arg), WRITEABLE)
+                    {
+                        unsafe { *ptr.as_mut() = current.termios };
+                        0
+                    } else {
+                        log::error!("ptr not writeable");
+                        -1
+                    }
+                }
+                TCSETS => {
+                    if let Some(ptr) = current.address_space.translate(VAddr::new(arg), READABLE) {
+                        current.termios = unsafe { *ptr.as_ref() };
+                        0
+                    } else {
+                        log::error!("ptr not readable");
+                        -1
+                    }
+                }
+                _ => {
+                    log::error!("unsupported ioctl request: {request:#x}");
+                    -1
+                }
+            }
+        }
+    }
+
+    /// 从控制台读入用户缓冲区 `buf`，返回读到的字节数，行编辑见 [`Termios::read_console`]。
+    ///
+    /// 先读进内核缓冲区再依次复制到用户缓冲区的各段，行编辑不受页边界影响。一次最多读入一页。
+    #[allow(deprecated)]
+    fn read_console(buf: Vec<&mut [u8]>, termios: Termios) -> usize {
+        let count = buf.iter().map(|b| b.len()).sum::<usize>();
+        let mut line = alloc::vec![0u8; count.min(1 << Sv39::PAGE_BITS)];
+        let len = termios.read_console(&mut line, sbi_rt::legacy::console_getchar, |c| {
+            sbi_rt::legacy::console_putchar(c as _);
+        });
+        let mut data = &line[..len];
+        for b in buf {
+            let n = b.len().min(data.len());
+            b[..n].copy_from_slice(&data[..n]);
+            data = &data[n..];
+        }
+        len
+    }
+
+    /// 用户缓冲区 `buf..buf + len` 对应的虚地址范围，首尾字节不是合法的虚地址时返回 `None`。
+    fn user_range(buf: usize, len: usize) -> Option<Range<VAddr<Sv39>>> {
+        let end = buf.checked_add(len)?;
+        let valid = |addr: usize| VAddr::<Sv39>::new(addr).val() == addr;
+        (valid(buf) && (len == 0 || valid(end - 1))).then(|| VAddr::new(buf)..VAddr::new(end))
+    }
+
+    /// 把 `data` 写到 `address_space` 中的用户地址 `uaddr` 起。
+    ///
+    /// 缓冲区可以跨页，逐页翻译，任何一页没有映射或不可写时一个字节也不写，返回 `false`。
+    fn copy_to_user(
+        address_space: &AddressSpace<Sv39, Sv39Manager>,
+        uaddr: usize,
+        data: &[u8],
+    ) -> bool {
+        const WRITABLE: VmFlags<Sv39> = VmFlags::build_from_str("W_V");
+        let Some(segments) = user_range(uaddr, data.len())
+            .and_then(|range| address_space.translate_range(range, WRITABLE))
+        else {
+            return false;
+        };
+        let mut data = data;
+        for (ptr, len) in segments {
+            let (head, rest) = data.split_at(len);
+            unsafe { core::slice::from_raw_parts_mut(ptr.as_ptr(), len) }.copy_from_slice(head);
+            data = rest;
+        }
+        true
+    }
+
+    /// 从 `address_space` 中的用户地址 `uaddr` 读出字符串，读到 NUL 或满 `max_len` 字节为止。
+    ///
+    /// 结果不含 NUL。逐页翻译，只要求实际读到的页可读，遇到不可读的页时返回 `None`。
+    fn read_user_str(
+        address_space: &AddressSpace<Sv39, Sv39Manager>,
+        uaddr: usize,
+        max_len: usize,
+    ) -> Option<Vec<u8>> {
+        const READABLE: VmFlags<Sv39> = VmFlags::build_from_str("RV");
+        const PAGE_SIZE: usize = 1 << Sv39::PAGE_BITS;
+        let mut bytes = Vec::new();
+        let mut addr = uaddr;
+        while bytes.len() < max_len {
+            if VAddr::<Sv39>::new(addr).val() != addr {
+                return None;
+            }
+            let ptr = address_space.translate::<u8>(VAddr::new(addr), READABLE)?;
+            let len = (PAGE_SIZE - addr % PAGE_SIZE).min(max_len - bytes.len());
+            let page = unsafe { core::slice::from_raw_parts(ptr.as_ptr(), len) };
+            if let Some(n) = page.iter().position(|&c| c == 0) {
+                bytes.extend_from_slice(&page[..n]);
+                break;
+            }
+            bytes.extend_from_slice(page);
+            addr = addr.checked_add(len)?;
+        }
+        Some(bytes)
     }
 
     impl Process for SyscallContext {
@@ -338,10 +450,12 @@ mod impls {
 
         fn fork(&self, _caller: Caller) -> isize {
             let current = unsafe { PROCESSOR.current().unwrap() };
-            let mut child_proc = current.fork().unwrap();
+            if !current.may_spawn {
+                log::error!("{current} is not allowed to spawn");
+                return -1;
+            }
+            let child_proc = current.fork().unwrap();
             let pid = child_proc.pid;
-            let context = &mut child_proc.context.context;
-            *context.a_mut(0) = 0 as _;
             unsafe {
                 PROCESSOR.add(pid, child_proc, current.pid);
             }
@@ -357,8 +471,8 @@ mod impls {
                 .map(|ptr| unsafe {
                     core::str::from_utf8_unchecked(core::slice::from_raw_parts(ptr.as_ptr(), count))
                 })
-                .and_then(|name| APPS.get(name))
-                .and_then(|input| ElfFile::new(input).ok())
+                .and_then(|name| APPS.get_key_value(name))
+                .and_then(|(name, input)| Some((*name, ElfFile::new(input).ok()?)))
                 .map_or_else(
                     || {
                         log::error!("unknown app, select one in the list: ");
@@ -366,24 +480,63 @@ mod impls {
                         println!();
                         -1
                     },
-                    |data| {
-                        current.exec(data);
+                    |(name, data)| {
+                        current.exec(name, data);
                         0
                     },
                 )
         }
 
-        fn wait(&self, _caller: Caller, pid: isize, exit_code_ptr: usize) -> isize {
+        fn wait4(
+            &self,
+            _caller: Caller,
+            pid: isize,
+            exit_code_ptr: usize,
+            options: usize,
+            rusage: usize,
+        ) -> isize {
             let current = unsafe { PROCESSOR.current().unwrap() };
             const WRITABLE: VmFlags<Sv39> = VmFlags::build_from_str("W_V");
+            if options & !WNOHANG != 0 {
+                return -1;
+            }
+            // 回收子进程之前检查输出参数，不可写时返回 -1，子进程留给下一次等待；
+            // 退出码按用户态的 `i32` 写回
+            let writable = |ptr: usize, len: usize| {
+                ptr == 0
+                    || user_range(ptr, len)
+                        .and_then(|range| current.address_space.translate_range(range, WRITABLE))
+                        .is_some()
+            };
+            if !writable(exit_code_ptr, core::mem::size_of::<i32>())
+                || !writable(rusage, core::mem::size_of::<RUsage>())
+            {
+                log::error!("ptr not writeable");
+                return -1;
+            }
             if let Some((dead_pid, exit_code)) =
                 unsafe { PROCESSOR.wait(ProcId::from_usize(pid as usize)) }
             {
-                if let Some(mut ptr) = current
-                    .address_space
-                    .translate(VAddr::new(exit_code_ptr), WRITABLE)
-                {
-                    unsafe { *ptr.as_mut() = exit_code };
+                if dead_pid.get_usize() as isize == -2 {
+                    // 子进程还在运行
+                    return if options & WNOHANG != 0 { 0 } else { -2 };
+                }
+                if exit_code_ptr != 0 {
+                    copy_to_user(
+                        &current.address_space,
+                        exit_code_ptr,
+                        &(exit_code as i32).to_ne_bytes(),
+                    );
+                }
+                let usage = unsafe { EXITED_USAGE.remove(&dead_pid) }.unwrap_or_default();
+                if rusage != 0 {
+                    let bytes = unsafe {
+                        core::slice::from_raw_parts(
+                            &usage as *const RUsage as *const u8,
+                            core::mem::size_of::<RUsage>(),
+                        )
+                    };
+                    copy_to_user(&current.address_space, rusage, bytes);
                 }
                 return dead_pid.get_usize() as _;
             } else {
@@ -396,11 +549,125 @@ mod impls {
             let current = unsafe { PROCESSOR.current().unwrap() };
             current.pid.get_usize() as _
         }
+
+        /// 单用户系统，所有进程都以 root 身份运行。
+        #[inline]
+        fn getuid(&self, _caller: Caller) -> isize {
+            0
+        }
+
+        #[inline]
+        fn geteuid(&self, _caller: Caller) -> isize {
+            0
+        }
+
+        #[inline]
+        fn getgid(&self, _caller: Caller) -> isize {
+            0
+        }
+
+        #[inline]
+        fn getegid(&self, _caller: Caller) -> isize {
+            0
+        }
+
+        fn ps(&self, _caller: Caller, buf: usize, len: usize) -> isize {
+            use core::fmt::Write;
+            let current = unsafe { PROCESSOR.current_id() };
+            let rel = unsafe { PROCESSOR.iter_rel() }.collect::<Vec<_>>();
+            let mut out = String::new();
+            writeln!(out, "  PID  PPID STATE       TIME NAME").unwrap();
+            for (pid, ppid) in rel {
+                let Some(task) = (unsafe { PROCESSOR.get_task(pid) }) else {
+                    continue;
+                };
+                // 初始进程的父进程号是 `usize::MAX`，显示为 0
+                let ppid = match ppid.get_usize() {
+                    usize::MAX => 0,
+                    ppid => ppid,
+                };
+                let state = if Some(pid) == current {
+                    "running"
+                } else {
+                    "ready"
+                };
+                writeln!(
+                    out,
+                    "{:>5} {ppid:>5} {state:<7} {:>6}ms {}",
+                    pid.get_usize(),
+                    task.cpu_time / 1_000_000,
+                    task.name,
+                )
+                .unwrap();
+            }
+            if out.len() <= len {
+                let process = unsafe { PROCESSOR.current() }.unwrap();
+                if !copy_to_user(&process.address_space, buf, out.as_bytes()) {
+                    log::error!("ptr not writeable");
+                    return -1;
+                }
+            }
+            out.len() as _
+        }
+
+        fn prctl(&self, _caller: Caller, option: usize, arg2: usize) -> isize {
+            let current = unsafe { PROCESSOR.current().unwrap() };
+            match option {
+                PR_SET_NAME => {
+                    // 超过 `TASK_COMM_LEN - 1` 的部分被截断
+                    let Some(buf) = read_user_str(&current.address_space, arg2, TASK_COMM_LEN - 1)
+                    else {
+                        log::error!("ptr not readable");
+                        return -1;
+                    };
+                    match core::str::from_utf8(&buf) {
+                        Ok(name) => {
+                            log::info!("{current} renamed to {name:?}");
+                            current.name = String::from(name);
+                            0
+                        }
+                        Err(_) => -1,
+                    }
+                }
+                PR_GET_NAME => {
+                    let mut buf = [0u8; TASK_COMM_LEN];
+                    let name = current.name.as_bytes();
+                    let len = name.len().min(TASK_COMM_LEN - 1);
+                    buf[..len].copy_from_slice(&name[..len]);
+                    if !copy_to_user(&current.address_space, arg2, &buf) {
+                        log::error!("ptr not writeable");
+                        return -1;
+                    }
+                    0
+                }
+                PR_SET_SPAWN => match (current.may_spawn, arg2 != 0) {
+                    (_, false) => {
+                        current.may_spawn = false;
+                        0
+                    }
+                    (true, true) => 0,
+                    // 清除之后不能重新获得
+                    (false, true) => -1,
+                },
+                PR_GET_SPAWN => current.may_spawn as _,
+                _ => {
+                    log::error!("unsupported prctl option: {option}");
+                    -1
+                }
+            }
+        }
     }
 
     impl Scheduling for SyscallContext {
+        /// 有其他任务等待调度、让出之后会切换时返回 1，调用者是唯一就绪的任务时返回 0。
         #[inline]
         fn sched_yield(&self, _caller: Caller) -> isize {
+            unsafe { PROCESSOR.has_ready() as _ }
+        }
+
+        fn yield_to(&self, _caller: Caller, pid: usize) -> isize {
+            // 目标不在就绪队列中时退化为普通的 yield
+            unsafe { PROCESSOR.schedule_next(ProcId::from_usize(pid)) };
             0
         }
     }
@@ -429,5 +696,32 @@ mod impls {
                 _ => -1,
             }
         }
+
+        /// 没有实时时钟，返回启动以来的时间；`tz` 非空时总是填入 UTC。
+        fn gettimeofday(&self, _caller: Caller, tv: usize, tz: usize) -> isize {
+            const WRITABLE: VmFlags<Sv39> = VmFlags::build_from_str("W_V");
+            let current = unsafe { PROCESSOR.current().unwrap() };
+            if tv != 0 {
+                let Some(mut ptr) = current.address_space.translate(VAddr::new(tv), WRITABLE)
+                else {
+                    log::error!("ptr not writeable");
+                    return -1;
+                };
+                let time = riscv::register::time::read() * 10000 / 125;
+                *unsafe { ptr.as_mut() } = TimeVal {
+                    tv_sec: time / 1_000_000_000,
+                    tv_usec: time % 1_000_000_000 / 1_000,
+                };
+            }
+            if tz != 0 {
+                let Some(mut ptr) = current.address_space.translate(VAddr::new(tz), WRITABLE)
+                else {
+                    log::error!("ptr not writeable");
+                    return -1;
+                };
+                *unsafe { ptr.as_mut() } = TimeZone::UTC;
+            }
+            0
+        }
     }
 }
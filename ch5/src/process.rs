@@ -1,13 +1,14 @@
 use crate::{map_portal, Sv39Manager};
-use alloc::alloc::alloc_zeroed;
+use alloc::{alloc::alloc_zeroed, string::String};
 use core::alloc::Layout;
-use core::str::FromStr;
+use core::{fmt, str::FromStr};
 use kernel_context::{foreign::ForeignContext, LocalContext};
 use kernel_vm::{
     page_table::{MmuMeta, Sv39, VAddr, VmFlags, PPN, VPN},
     AddressSpace,
 };
 use rcore_task_manage::ProcId;
+use syscall::{RUsage, Termios, TimeVal};
 use xmas_elf::{
     header::{self, HeaderPt2, Machine},
     program, ElfFile,
@@ -17,14 +18,23 @@ use xmas_elf::{
 pub struct Process {
     /// 不可变
     pub pid: ProcId,
+    /// 进程名，默认是应用程序名。
+    pub name: String,
+    /// 累计在用户态运行的时间（纳秒）。
+    pub cpu_time: usize,
     /// 可变
     pub context: ForeignContext,
     pub address_space: AddressSpace<Sv39, Sv39Manager>,
+    /// 控制台的终端属性，标准输入输出共用
+    pub termios: Termios,
+    /// 能否创建子进程，见 [`PR_SET_SPAWN`](syscall::PR_SET_SPAWN)
+    pub may_spawn: bool,
 }
 
 impl Process {
-    pub fn exec(&mut self, elf: ElfFile) {
-        let proc = Process::from_elf(elf).unwrap();
+    pub fn exec(&mut self, name: &str, elf: ElfFile) {
+        let proc = Process::from_elf(name, elf).unwrap();
+        self.name = proc.name;
         self.address_space = proc.address_space;
         self.context = proc.context;
     }
@@ -38,17 +48,41 @@ impl Process {
         parent_addr_space.cloneself(&mut address_space);
         map_portal(&address_space);
         // 复制父进程上下文
-        let context = self.context.context.clone();
+        let context = self.context.context.fork_child();
         let satp = (8 << 60) | address_space.root_ppn().val();
         let foreign_ctx = ForeignContext { context, satp };
         Some(Self {
             pid,
+            name: self.name.clone(),
+            cpu_time: 0,
             context: foreign_ctx,
             address_space,
+            termios: self.termios,
+            may_spawn: self.may_spawn,
         })
     }
 
-    pub fn from_elf(elf: ElfFile) -> Option<Self> {
+    /// 资源使用统计。
+    ///
+    /// 地址空间只增不减，所以当前映射的页数就是最大常驻内存。
+    pub fn rusage(&self) -> RUsage {
+        let pages: usize = self
+            .address_space
+            .areas
+            .iter()
+            .map(|area| area.end.val() - area.start.val())
+            .sum();
+        RUsage {
+            ru_utime: TimeVal {
+                tv_sec: self.cpu_time / 1_000_000_000,
+                tv_usec: self.cpu_time % 1_000_000_000 / 1_000,
+            },
+            ru_maxrss: pages << Sv39::PAGE_BITS >> 10,
+            ..RUsage::default()
+        }
+    }
+
+    pub fn from_elf(name: &str, elf: ElfFile) -> Option<Self> {
         let entry = match elf.header.pt2 {
             HeaderPt2::Header64(pt2)
                 if pt2.type_.as_type() == header::Type::Executable
@@ -111,8 +145,18 @@ impl Process {
         *context.sp_mut() = 1 << 38;
         Some(Self {
             pid: ProcId::new(),
+            name: String::from(name),
+            cpu_time: 0,
             context: ForeignContext { context, satp },
             address_space,
+            termios: Termios::COOKED,
+            may_spawn: true,
         })
     }
 }
+
+impl fmt::Display for Process {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "process {:?} (pid {})", self.name, self.pid.get_usize())
+    }
+}
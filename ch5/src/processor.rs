@@ -49,4 +49,18 @@ impl Schedule<ProcId> for ProcManager {
     fn fetch(&mut self) -> Option<ProcId> {
         self.ready_queue.pop_front()
     }
+    /// 调度队列是否为空
+    fn is_empty(&self) -> bool {
+        self.ready_queue.is_empty()
+    }
+    /// 把 id 提到调度队列的队首
+    fn prioritize(&mut self, id: ProcId) -> bool {
+        if let Some(i) = self.ready_queue.iter().position(|&x| x == id) {
+            self.ready_queue.remove(i);
+            self.ready_queue.push_front(id);
+            true
+        } else {
+            false
+        }
+    }
 }
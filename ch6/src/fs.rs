@@ -11,6 +11,17 @@ pub struct FileSystem {
     root: Inode,
 }
 
+impl FileSystem {
+    /// 找到 `path` 的父目录，并返回最后一项的名字。
+    fn split<'a>(&self, path: &'a str) -> Option<(Arc<Inode>, &'a str)> {
+        let path = path.trim_end_matches('/');
+        let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+        self.find(dir)
+            .filter(|dir| dir.is_dir())
+            .map(|dir| (dir, name))
+    }
+}
+
 impl FSManager for FileSystem {
     fn open(&self, path: &str, flags: OpenFlags) -> Option<Arc<FileHandle>> {
         let (readable, writable) = flags.read_write();
@@ -21,8 +32,8 @@ impl FSManager for FileSystem {
                 Some(Arc::new(FileHandle::new(readable, writable, inode)))
             } else {
                 // Create new file
-                self.root
-                    .create(path)
+                let (dir, name) = self.split(path)?;
+                dir.create(name)
                     .map(|new_inode| Arc::new(FileHandle::new(readable, writable, new_inode)))
             }
         } else {
@@ -36,11 +47,13 @@ impl FSManager for FileSystem {
     }
 
     fn find(&self, path: &str) -> Option<Arc<Inode>> {
-        self.root.find(path)
+        self.root.find_path(path)
     }
 
-    fn readdir(&self, _path: &str) -> Option<alloc::vec::Vec<String>> {
-        Some(self.root.readdir())
+    fn readdir(&self, path: &str) -> Option<alloc::vec::Vec<String>> {
+        self.find(path)
+            .filter(|dir| dir.is_dir())
+            .map(|dir| dir.readdir())
     }
 
     fn link(&self, _src: &str, _dst: &str) -> isize {
@@ -50,6 +63,27 @@ impl FSManager for FileSystem {
     fn unlink(&self, _path: &str) -> isize {
         unimplemented!()
     }
+
+    /// 目录不能移到它自己或它的子目录下，由 [`Inode::rename`] 沿着 `..` 检查，这时返回 -1。
+    fn rename(&self, src: &str, dst: &str) -> isize {
+        match (self.split(src), self.split(dst)) {
+            (Some((src_dir, src_name)), Some((dst_dir, dst_name)))
+                if src_dir.rename(src_name, &dst_dir, dst_name) =>
+            {
+                0
+            }
+            _ => -1,
+        }
+    }
+
+    fn mkdir(&self, path: &str) -> isize {
+        if self.find(path).is_some() {
+            return -1;
+        }
+        self.split(path)
+            .and_then(|(dir, name)| dir.create_dir(name))
+            .map_or(-1, |_| 0)
+    }
 }
 
 pub fn read_all(fd: Arc<FileHandle>) -> Vec<u8> {
@@ -127,6 +127,7 @@ extern "C" fn rust_main() -> ! {
 #[panic_handler]
 fn panic(info: &core::panic::PanicInfo) -> ! {
     println!("{info}");
+    rcore_console::flush();
     system_reset(Shutdown, SystemFailure);
     loop {}
 }
@@ -323,18 +324,25 @@ mod impls {
 
         fn read(&self, _caller: Caller, fd: usize, buf: usize, count: usize) -> isize {
             let current = unsafe { PROCESSOR.current().unwrap() };
+            if fd == STDIN {
+                // 用户缓冲区可以跨页，先检查每一页都可写再等待输入
+                let segments = buf.checked_add(count).and_then(|end| {
+                    current
+                        .address_space
+                        .translate_range(VAddr::new(buf)..VAddr::new(end), WRITEABLE)
+                });
+                let Some(segments) = segments else {
+                    log::error!("ptr not writeable");
+                    return -1;
+                };
+                let buf = segments
+                    .into_iter()
+                    .map(|(ptr, len)| unsafe { core::slice::from_raw_parts_mut(ptr.as_ptr(), len) })
+                    .collect();
+                return read_console(buf, current.termios) as _;
+            }
             if let Some(ptr) = current.address_space.translate(VAddr::new(buf), WRITEABLE) {
-                if fd == STDIN {
-                    let mut ptr = ptr.as_ptr();
-                    for _ in 0..count {
-                        #[allow(deprecated)]
-                        unsafe {
-                            *ptr = sbi_rt::legacy::console_getchar() as u8;
-                            ptr = ptr.add(1);
-                        }
-                    }
-                    count as _
-                } else if let Some(file) = &current.fd_table[fd] {
+                if let Some(file) = &current.fd_table[fd] {
                     let mut file = file.lock();
                     if file.readable() {
                         let mut v: Vec<&'static mut [u8]> = Vec::new();
@@ -354,6 +362,40 @@ mod impls {
             }
         }
 
+        fn ioctl(&self, _caller: Caller, fd: usize, request: usize, arg: usize) -> isize {
+            let current = unsafe { PROCESSOR.current().unwrap() };
+            if fd > STDDEBUG {
+                log::error!("fd {fd} is not a terminal");
+                return -1;
+            }
+            match request {
+                TCGETS => {
+                    if let Some(mut ptr) =
+                        current.address_space.translate(VAddr::new(arg), WRITEABLE)
+                    {
+                        unsafe { *ptr.as_mut() = current.termios };
+                        0
+                    } else {
+                        log::error!("ptr not writeable");
+                        -1
+                    }
+                }
+                TCSETS => {
+                    if let Some(ptr) = current.address_space.translate(VAddr::new(arg), READABLE) {
+                        current.termios = unsafe { *ptr.as_ref() };
+                        0
+                    } else {
+                        log::error!("ptr not readable");
+                        -1
+                    }
+                }
+                _ => {
+                    log::error!("unsupported ioctl request: {request:#x}");
+                    -1
+                }
+            }
+        }
+
         fn open(&self, _caller: Caller, path: usize, flags: usize) -> isize {
             // FS.open(, flags)
             let current = unsafe { PROCESSOR.current().unwrap() };
@@ -392,11 +434,33 @@ mod impls {
             if fd >= current.fd_table.len() || current.fd_table[fd].is_none() {
                 return -1;
             }
-            current.fd_table[fd].take();
+            // 关闭文件隐含一次 fsync
+            if let Some(file) = current.fd_table[fd].take() {
+                file.lock().sync();
+            }
             0
         }
     }
 
+    /// 从控制台读入用户缓冲区 `buf`，返回读到的字节数，行编辑见 [`Termios::read_console`]。
+    ///
+    /// 先读进内核缓冲区再依次复制到用户缓冲区的各段，行编辑不受页边界影响。一次最多读入一页。
+    #[allow(deprecated)]
+    fn read_console(buf: Vec<&mut [u8]>, termios: Termios) -> usize {
+        let count = buf.iter().map(|b| b.len()).sum::<usize>();
+        let mut line = alloc::vec![0u8; count.min(1 << Sv39::PAGE_BITS)];
+        let len = termios.read_console(&mut line, sbi_rt::legacy::console_getchar, |c| {
+            sbi_rt::legacy::console_putchar(c as _);
+        });
+        let mut data = &line[..len];
+        for b in buf {
+            let n = b.len().min(data.len());
+            b[..n].copy_from_slice(&data[..n]);
+            data = &data[n..];
+        }
+        len
+    }
+
     impl Process for SyscallContext {
         #[inline]
         fn exit(&self, _caller: Caller, exit_code: usize) -> isize {
@@ -405,10 +469,8 @@ mod impls {
 
         fn fork(&self, _caller: Caller) -> isize {
             let current = unsafe { PROCESSOR.current().unwrap() };
-            let mut child_proc = current.fork().unwrap();
+            let child_proc = current.fork().unwrap();
             let pid = child_proc.pid;
-            let context = &mut child_proc.context.context;
-            *context.a_mut(0) = 0 as _;
             unsafe {
                 PROCESSOR.add(pid, child_proc, current.pid);
             }
@@ -465,12 +527,34 @@ mod impls {
             let current = unsafe { PROCESSOR.current().unwrap() };
             current.pid.get_usize() as _
         }
+
+        /// 单用户系统，所有进程都以 root 身份运行。
+        #[inline]
+        fn getuid(&self, _caller: Caller) -> isize {
+            0
+        }
+
+        #[inline]
+        fn geteuid(&self, _caller: Caller) -> isize {
+            0
+        }
+
+        #[inline]
+        fn getgid(&self, _caller: Caller) -> isize {
+            0
+        }
+
+        #[inline]
+        fn getegid(&self, _caller: Caller) -> isize {
+            0
+        }
     }
 
     impl Scheduling for SyscallContext {
+        /// 有其他任务等待调度、让出之后会切换时返回 1，调用者是唯一就绪的任务时返回 0。
         #[inline]
         fn sched_yield(&self, _caller: Caller) -> isize {
-            0
+            unsafe { PROCESSOR.has_ready() as _ }
         }
     }
 
@@ -498,5 +582,32 @@ mod impls {
                 _ => -1,
             }
         }
+
+        /// 没有实时时钟，返回启动以来的时间；`tz` 非空时总是填入 UTC。
+        fn gettimeofday(&self, _caller: Caller, tv: usize, tz: usize) -> isize {
+            const WRITABLE: VmFlags<Sv39> = VmFlags::build_from_str("W_V");
+            let current = unsafe { PROCESSOR.current().unwrap() };
+            if tv != 0 {
+                let Some(mut ptr) = current.address_space.translate(VAddr::new(tv), WRITABLE)
+                else {
+                    log::error!("ptr not writeable");
+                    return -1;
+                };
+                let time = riscv::register::time::read() * 10000 / 125;
+                *unsafe { ptr.as_mut() } = TimeVal {
+                    tv_sec: time / 1_000_000_000,
+                    tv_usec: time % 1_000_000_000 / 1_000,
+                };
+            }
+            if tz != 0 {
+                let Some(mut ptr) = current.address_space.translate(VAddr::new(tz), WRITABLE)
+                else {
+                    log::error!("ptr not writeable");
+                    return -1;
+                };
+                *unsafe { ptr.as_mut() } = TimeZone::UTC;
+            }
+            0
+        }
     }
 }
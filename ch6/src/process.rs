@@ -9,6 +9,7 @@ use kernel_vm::{
 };
 use rcore_task_manage::ProcId;
 use spin::Mutex;
+use syscall::Termios;
 use xmas_elf::{
     header::{self, HeaderPt2, Machine},
     program, ElfFile,
@@ -23,6 +24,8 @@ pub struct Process {
     pub address_space: AddressSpace<Sv39, Sv39Manager>,
     /// 文件描述符表
     pub fd_table: Vec<Option<Mutex<FileHandle>>>,
+    /// 控制台的终端属性，标准输入输出共用
+    pub termios: Termios,
 }
 
 impl Process {
@@ -41,7 +44,7 @@ impl Process {
         parent_addr_space.cloneself(&mut address_space);
         map_portal(&address_space);
         // 复制父进程上下文
-        let context = self.context.context.clone();
+        let context = self.context.context.fork_child();
         let satp = (8 << 60) | address_space.root_ppn().val();
         let foreign_ctx = ForeignContext { context, satp };
         // 复制父进程文件符描述表
@@ -58,6 +61,7 @@ impl Process {
             context: foreign_ctx,
             address_space,
             fd_table: new_fd_table,
+            termios: self.termios,
         })
     }
 
@@ -132,6 +136,7 @@ impl Process {
                 // Stdout
                 Some(Mutex::new(FileHandle::empty(false, true))),
             ],
+            termios: Termios::COOKED,
         })
     }
 }
@@ -49,4 +49,8 @@ impl Schedule<ProcId> for ProcManager {
     fn fetch(&mut self) -> Option<ProcId> {
         self.ready_queue.pop_front()
     }
+    /// 调度队列是否为空
+    fn is_empty(&self) -> bool {
+        self.ready_queue.is_empty()
+    }
 }
@@ -11,6 +11,7 @@ use rcore_task_manage::ProcId;
 use signal::Signal;
 use signal_impl::SignalImpl;
 use spin::Mutex;
+use syscall::Termios;
 use xmas_elf::{
     header::{self, HeaderPt2, Machine},
     program, ElfFile,
@@ -26,6 +27,8 @@ pub struct Process {
 
     /// 文件描述符表
     pub fd_table: Vec<Option<Mutex<FileHandle>>>,
+    /// 控制台的终端属性，标准输入输出共用
+    pub termios: Termios,
 
     /// 信号模块
     pub signal: Box<dyn Signal>,
@@ -47,7 +50,7 @@ impl Process {
         parent_addr_space.cloneself(&mut address_space);
         map_portal(&address_space);
         // 复制父进程上下文
-        let context = self.context.context.clone();
+        let context = self.context.context.fork_child();
         let satp = (8 << 60) | address_space.root_ppn().val();
         let foreign_ctx = ForeignContext { context, satp };
         // 复制父进程文件符描述表
@@ -64,6 +67,7 @@ impl Process {
             context: foreign_ctx,
             address_space,
             fd_table: new_fd_table,
+            termios: self.termios,
             signal: self.signal.from_fork(),
         })
     }
@@ -139,6 +143,7 @@ impl Process {
                 // Stdout
                 Some(Mutex::new(FileHandle::empty(false, true))),
             ],
+            termios: Termios::COOKED,
             signal: Box::new(SignalImpl::new()),
         })
     }
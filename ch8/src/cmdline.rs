@@ -0,0 +1,45 @@
+//! 内核命令行。
+//!
+//! 启动时从设备树的 `/chosen/bootargs` 读出以空白分隔的 `key=value` 选项。
+//! QEMU 用 `-append` 设置这个属性。
+//!
+//! 支持的选项：
+//!
+//! - `root=<image>`：根文件系统所在的镜像，见 [`crate::fs::select_root`]。
+
+use alloc::string::{String, ToString};
+use rcore_console::log;
+
+/// 命令行选项，没有给出的项是 `None`。
+#[derive(Default)]
+pub struct Options {
+    /// 根文件系统镜像的名字。
+    pub root: Option<String>,
+}
+
+impl Options {
+    /// 从物理地址 `dtb` 处的设备树解析命令行。
+    ///
+    /// 必须在启用分页之前调用。找不到设备树或 `bootargs` 时返回全部为空的选项。
+    pub fn from_dtb(dtb: usize) -> Self {
+        match unsafe { linker::bootargs(dtb) } {
+            Some(args) => {
+                log::info!("bootargs: {args}");
+                Self::parse(args)
+            }
+            None => Self::default(),
+        }
+    }
+
+    /// 解析命令行，不认识的选项打印警告后忽略。
+    pub fn parse(args: &str) -> Self {
+        let mut ans = Self::default();
+        for arg in args.split_ascii_whitespace() {
+            match arg.split_once('=') {
+                Some(("root", val)) if !val.is_empty() => ans.root = Some(val.to_string()),
+                _ => log::warn!("unknown boot option: {arg}"),
+            }
+        }
+        ans
+    }
+}
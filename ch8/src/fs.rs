@@ -1,16 +1,62 @@
-use crate::virtio_block::BLOCK_DEVICE;
+use crate::virtio_block::{BLOCK_DEVICE, ROOT_SLOT};
 use alloc::{string::String, sync::Arc, vec::Vec};
-use easy_fs::{EasyFileSystem, FSManager, FileHandle, Inode, OpenFlags};
+use core::sync::atomic::Ordering;
+use easy_fs::{EasyFileSystem, FSManager, FileHandle, FsStat, Inode, OpenFlags};
 use spin::Lazy;
 
 pub static FS: Lazy<FileSystem> = Lazy::new(|| FileSystem {
     root: EasyFileSystem::root_inode(&EasyFileSystem::open(BLOCK_DEVICE.clone())),
 });
 
+/// 编译时额外打包的镜像名字，以空白分隔。
+///
+/// 第 0 个 virtio 槽位是默认的 `fs.img`，这些镜像按顺序挂在第 1 个起的槽位上。
+const IMAGES: &str = match option_env!("FS_IMAGES") {
+    Some(images) => images,
+    None => "",
+};
+
+/// 选择名为 `name` 的镜像作为根文件系统，`default` 表示 `fs.img`。
+///
+/// 必须在第一次访问 [`FS`] 之前调用，镜像不存在时返回 `false`。
+pub fn select_root(name: &str) -> bool {
+    let slot = if name == "default" {
+        Some(0)
+    } else {
+        IMAGES
+            .split_ascii_whitespace()
+            .position(|image| image == name)
+            .map(|i| i + 1)
+    };
+    match slot {
+        Some(slot) => {
+            ROOT_SLOT.store(slot, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
 pub struct FileSystem {
     root: Inode,
 }
 
+impl FileSystem {
+    /// 文件系统的容量统计。
+    pub fn stat(&self) -> FsStat {
+        self.root.fs_stat()
+    }
+
+    /// 找到 `path` 的父目录，并返回最后一项的名字。
+    fn split<'a>(&self, path: &'a str) -> Option<(Arc<Inode>, &'a str)> {
+        let path = path.trim_end_matches('/');
+        let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+        self.find(dir)
+            .filter(|dir| dir.is_dir())
+            .map(|dir| (dir, name))
+    }
+}
+
 impl FSManager for FileSystem {
     fn open(&self, path: &str, flags: OpenFlags) -> Option<Arc<FileHandle>> {
         let (readable, writable) = flags.read_write();
@@ -21,8 +67,8 @@ impl FSManager for FileSystem {
                 Some(Arc::new(FileHandle::new(readable, writable, inode)))
             } else {
                 // Create new file
-                self.root
-                    .create(path)
+                let (dir, name) = self.split(path)?;
+                dir.create(name)
                     .map(|new_inode| Arc::new(FileHandle::new(readable, writable, new_inode)))
             }
         } else {
@@ -36,11 +82,13 @@ impl FSManager for FileSystem {
     }
 
     fn find(&self, path: &str) -> Option<Arc<Inode>> {
-        self.root.find(path)
+        self.root.find_path(path)
     }
 
-    fn readdir(&self, _path: &str) -> Option<alloc::vec::Vec<String>> {
-        Some(self.root.readdir())
+    fn readdir(&self, path: &str) -> Option<alloc::vec::Vec<String>> {
+        self.find(path)
+            .filter(|dir| dir.is_dir())
+            .map(|dir| dir.readdir())
     }
 
     fn link(&self, _src: &str, _dst: &str) -> isize {
@@ -50,6 +98,53 @@ impl FSManager for FileSystem {
     fn unlink(&self, _path: &str) -> isize {
         unimplemented!()
     }
+
+    /// 目录不能移到它自己或它的子目录下，由 [`Inode::rename`] 沿着 `..` 检查，这时返回 -1。
+    fn rename(&self, src: &str, dst: &str) -> isize {
+        match (self.split(src), self.split(dst)) {
+            (Some((src_dir, src_name)), Some((dst_dir, dst_name)))
+                if src_dir.rename(src_name, &dst_dir, dst_name) =>
+            {
+                0
+            }
+            _ => -1,
+        }
+    }
+
+    fn mkdir(&self, path: &str) -> isize {
+        if self.find(path).is_some() {
+            return -1;
+        }
+        self.split(path)
+            .and_then(|(dir, name)| dir.create_dir(name))
+            .map_or(-1, |_| 0)
+    }
+}
+
+/// 将 `path` 解释为相对 `cwd` 的路径，返回规范化的绝对路径。
+///
+/// `.` 和 `..` 按字面处理，根目录的 `..` 仍是根目录。
+pub fn join_path(cwd: &str, path: &str) -> String {
+    let mut components = Vec::new();
+    let base = if path.starts_with('/') { "" } else { cwd };
+    for name in base.split('/').chain(path.split('/')) {
+        match name {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            name => components.push(name),
+        }
+    }
+    let mut ans = String::new();
+    for name in components {
+        ans.push('/');
+        ans.push_str(name);
+    }
+    if ans.is_empty() {
+        ans.push('/');
+    }
+    ans
 }
 
 pub fn read_all(fd: Arc<FileHandle>) -> Vec<u8> {
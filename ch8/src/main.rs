@@ -2,9 +2,11 @@
 #![no_main]
 // #![deny(warnings)]
 
+mod cmdline;
 mod fs;
 mod process;
 mod processor;
+mod shm;
 mod virtio_block;
 
 #[macro_use]
@@ -46,7 +48,7 @@ const PROTAL_TRANSIT: VPN<Sv39> = VPN::MAX;
 // 内核地址空间。
 static mut KERNEL_SPACE: MaybeUninit<AddressSpace<Sv39, Sv39Manager>> = MaybeUninit::uninit();
 
-extern "C" fn rust_main() -> ! {
+extern "C" fn rust_main(_hartid: usize, dtb: usize) -> ! {
     let layout = linker::KernelLayout::locate();
     // bss 段清零
     unsafe { layout.zero_bss() };
@@ -62,6 +64,13 @@ extern "C" fn rust_main() -> ! {
             MEMORY - layout.len(),
         ))
     };
+    // 解析命令行，设备树不在内核地址空间里，必须在启用分页之前读
+    let options = cmdline::Options::from_dtb(dtb);
+    if let Some(root) = &options.root {
+        if !fs::select_root(root) {
+            log::warn!("unknown fs image: {root}, fall back to the default one");
+        }
+    }
     // 建立异界传送门
     let portal_size = MultislotPortal::calculate_size(1);
     let portal_layout = Layout::from_size_align(portal_size, 1 << Sv39::PAGE_BITS).unwrap();
@@ -73,6 +82,7 @@ extern "C" fn rust_main() -> ! {
     let portal = unsafe { MultislotPortal::init_transit(PROTAL_TRANSIT.base().val(), 1) };
     // 初始化 syscall
     syscall::init_io(&SyscallContext);
+    syscall::init_memory(&SyscallContext);
     syscall::init_process(&SyscallContext);
     syscall::init_scheduling(&SyscallContext);
     syscall::init_clock(&SyscallContext);
@@ -111,11 +121,11 @@ extern "C" fn rust_main() -> ! {
                     match current_proc.signal.handle_signals(ctx) {
                         // 进程应该结束执行
                         SignalResult::ProcessKilled(exit_code) => unsafe {
-                            PROCESSOR.make_current_exited(exit_code as _)
+                            exit_current(task, exit_code as _)
                         },
                         _ => match syscall_ret {
                             Ret::Done(ret) => match id {
-                                Id::EXIT => unsafe { PROCESSOR.make_current_exited(ret) },
+                                Id::EXIT => unsafe { exit_current(task, ret) },
                                 Id::SEMAPHORE_DOWN | Id::MUTEX_LOCK | Id::CONDVAR_WAIT => {
                                     if ret == -1 {
                                         unsafe { PROCESSOR.make_current_blocked() };
@@ -131,14 +141,14 @@ extern "C" fn rust_main() -> ! {
                             },
                             Ret::Unsupported(_) => {
                                 log::info!("id = {id:?}");
-                                unsafe { PROCESSOR.make_current_exited(-2) };
+                                unsafe { exit_current(task, -2) };
                             }
                         },
                     }
                 }
                 e => {
                     log::error!("unsupported trap: {e:?}");
-                    unsafe { PROCESSOR.make_current_exited(-3) };
+                    unsafe { exit_current(task, -3) };
                 }
             }
         } else {
@@ -151,16 +161,104 @@ extern "C" fn rust_main() -> ! {
     unreachable!()
 }
 
+/// 结束当前线程。
+///
+/// 线程用 `set_tid_address` 登记过地址时，先在它所属进程的地址空间中把这个地址清零，
+/// 等待它结束的线程可以据此得知。登记过健壮锁链表时，给它仍持有的锁标记持有者已退出。
+/// 进程的最后一个线程结束时分离进程映射的共享内存段。
+///
+/// # Safety
+///
+/// `thread` 必须是当前线程。
+unsafe fn exit_current(thread: &Thread, exit_code: isize) {
+    const WRITABLE: VmFlags<Sv39> = VmFlags::build_from_str("U_WV");
+    if thread.robust_list != 0 {
+        release_robust_list(thread);
+    }
+    if thread.clear_child_tid != 0 {
+        let proc = PROCESSOR.get_current_proc().unwrap();
+        match proc
+            .address_space
+            .translate::<u32>(VAddr::new(thread.clear_child_tid), WRITABLE)
+        {
+            Some(ptr) => ptr.as_ptr().write_volatile(0),
+            None => log::warn!("clear_child_tid {:#x} not writable", thread.clear_child_tid),
+        }
+    }
+    let proc = PROCESSOR.get_current_proc().unwrap();
+    if PROCESSOR.get_thread(proc.pid).unwrap().len() == 1 {
+        proc.detach_shm();
+    }
+    PROCESSOR.make_current_exited(exit_code);
+}
+
+/// 遍历退出线程的健壮锁链表，把它仍持有的锁字改成 [`FUTEX_OWNER_DIED`]，保留等待标志。
+///
+/// 链表在用户态，每个指针都在线程所属进程的地址空间中翻译，翻译失败就停止遍历。
+/// 本章没有 futex，等待者在用户态轮询锁字，看到这个标志就知道可以接手。
+///
+/// [`FUTEX_OWNER_DIED`]: syscall::FUTEX_OWNER_DIED
+unsafe fn release_robust_list(thread: &Thread) {
+    use syscall::{
+        RobustListHead, FUTEX_OWNER_DIED, FUTEX_TID_MASK, FUTEX_WAITERS, ROBUST_LIST_LIMIT,
+    };
+    const READABLE: VmFlags<Sv39> = VmFlags::build_from_str("U_RV");
+    const WRITABLE: VmFlags<Sv39> = VmFlags::build_from_str("U_WV");
+    let space = &PROCESSOR.get_current_proc().unwrap().address_space;
+    let tid = thread.tid.get_usize() as u32 & FUTEX_TID_MASK;
+    // 锁字属于这个线程时标记持有者已退出
+    let release = |node: usize, offset: isize| {
+        let addr = node.wrapping_add_signed(offset);
+        match space.translate::<u32>(VAddr::new(addr), WRITABLE) {
+            Some(ptr) => {
+                let word = ptr.as_ptr().read_volatile();
+                if word & FUTEX_TID_MASK == tid {
+                    ptr.as_ptr()
+                        .write_volatile((word & FUTEX_WAITERS) | FUTEX_OWNER_DIED);
+                }
+            }
+            None => log::warn!("robust futex {addr:#x} not writable"),
+        }
+    };
+    let head_addr = thread.robust_list;
+    let Some(head) = space.translate::<RobustListHead>(VAddr::new(head_addr), READABLE) else {
+        log::warn!("robust list head {head_addr:#x} not readable");
+        return;
+    };
+    let head = head.as_ptr().read_volatile();
+    // 最低位是优先级继承锁的标记，这里不区分
+    let mut node = head.list.next & !1;
+    for _ in 0..ROBUST_LIST_LIMIT {
+        if node == head_addr {
+            break;
+        }
+        // 先读出下一项，锁被接手后节点可能马上被改写
+        let Some(next) = space.translate::<usize>(VAddr::new(node), READABLE) else {
+            log::warn!("robust list node {node:#x} not readable");
+            break;
+        };
+        let next = next.as_ptr().read_volatile() & !1;
+        if node != head.list_op_pending {
+            release(node, head.futex_offset);
+        }
+        node = next;
+    }
+    if head.list_op_pending != 0 {
+        release(head.list_op_pending & !1, head.futex_offset);
+    }
+}
+
 /// Rust 异常处理函数，以异常方式关机。
 #[panic_handler]
 fn panic(info: &core::panic::PanicInfo) -> ! {
     println!("{info}");
+    rcore_console::flush();
     system_reset(Shutdown, SystemFailure);
     loop {}
 }
 
 pub const MMIO: &[(usize, usize)] = &[
-    (0x1000_1000, 0x00_1000), // Virtio Block in virt machine
+    (0x1000_1000, 0x00_8000), // Virtio MMIO slots in virt machine, each holds a Virtio Block
 ];
 
 fn kernel_space(layout: linker::KernelLayout, memory: usize, portal: usize) {
@@ -221,17 +319,21 @@ fn map_portal(space: &AddressSpace<Sv39, Sv39Manager>) {
 /// 各种接口库的实现。
 mod impls {
     use crate::{
-        fs::{read_all, FS},
-        Thread, PROCESSOR,
+        fs::{join_path, read_all, FS},
+        shm, Thread, PROCESSOR,
     };
     use alloc::sync::Arc;
     use alloc::{alloc::alloc_zeroed, string::String, vec::Vec};
-    use core::{alloc::Layout, ptr::NonNull};
-    use easy_fs::UserBuffer;
+    use core::{
+        alloc::Layout,
+        ptr::NonNull,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
     use easy_fs::{FSManager, OpenFlags};
+    use easy_fs::{FileHandle, UserBuffer};
     use kernel_vm::{
         page_table::{MmuMeta, Pte, Sv39, VAddr, VmFlags, VmMeta, PPN, VPN},
-        PageManager,
+        AddressSpace, PageManager,
     };
     use rcore_console::log;
     use rcore_task_manage::{ProcId, ThreadId};
@@ -353,18 +455,16 @@ mod impls {
 
         fn read(&self, _caller: Caller, fd: usize, buf: usize, count: usize) -> isize {
             let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            if fd == STDIN {
+                // 用户缓冲区可以跨页，先检查每一页都可写再等待输入
+                let Some(buf) = user_buffer(&current.address_space, buf, count, WRITEABLE) else {
+                    log::error!("ptr not writeable");
+                    return -1;
+                };
+                return read_console(buf, current.termios) as _;
+            }
             if let Some(ptr) = current.address_space.translate(VAddr::new(buf), WRITEABLE) {
-                if fd == STDIN {
-                    let mut ptr = ptr.as_ptr();
-                    for _ in 0..count {
-                        #[allow(deprecated)]
-                        unsafe {
-                            *ptr = sbi_rt::legacy::console_getchar() as u8;
-                            ptr = ptr.add(1);
-                        }
-                    }
-                    count as _
-                } else if let Some(file) = &current.fd_table[fd] {
+                if let Some(file) = &current.fd_table[fd] {
                     let mut file = file.lock();
                     if file.readable() {
                         let mut v: Vec<&'static mut [u8]> = Vec::new();
@@ -384,23 +484,373 @@ mod impls {
             }
         }
 
-        fn open(&self, _caller: Caller, path: usize, flags: usize) -> isize {
-            // FS.open(, flags)
+        fn pread(
+            &self,
+            _caller: Caller,
+            fd: usize,
+            buf: usize,
+            count: usize,
+            offset: usize,
+        ) -> isize {
             let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
-            if let Some(ptr) = current.address_space.translate(VAddr::new(path), READABLE) {
-                let mut string = String::new();
-                let mut raw_ptr: *mut u8 = ptr.as_ptr();
-                loop {
-                    unsafe {
-                        let ch = *raw_ptr;
-                        if ch == 0 {
-                            break;
+            if (offset as isize) < 0 {
+                log::error!("negative offset");
+                return -1;
+            }
+            // 定位读写不改变文件偏移，也不支持标准输入输出
+            let Some(Some(file)) = current.fd_table.get(fd) else {
+                log::error!("unsupported fd: {fd}");
+                return -1;
+            };
+            if let Some(buf) = user_buffer(&current.address_space, buf, count, WRITEABLE) {
+                let file = file.lock();
+                if file.readable() {
+                    file.read_at(offset, buf) as _
+                } else {
+                    log::error!("file not readable");
+                    -1
+                }
+            } else {
+                log::error!("ptr not writeable");
+                -1
+            }
+        }
+
+        fn pwrite(
+            &self,
+            _caller: Caller,
+            fd: usize,
+            buf: usize,
+            count: usize,
+            offset: usize,
+        ) -> isize {
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            if (offset as isize) < 0 {
+                log::error!("negative offset");
+                return -1;
+            }
+            let Some(Some(file)) = current.fd_table.get(fd) else {
+                log::error!("unsupported fd: {fd}");
+                return -1;
+            };
+            if let Some(buf) = user_buffer(&current.address_space, buf, count, READABLE) {
+                let file = file.lock();
+                if file.writable() {
+                    file.write_at(offset, buf) as _
+                } else {
+                    log::error!("file not writable");
+                    -1
+                }
+            } else {
+                log::error!("ptr not readable");
+                -1
+            }
+        }
+
+        fn sendfile(
+            &self,
+            _caller: Caller,
+            out_fd: usize,
+            in_fd: usize,
+            offset: usize,
+            count: usize,
+        ) -> isize {
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            let Some(Some(input)) = current.fd_table.get(in_fd) else {
+                log::error!("unsupported fd: {in_fd}");
+                return -1;
+            };
+            let (inode, mut pos) = {
+                let input = input.lock();
+                match &input.inode {
+                    Some(inode) if input.readable() => (inode.clone(), input.offset),
+                    _ => {
+                        log::error!("fd {in_fd} is not a readable file");
+                        return -1;
+                    }
+                }
+            };
+            // 给出偏移指针时从指针处读，并把新偏移写回，不改变输入文件的偏移
+            let offset_ptr = if offset != 0 {
+                let Some(ptr) = current
+                    .address_space
+                    .translate::<isize>(VAddr::new(offset), WRITEABLE)
+                else {
+                    log::error!("ptr not writeable");
+                    return -1;
+                };
+                let value = unsafe { *ptr.as_ptr() };
+                if value < 0 {
+                    log::error!("negative offset");
+                    return -1;
+                }
+                pos = value as usize;
+                Some(ptr)
+            } else {
+                None
+            };
+            let to_console = out_fd == STDOUT || out_fd == STDDEBUG;
+            let output = if to_console {
+                None
+            } else {
+                match current.fd_table.get(out_fd) {
+                    Some(Some(file)) if is_writable_file(file) => Some(file),
+                    _ => {
+                        log::error!("fd {out_fd} is not a writable file");
+                        return -1;
+                    }
+                }
+            };
+            // 按块在内核里搬运，不经过用户内存
+            let mut block = [0u8; 512];
+            let mut total = 0;
+            while total < count {
+                let len = (count - total).min(block.len());
+                let len = inode.read_at(pos, &mut block[..len]);
+                if len == 0 {
+                    break;
+                }
+                match output {
+                    None => print!("{}", unsafe {
+                        core::str::from_utf8_unchecked(&block[..len])
+                    }),
+                    Some(file) => {
+                        let mut file = file.lock();
+                        let out = file.inode.as_ref().unwrap().clone();
+                        file.offset += out.write_at(file.offset, &block[..len]);
+                    }
+                }
+                pos += len;
+                total += len;
+            }
+            match offset_ptr {
+                Some(ptr) => unsafe { *ptr.as_ptr() = pos as _ },
+                None => input.lock().offset = pos,
+            }
+            total as _
+        }
+
+        /// epoll 实例在描述符表里占一个不可读写的空位，兴趣列表另存在进程里。
+        fn epoll_create(&self, _caller: Caller, flags: usize) -> isize {
+            if flags & !EPOLL_CLOEXEC != 0 {
+                log::error!("unsupported epoll flags: {flags:#x}");
+                return -1;
+            }
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            let mut placeholder = FileHandle::empty(false, false);
+            placeholder.cloexec = flags & EPOLL_CLOEXEC != 0;
+            let epfd = current.fd_table.len();
+            current.fd_table.push(Some(Mutex::new(placeholder)));
+            current.epolls.insert(epfd, Vec::new());
+            epfd as _
+        }
+
+        fn epoll_ctl(
+            &self,
+            _caller: Caller,
+            epfd: usize,
+            op: usize,
+            fd: usize,
+            event: usize,
+        ) -> isize {
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            let opened = fd <= STDDEBUG || current.fd_table.get(fd).is_some_and(Option::is_some);
+            if !opened || current.epolls.contains_key(&fd) {
+                log::error!("fd {fd} cannot be watched");
+                return -1;
+            }
+            let event = if op == EPOLL_CTL_DEL {
+                EpollEvent::default()
+            } else if let Some(ptr) = current
+                .address_space
+                .translate::<EpollEvent>(VAddr::new(event), READABLE)
+            {
+                unsafe { *ptr.as_ptr() }
+            } else {
+                log::error!("ptr not readable");
+                return -1;
+            };
+            let Some(interests) = current.epolls.get_mut(&epfd) else {
+                log::error!("fd {epfd} is not an epoll instance");
+                return -1;
+            };
+            let index = interests
+                .iter()
+                .position(|(registered, _)| *registered == fd);
+            match (op, index) {
+                (EPOLL_CTL_ADD, None) => interests.push((fd, event)),
+                (EPOLL_CTL_MOD, Some(i)) => interests[i].1 = event,
+                (EPOLL_CTL_DEL, Some(i)) => {
+                    interests.remove(i);
+                }
+                _ => {
+                    log::error!("epoll_ctl op {op} on fd {fd} rejected");
+                    return -1;
+                }
+            }
+            0
+        }
+
+        /// 水平触发：每次调用都报告此刻就绪的描述符，不论之前是否报告过。
+        ///
+        /// 没有就绪的描述符时，`timeout` 为 0 返回 0，否则返回 -2 由用户态让出后重试。
+        fn epoll_wait(
+            &self,
+            _caller: Caller,
+            epfd: usize,
+            events: usize,
+            maxevents: usize,
+            timeout: isize,
+        ) -> isize {
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            let Some(interests) = current.epolls.get(&epfd) else {
+                log::error!("fd {epfd} is not an epoll instance");
+                return -1;
+            };
+            if maxevents == 0 {
+                return -1;
+            }
+            let mut count = 0;
+            for (fd, interest) in interests {
+                if count == maxevents {
+                    break;
+                }
+                let ready = poll_fd(current, *fd) & (interest.events | EPOLLERR | EPOLLHUP);
+                if ready == 0 {
+                    continue;
+                }
+                let addr = events + count * core::mem::size_of::<EpollEvent>();
+                let Some(mut ptr) = current
+                    .address_space
+                    .translate::<EpollEvent>(VAddr::new(addr), WRITEABLE)
+                else {
+                    log::error!("ptr not writeable");
+                    return -1;
+                };
+                unsafe {
+                    *ptr.as_mut() = EpollEvent {
+                        events: ready,
+                        data: interest.data,
+                    }
+                };
+                count += 1;
+            }
+            match (count, timeout) {
+                (0, 0) => 0,
+                (0, _) => -2,
+                _ => count as _,
+            }
+        }
+
+        fn ioctl(&self, _caller: Caller, fd: usize, request: usize, arg: usize) -> isize {
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            if fd > STDDEBUG {
+                log::error!("fd {fd} is not a terminal");
+                return -1;
+            }
+            match request {
+                TCGETS => {
+                    if let Some(mut ptr) =
+                        current.address_space.translate(VAddr::new(arg), WRITEABLE)
+                    {
+                        unsafe { *ptr.as_mut() = current.termios };
+                        0
+                    } else {
+                        log::error!("ptr not writeable");
+                        -1
+                    }
+                }
+                TCSETS => {
+                    if let Some(ptr) = current.address_space.translate(VAddr::new(arg), READABLE) {
+                        current.termios = unsafe { *ptr.as_ref() };
+                        0
+                    } else {
+                        log::error!("ptr not readable");
+                        -1
+                    }
+                }
+                _ => {
+                    log::error!("unsupported ioctl request: {request:#x}");
+                    -1
+                }
+            }
+        }
+
+        fn writev(&self, _caller: Caller, fd: usize, iov: usize, iovcnt: usize) -> isize {
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            let file = match fd {
+                STDOUT | STDDEBUG => None,
+                _ => match current.fd_table.get(fd) {
+                    Some(Some(file)) => Some(file),
+                    _ => {
+                        log::error!("unsupported fd: {fd}");
+                        return -1;
+                    }
+                },
+            };
+            transfer_vectored(iov, iovcnt, |base, len| {
+                let Some(buf) = user_buffer(&current.address_space, base, len, READABLE) else {
+                    log::error!("ptr not readable");
+                    return -1;
+                };
+                match file {
+                    None => {
+                        for b in buf.buffers {
+                            print!("{}", unsafe { core::str::from_utf8_unchecked(b) });
+                        }
+                        len as _
+                    }
+                    Some(file) => {
+                        let mut file = file.lock();
+                        if file.writable() {
+                            file.write(buf) as _
+                        } else {
+                            log::error!("file not writable");
+                            -1
+                        }
+                    }
+                }
+            })
+        }
+
+        fn readv(&self, _caller: Caller, fd: usize, iov: usize, iovcnt: usize) -> isize {
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            let termios = current.termios;
+            let file = match fd {
+                STDIN => None,
+                _ => match current.fd_table.get(fd) {
+                    Some(Some(file)) => Some(file),
+                    _ => {
+                        log::error!("unsupported fd: {fd}");
+                        return -1;
+                    }
+                },
+            };
+            transfer_vectored(iov, iovcnt, |base, len| {
+                let Some(buf) = user_buffer(&current.address_space, base, len, WRITEABLE) else {
+                    log::error!("ptr not writeable");
+                    return -1;
+                };
+                match file {
+                    None => read_console(buf, termios) as _,
+                    Some(file) => {
+                        let mut file = file.lock();
+                        if file.readable() {
+                            file.read(buf) as _
+                        } else {
+                            log::error!("file not readable");
+                            -1
                         }
-                        string.push(ch as char);
-                        raw_ptr = (raw_ptr as usize + 1) as *mut u8;
                     }
                 }
+            })
+        }
 
+        fn open(&self, _caller: Caller, path: usize, flags: usize) -> isize {
+            // FS.open(, flags)
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            if let Some(string) = read_path(path) {
+                let string = join_path(&current.cwd, &string);
                 if let Some(fd) =
                     FS.open(string.as_str(), OpenFlags::from_bits(flags as u32).unwrap())
                 {
@@ -422,9 +872,359 @@ mod impls {
             if fd >= current.fd_table.len() || current.fd_table[fd].is_none() {
                 return -1;
             }
-            current.fd_table[fd].take();
+            // 关闭文件隐含一次 fsync
+            if let Some(file) = current.fd_table[fd].take() {
+                file.lock().sync();
+            }
+            // 关闭的描述符自动从所有 epoll 实例里注销
+            current.epolls.remove(&fd);
+            for interests in current.epolls.values_mut() {
+                interests.retain(|(registered, _)| *registered != fd);
+            }
             0
         }
+
+        fn fcntl(&self, _caller: Caller, fd: usize, cmd: usize, arg: usize) -> isize {
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            let Some(Some(file)) = current.fd_table.get(fd) else {
+                return -1;
+            };
+            let mut file = file.lock();
+            match cmd {
+                F_DUPFD => {
+                    if arg >= MAX_FD {
+                        log::error!("fcntl: fd {arg} out of range");
+                        return -1;
+                    }
+                    // 新描述符不继承 close-on-exec 标志
+                    let mut new_file = file.clone();
+                    new_file.cloexec = false;
+                    drop(file);
+                    let Some(new_fd) = (arg..MAX_FD)
+                        .find(|&i| current.fd_table.get(i).is_none_or(Option::is_none))
+                    else {
+                        log::error!("fcntl: no free fd from {arg}");
+                        return -1;
+                    };
+                    if new_fd >= current.fd_table.len() {
+                        current.fd_table.resize_with(new_fd + 1, || None);
+                    }
+                    current.fd_table[new_fd] = Some(Mutex::new(new_file));
+                    new_fd as _
+                }
+                F_GETFD => {
+                    if file.cloexec {
+                        FD_CLOEXEC as _
+                    } else {
+                        0
+                    }
+                }
+                F_SETFD => {
+                    file.cloexec = arg & FD_CLOEXEC != 0;
+                    0
+                }
+                F_GETFL => {
+                    let mode = match (file.readable(), file.writable()) {
+                        (true, true) => O_RDWR,
+                        (false, true) => O_WRONLY,
+                        _ => O_RDONLY,
+                    };
+                    (mode | if file.nonblock { O_NONBLOCK } else { 0 }) as _
+                }
+                F_SETFL => {
+                    // 读写模式在打开时确定，只有非阻塞标志可以修改
+                    file.nonblock = arg & O_NONBLOCK != 0;
+                    0
+                }
+                _ => {
+                    log::error!("unsupported fcntl command: {cmd}");
+                    -1
+                }
+            }
+        }
+
+        fn fsync(&self, _caller: Caller, fd: usize) -> isize {
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            if let Some(Some(file)) = current.fd_table.get(fd) {
+                file.lock().sync();
+                0
+            } else {
+                -1
+            }
+        }
+
+        fn rename(&self, _caller: Caller, old_path: usize, new_path: usize) -> isize {
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            match (read_path(old_path), read_path(new_path)) {
+                (Some(old), Some(new)) => FS.rename(
+                    &join_path(&current.cwd, &old),
+                    &join_path(&current.cwd, &new),
+                ),
+                _ => {
+                    log::error!("ptr not readable");
+                    -1
+                }
+            }
+        }
+
+        fn mkdir(&self, _caller: Caller, path: usize) -> isize {
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            if let Some(path) = read_path(path) {
+                FS.mkdir(&join_path(&current.cwd, &path))
+            } else {
+                log::error!("ptr not readable");
+                -1
+            }
+        }
+
+        fn chdir(&self, _caller: Caller, path: usize) -> isize {
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            if let Some(path) = read_path(path) {
+                let path = join_path(&current.cwd, &path);
+                if FS.find(&path).is_some_and(|inode| inode.is_dir()) {
+                    current.cwd = path;
+                    0
+                } else {
+                    -1
+                }
+            } else {
+                log::error!("ptr not readable");
+                -1
+            }
+        }
+
+        fn access(&self, _caller: Caller, path: usize, mode: usize) -> isize {
+            if mode & !(R_OK | W_OK | X_OK) != 0 {
+                return -1;
+            }
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            if let Some(path) = read_path(path) {
+                // easy-fs 没有权限位，存在的文件允许任何访问
+                match FS.find(&join_path(&current.cwd, &path)) {
+                    Some(_) => 0,
+                    None => -1,
+                }
+            } else {
+                log::error!("ptr not readable");
+                -1
+            }
+        }
+
+        fn statfs(&self, _caller: Caller, path: usize, buf: usize) -> isize {
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            let Some(path) = read_path(path) else {
+                log::error!("ptr not readable");
+                return -1;
+            };
+            // 只有一个文件系统，路径只用来确认存在
+            if FS.find(&join_path(&current.cwd, &path)).is_none() {
+                return -1;
+            }
+            let Some(ptr) = current
+                .address_space
+                .translate::<Statfs>(VAddr::new(buf), WRITEABLE)
+            else {
+                log::error!("ptr not writeable");
+                return -1;
+            };
+            let stat = FS.stat();
+            unsafe {
+                *ptr.as_ptr() = Statfs {
+                    block_size: stat.block_size as _,
+                    total_blocks: stat.total_blocks as _,
+                    free_blocks: stat.free_blocks as _,
+                    total_inodes: stat.total_inodes as _,
+                    free_inodes: stat.free_inodes as _,
+                }
+            };
+            0
+        }
+
+        fn getcwd(&self, _caller: Caller, buf: usize, len: usize) -> isize {
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            // 路径加上结尾的 `\0`
+            let cwd = current.cwd.as_bytes();
+            if cwd.len() + 1 > len {
+                return -1;
+            }
+            // 路径可能跨页，写之前检查每一页
+            if let Some(dst) = user_buffer(&current.address_space, buf, cwd.len() + 1, WRITEABLE) {
+                let mut src = cwd.iter().chain(&[0]);
+                for byte in dst.buffers.into_iter().flatten() {
+                    *byte = *src.next().unwrap();
+                }
+                buf as _
+            } else {
+                log::error!("ptr not writeable");
+                -1
+            }
+        }
+    }
+
+    /// 和 `poll` 一样计算描述符此刻的就绪状态。
+    ///
+    /// 标准输入有字符可读时可读，标准输出总是可写，普通文件按打开方式总是可读或可写。
+    fn poll_fd(current: &crate::process::Process, fd: usize) -> u32 {
+        match fd {
+            STDIN => {
+                if stdin_ready() {
+                    EPOLLIN
+                } else {
+                    0
+                }
+            }
+            STDOUT | STDDEBUG => EPOLLOUT,
+            _ => match current.fd_table.get(fd) {
+                Some(Some(file)) => {
+                    let file = file.lock();
+                    match file.inode {
+                        Some(_) => {
+                            let mut events = 0;
+                            if file.readable() {
+                                events |= EPOLLIN;
+                            }
+                            if file.writable() {
+                                events |= EPOLLOUT;
+                            }
+                            events
+                        }
+                        None => 0,
+                    }
+                }
+                _ => EPOLLHUP,
+            },
+        }
+    }
+
+    /// 为判断标准输入是否可读而预先取出的字符，`usize::MAX` 表示没有。
+    static STDIN_PENDING: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+    /// 标准输入是否有字符可读。取到的字符暂存起来，留给下一次读。
+    fn stdin_ready() -> bool {
+        if STDIN_PENDING.load(Ordering::Relaxed) != usize::MAX {
+            return true;
+        }
+        #[allow(deprecated)]
+        let c = sbi_rt::legacy::console_getchar();
+        if c == usize::MAX {
+            false
+        } else {
+            STDIN_PENDING.store(c, Ordering::Relaxed);
+            true
+        }
+    }
+
+    /// 从控制台取一个字符，先取走 [`stdin_ready`] 暂存的字符。
+    #[allow(deprecated)]
+    fn console_getchar() -> usize {
+        match STDIN_PENDING.swap(usize::MAX, Ordering::Relaxed) {
+            usize::MAX => sbi_rt::legacy::console_getchar(),
+            c => c,
+        }
+    }
+
+    /// 描述符是否指向一个可写的文件（而不是标准输入输出）。
+    fn is_writable_file(file: &Mutex<FileHandle>) -> bool {
+        let file = file.lock();
+        file.writable() && file.inode.is_some()
+    }
+
+    /// 从当前进程的地址空间读取以 `\0` 结尾的路径。
+    fn read_path(path: usize) -> Option<String> {
+        let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+        let ptr = current
+            .address_space
+            .translate::<u8>(VAddr::new(path), READABLE)?;
+        let mut string = String::new();
+        let mut raw_ptr: *mut u8 = ptr.as_ptr();
+        loop {
+            unsafe {
+                let ch = *raw_ptr;
+                if ch == 0 {
+                    break;
+                }
+                string.push(ch as char);
+                raw_ptr = (raw_ptr as usize + 1) as *mut u8;
+            }
+        }
+        Some(string)
+    }
+
+    /// 把用户缓冲区 `buf..buf + count` 逐页翻译成 [`UserBuffer`]，物理上相邻的页合并成一段。
+    ///
+    /// 缓冲区可以跨页，任何一页没有映射、不满足 `flags` 或者地址不合法时返回 `None`。
+    fn user_buffer(
+        address_space: &AddressSpace<Sv39, Sv39Manager>,
+        buf: usize,
+        count: usize,
+        flags: VmFlags<Sv39>,
+    ) -> Option<UserBuffer> {
+        let end = buf.checked_add(count)?;
+        let valid = |addr: usize| VAddr::<Sv39>::new(addr).val() == addr;
+        if !valid(buf) || (count > 0 && !valid(end - 1)) {
+            return None;
+        }
+        let segments = address_space.translate_range(VAddr::new(buf)..VAddr::new(end), flags)?;
+        Some(UserBuffer::new(
+            segments
+                .into_iter()
+                .map(|(ptr, len)| unsafe { core::slice::from_raw_parts_mut(ptr.as_ptr(), len) })
+                .collect(),
+        ))
+    }
+
+    /// 依次对 `iov` 数组描述的每段缓冲区执行 `f`，返回传输的总字节数。
+    ///
+    /// 每段缓冲区由 `f` 用 [`user_buffer`] 各自翻译和检查。某一段失败或只传输了一部分时停止，
+    /// 返回之前已经传输的字节数，可以是 0，例如第一段就读到了文件末尾；
+    /// 只有第一段的 `iov` 项不可读或者 `f` 失败时返回 -1。
+    fn transfer_vectored(
+        iov: usize,
+        iovcnt: usize,
+        mut f: impl FnMut(usize, usize) -> isize,
+    ) -> isize {
+        let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+        let mut total = 0isize;
+        for i in 0..iovcnt {
+            let fail = if i == 0 { -1 } else { total };
+            let addr = iov + i * core::mem::size_of::<IoVec>();
+            let Some(ptr) = current
+                .address_space
+                .translate::<IoVec>(VAddr::new(addr), READABLE)
+            else {
+                log::error!("iov[{i}] not readable");
+                return fail;
+            };
+            let IoVec { base, len } = unsafe { *ptr.as_ptr() };
+            let ret = f(base, len);
+            if ret < 0 {
+                return fail;
+            }
+            total += ret;
+            if (ret as usize) < len {
+                break;
+            }
+        }
+        total
+    }
+
+    /// 从控制台读入用户缓冲区 `buf`，返回读到的字节数，行编辑见 [`Termios::read_console`]。
+    ///
+    /// 先读进内核缓冲区再依次复制到用户缓冲区的各段，行编辑不受页边界影响。一次最多读入一页。
+    #[allow(deprecated)]
+    fn read_console(buf: UserBuffer, termios: Termios) -> usize {
+        let count = buf.len();
+        let mut line = alloc::vec![0u8; count.min(1 << Sv39::PAGE_BITS)];
+        let len = termios.read_console(&mut line, console_getchar, |c| {
+            sbi_rt::legacy::console_putchar(c as _);
+        });
+        let mut data = &line[..len];
+        for b in buf.buffers {
+            let n = b.len().min(data.len());
+            b[..n].copy_from_slice(&data[..n]);
+            data = &data[n..];
+        }
+        len
     }
 
     impl Process for SyscallContext {
@@ -433,16 +1233,47 @@ mod impls {
             exit_code as isize
         }
 
-        fn fork(&self, _caller: Caller) -> isize {
+        #[inline]
+        fn fork(&self, caller: Caller) -> isize {
+            self.clone(caller, 0, 0)
+        }
+
+        fn clone(&self, _caller: Caller, flags: usize, stack: usize) -> isize {
+            if flags & !(CSIGNAL | CLONE_VM | CLONE_FILES) != 0 {
+                log::error!("unsupported clone flags: {flags:#x}");
+                return -1;
+            }
             let current_proc = unsafe { PROCESSOR.get_current_proc().unwrap() };
-            let (proc, mut thread) = current_proc.fork().unwrap();
-            let pid = proc.pid;
-            *thread.context.context.a_mut(0) = 0 as _;
-            unsafe {
-                PROCESSOR.add_proc(pid, proc, current_proc.pid);
-                PROCESSOR.add(thread.tid, thread, pid);
+            // 文件描述符表属于进程，只能和地址空间一起共享或者一起复制
+            match (flags & CLONE_VM != 0, flags & CLONE_FILES != 0) {
+                // 共享地址空间，在当前进程中新建线程，它必须有自己的栈
+                (true, true) if stack != 0 => {
+                    let current_thread = unsafe { PROCESSOR.current().unwrap() };
+                    let mut context = current_thread.context.context.fork_child();
+                    *context.sp_mut() = stack;
+                    let thread = Thread::new(current_thread.context.satp, context);
+                    let tid = thread.tid;
+                    unsafe { PROCESSOR.add(tid, thread, current_proc.pid) };
+                    tid.get_usize() as _
+                }
+                // 复制地址空间，新建进程
+                (false, false) => {
+                    let (proc, mut thread) = current_proc.fork().unwrap();
+                    if stack != 0 {
+                        *thread.context.context.sp_mut() = stack;
+                    }
+                    let pid = proc.pid;
+                    unsafe {
+                        PROCESSOR.add_proc(pid, proc, current_proc.pid);
+                        PROCESSOR.add(thread.tid, thread, pid);
+                    }
+                    pid.get_usize() as isize
+                }
+                _ => {
+                    log::error!("invalid clone flags: {flags:#x}, stack = {stack:#x}");
+                    -1
+                }
             }
-            pid.get_usize() as isize
         }
 
         fn exec(&self, _caller: Caller, path: usize, count: usize) -> isize {
@@ -454,7 +1285,7 @@ mod impls {
                 .map(|ptr| unsafe {
                     core::str::from_utf8_unchecked(core::slice::from_raw_parts(ptr.as_ptr(), count))
                 })
-                .and_then(|name| FS.open(name, OpenFlags::RDONLY))
+                .and_then(|name| FS.open(&join_path(&current.cwd, name), OpenFlags::RDONLY))
                 .map_or_else(
                     || {
                         log::error!("unknown app, select one in the list: ");
@@ -495,12 +1326,120 @@ mod impls {
             let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
             current.pid.get_usize() as _
         }
+
+        /// 单用户系统，所有进程都以 root 身份运行。
+        #[inline]
+        fn getuid(&self, _caller: Caller) -> isize {
+            0
+        }
+
+        #[inline]
+        fn geteuid(&self, _caller: Caller) -> isize {
+            0
+        }
+
+        #[inline]
+        fn getgid(&self, _caller: Caller) -> isize {
+            0
+        }
+
+        #[inline]
+        fn getegid(&self, _caller: Caller) -> isize {
+            0
+        }
+    }
+
+    /// 共享内存段默认从这里向上找第一段空闲的虚页映射。
+    const SHM_BASE: usize = 1 << 36;
+
+    impl Memory for SyscallContext {
+        fn shmget(&self, _caller: Caller, key: usize, size: usize, flags: usize) -> isize {
+            match shm::get(key, size, flags) {
+                Some(shmid) => shmid as _,
+                None => {
+                    log::error!("shmget key {key:#x} size {size:#x} failed");
+                    -1
+                }
+            }
+        }
+
+        fn shmat(&self, _caller: Caller, shmid: usize, addr: usize, flags: usize) -> isize {
+            if VAddr::<Sv39>::new(addr).offset() != 0 {
+                log::error!("shmat address {addr:#x} not page aligned");
+                return -1;
+            }
+            let Some((ppn, pages)) = shm::attach(shmid) else {
+                log::error!("shm {shmid} not found");
+                return -1;
+            };
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            let flags = if flags & SHM_RDONLY != 0 {
+                VmFlags::build_from_str("U_RV")
+            } else {
+                VmFlags::build_from_str("U_WRV")
+            };
+            let start = if addr == 0 {
+                let mut start = VPN::<Sv39>::new(SHM_BASE >> Sv39::PAGE_BITS);
+                while let Some(vpn) = current.address_space.find_mapped(start..start + pages) {
+                    start = vpn + 1;
+                }
+                start
+            } else {
+                VAddr::<Sv39>::new(addr).floor()
+            };
+            let range = start..start + pages;
+            if let Err(vpn) = current
+                .address_space
+                .try_map_extern(range.clone(), ppn, flags)
+            {
+                log::error!("{vpn:?} is already mapped");
+                shm::detach(shmid);
+                return -1;
+            }
+            current.shm.push(shm::Attachment {
+                shmid,
+                range,
+                flags,
+            });
+            start.base().val() as _
+        }
+
+        fn shmdt(&self, _caller: Caller, addr: usize) -> isize {
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            let Some(i) = current
+                .shm
+                .iter()
+                .position(|attachment| attachment.range.start.base().val() == addr)
+            else {
+                log::error!("no shm attached at {addr:#x}");
+                return -1;
+            };
+            let attachment = current.shm.remove(i);
+            current.address_space.unmap(attachment.range);
+            shm::detach(attachment.shmid);
+            0
+        }
+
+        fn shmctl(&self, _caller: Caller, shmid: usize, cmd: usize, _buf: usize) -> isize {
+            match cmd {
+                IPC_RMID if shm::remove(shmid) => 0,
+                IPC_RMID => {
+                    log::error!("shm {shmid} not found");
+                    -1
+                }
+                _ => {
+                    log::error!("unsupported shmctl cmd: {cmd}");
+                    -1
+                }
+            }
+        }
     }
 
     impl Scheduling for SyscallContext {
+        /// 有其他任务等待调度、让出之后会切换时返回 1，调用者是唯一就绪的任务时返回 0。
         #[inline]
         fn sched_yield(&self, _caller: Caller) -> isize {
-            0
+            unsafe { PROCESSOR.has_ready() as _ }
         }
     }
 
@@ -528,6 +1467,33 @@ mod impls {
                 _ => -1,
             }
         }
+
+        /// 没有实时时钟，返回启动以来的时间；`tz` 非空时总是填入 UTC。
+        fn gettimeofday(&self, _caller: Caller, tv: usize, tz: usize) -> isize {
+            const WRITABLE: VmFlags<Sv39> = VmFlags::build_from_str("W_V");
+            let current = unsafe { PROCESSOR.get_current_proc().unwrap() };
+            if tv != 0 {
+                let Some(mut ptr) = current.address_space.translate(VAddr::new(tv), WRITABLE)
+                else {
+                    log::error!("ptr not writeable");
+                    return -1;
+                };
+                let time = riscv::register::time::read() * 10000 / 125;
+                *unsafe { ptr.as_mut() } = TimeVal {
+                    tv_sec: time / 1_000_000_000,
+                    tv_usec: time % 1_000_000_000 / 1_000,
+                };
+            }
+            if tz != 0 {
+                let Some(mut ptr) = current.address_space.translate(VAddr::new(tz), WRITABLE)
+                else {
+                    log::error!("ptr not writeable");
+                    return -1;
+                };
+                *unsafe { ptr.as_mut() } = TimeZone::UTC;
+            }
+            0
+        }
     }
 
     impl Signal for SyscallContext {
@@ -644,9 +1610,9 @@ mod impls {
                 VmFlags::build_from_str("U_WRV"),
             );
             let satp = (8 << 60) | addrspace.root_ppn().val();
-            let mut context = kernel_context::LocalContext::user(entry);
-            *context.sp_mut() = (vpn + 2).base().val();
-            *context.a_mut(0) = arg;
+            let current_thread = unsafe { PROCESSOR.current().unwrap() };
+            let sp = (vpn + 2).base().val();
+            let context = current_thread.context.context.spawn_thread(entry, sp, arg);
             let thread = Thread::new(satp, context);
             let tid = thread.tid;
             unsafe {
@@ -660,6 +1626,22 @@ mod impls {
             current_thread.tid.get_usize() as _
         }
 
+        fn set_tid_address(&self, _caller: Caller, tidptr: usize) -> isize {
+            let current_thread = unsafe { PROCESSOR.current().unwrap() };
+            current_thread.clear_child_tid = tidptr;
+            current_thread.tid.get_usize() as _
+        }
+
+        fn set_robust_list(&self, _caller: Caller, head: usize, len: usize) -> isize {
+            if len != core::mem::size_of::<RobustListHead>() {
+                log::error!("robust list head size mismatches: {len}");
+                return -1;
+            }
+            let current_thread = unsafe { PROCESSOR.current().unwrap() };
+            current_thread.robust_list = head;
+            0
+        }
+
         fn waittid(&self, _caller: Caller, tid: usize) -> isize {
             let current_thread = unsafe { PROCESSOR.current().unwrap() };
             // 线程不能自己等待自己
@@ -1,6 +1,6 @@
-use crate::{map_portal, Sv39Manager, PROCESSOR};
+use crate::{map_portal, shm, Sv39Manager, PROCESSOR};
 use alloc::sync::Arc;
-use alloc::{alloc::alloc_zeroed, boxed::Box, vec::Vec};
+use alloc::{alloc::alloc_zeroed, boxed::Box, collections::BTreeMap, string::String, vec::Vec};
 use core::{alloc::Layout, str::FromStr};
 use easy_fs::FileHandle;
 use kernel_context::{foreign::ForeignContext, LocalContext};
@@ -13,6 +13,7 @@ use signal::Signal;
 use signal_impl::SignalImpl;
 use spin::Mutex;
 use sync::{Condvar, Mutex as MutexTrait, Semaphore};
+use syscall::{EpollEvent, Termios};
 use xmas_elf::{
     header::{self, HeaderPt2, Machine},
     program, ElfFile,
@@ -24,6 +25,10 @@ pub struct Thread {
     pub tid: ThreadId,
     /// 可变
     pub context: ForeignContext,
+    /// `set_tid_address` 登记的用户地址，线程退出时清零，0 表示没有登记。
+    pub clear_child_tid: usize,
+    /// `set_robust_list` 登记的健壮锁链表表头，线程退出时遍历，0 表示没有登记。
+    pub robust_list: usize,
 }
 
 impl Thread {
@@ -31,6 +36,8 @@ impl Thread {
         Self {
             tid: ThreadId::new(),
             context: ForeignContext { context, satp },
+            clear_child_tid: 0,
+            robust_list: 0,
         }
     }
 }
@@ -43,6 +50,14 @@ pub struct Process {
     pub address_space: AddressSpace<Sv39, Sv39Manager>,
     /// 文件描述符表
     pub fd_table: Vec<Option<Mutex<FileHandle>>>,
+    /// 控制台的终端属性，标准输入输出共用
+    pub termios: Termios,
+    /// 当前工作目录，总是以 `/` 开头的规范化绝对路径
+    pub cwd: String,
+    /// epoll 实例的兴趣列表，以实例的文件描述符为键
+    pub epolls: BTreeMap<usize, Vec<(usize, EpollEvent)>>,
+    /// 映射进地址空间的共享内存段
+    pub shm: Vec<shm::Attachment>,
     /// 信号模块
     pub signal: Box<dyn Signal>,
     /// 分配的锁以及信号量
@@ -55,7 +70,17 @@ impl Process {
     /// 只支持一个线程
     pub fn exec(&mut self, elf: ElfFile) {
         let (proc, thread) = Process::from_elf(elf).unwrap();
+        self.detach_shm();
         self.address_space = proc.address_space;
+        // 关闭带有 close-on-exec 标志的文件描述符
+        for fd in self.fd_table.iter_mut() {
+            if fd.as_mut().is_some_and(|file| file.get_mut().cloexec) {
+                *fd = None;
+            }
+        }
+        let fd_table = &self.fd_table;
+        self.epolls
+            .retain(|&epfd, _| fd_table.get(epfd).is_some_and(Option::is_some));
         unsafe {
             let pthreads = PROCESSOR.get_thread(self.pid).unwrap();
             PROCESSOR.get_task(pthreads[0]).unwrap().context = thread.context;
@@ -66,9 +91,21 @@ impl Process {
         // 子进程 pid
         let pid = ProcId::new();
         // 复制父进程地址空间
-        let parent_addr_space = &self.address_space;
+        let parent_addr_space = &mut self.address_space;
         let mut address_space: AddressSpace<Sv39, Sv39Manager> = AddressSpace::new();
+        // 共享内存段不复制，子进程映射同一组物理页
+        let areas = core::mem::take(&mut parent_addr_space.areas);
+        parent_addr_space.areas = areas
+            .iter()
+            .filter(|area| !self.shm.iter().any(|shm| shm.range == **area))
+            .cloned()
+            .collect();
         parent_addr_space.cloneself(&mut address_space);
+        parent_addr_space.areas = areas;
+        for attachment in &self.shm {
+            let ppn = shm::inherit(attachment.shmid);
+            address_space.map_extern(attachment.range.clone(), ppn, attachment.flags);
+        }
         map_portal(&address_space);
         // 线程
         let pthreads = unsafe { PROCESSOR.get_thread(self.pid).unwrap() };
@@ -78,7 +115,7 @@ impl Process {
                 .unwrap()
                 .context
                 .context
-                .clone()
+                .fork_child()
         };
         let satp = (8 << 60) | address_space.root_ppn().val();
         let thread = Thread::new(satp, context);
@@ -96,6 +133,10 @@ impl Process {
                 pid,
                 address_space,
                 fd_table: new_fd_table,
+                termios: self.termios,
+                cwd: self.cwd.clone(),
+                epolls: self.epolls.clone(),
+                shm: self.shm.clone(),
                 signal: self.signal.from_fork(),
                 semaphore_list: Vec::new(),
                 mutex_list: Vec::new(),
@@ -105,6 +146,14 @@ impl Process {
         ))
     }
 
+    /// 分离所有共享内存段，进程退出或替换地址空间时调用。
+    pub fn detach_shm(&mut self) {
+        for attachment in core::mem::take(&mut self.shm) {
+            self.address_space.unmap(attachment.range);
+            shm::detach(attachment.shmid);
+        }
+    }
+
     pub fn from_elf(elf: ElfFile) -> Option<(Self, Thread)> {
         let entry = match elf.header.pt2 {
             HeaderPt2::Header64(pt2)
@@ -177,6 +226,10 @@ impl Process {
                     // Stdout
                     Some(Mutex::new(FileHandle::empty(false, true))),
                 ],
+                termios: Termios::COOKED,
+                cwd: String::from("/"),
+                epolls: BTreeMap::new(),
+                shm: Vec::new(),
                 signal: Box::new(SignalImpl::new()),
                 semaphore_list: Vec::new(),
                 mutex_list: Vec::new(),
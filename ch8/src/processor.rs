@@ -50,6 +50,10 @@ impl Schedule<ThreadId> for ThreadManager {
     fn fetch(&mut self) -> Option<ThreadId> {
         self.ready_queue.pop_front()
     }
+    /// 调度队列是否为空
+    fn is_empty(&self) -> bool {
+        self.ready_queue.is_empty()
+    }
 }
 
 /// 进程管理器
@@ -0,0 +1,136 @@
+//! System V 风格的共享内存段。
+//!
+//! 段的物理页归内核所有，以 [`map_extern`] 映射进各个进程，地址空间不会释放它们。
+//! 每个段记录被映射的次数，标记删除后最后一次分离时释放物理页。
+//!
+//! [`map_extern`]: kernel_vm::AddressSpace::map_extern
+
+use alloc::{
+    alloc::{alloc_zeroed, dealloc},
+    vec::Vec,
+};
+use core::{alloc::Layout, ops::Range};
+use kernel_vm::page_table::{MmuMeta, Sv39, VmFlags, PPN, VPN};
+use spin::Mutex;
+use syscall::{IPC_CREAT, IPC_EXCL, IPC_PRIVATE};
+
+/// 共享内存段，以在 [`SEGMENTS`] 中的序号为段号。
+struct Segment {
+    key: usize,
+    /// 物理页的起始地址，内核恒等映射。
+    base: usize,
+    pages: usize,
+    /// 被映射的次数，进程分离或退出时减少。
+    attached: usize,
+    /// 已标记删除，不能再按键找到或映射。
+    removed: bool,
+}
+
+impl Segment {
+    #[inline]
+    fn layout(pages: usize) -> Layout {
+        Layout::from_size_align(pages << Sv39::PAGE_BITS, 1 << Sv39::PAGE_BITS).unwrap()
+    }
+}
+
+static SEGMENTS: Mutex<Vec<Option<Segment>>> = Mutex::new(Vec::new());
+
+/// 进程中的一次映射。
+#[derive(Clone)]
+pub struct Attachment {
+    pub shmid: usize,
+    pub range: Range<VPN<Sv39>>,
+    pub flags: VmFlags<Sv39>,
+}
+
+/// 按键查找段，找不到且带 [`IPC_CREAT`] 时新建一个至少 `size` 字节的段，返回段号。
+pub fn get(key: usize, size: usize, flags: usize) -> Option<usize> {
+    let mut segments = SEGMENTS.lock();
+    if key != IPC_PRIVATE {
+        let found = segments.iter().position(|segment| {
+            segment
+                .as_ref()
+                .is_some_and(|segment| !segment.removed && segment.key == key)
+        });
+        if let Some(shmid) = found {
+            let segment = segments[shmid].as_ref().unwrap();
+            let exclusive = flags & (IPC_CREAT | IPC_EXCL) == IPC_CREAT | IPC_EXCL;
+            return (!exclusive && size <= segment.pages << Sv39::PAGE_BITS).then_some(shmid);
+        }
+        if flags & IPC_CREAT == 0 {
+            return None;
+        }
+    }
+    if size == 0 {
+        return None;
+    }
+    let pages = size.div_ceil(1 << Sv39::PAGE_BITS);
+    let base = unsafe { alloc_zeroed(Segment::layout(pages)) } as usize;
+    if base == 0 {
+        return None;
+    }
+    let segment = Segment {
+        key,
+        base,
+        pages,
+        attached: 0,
+        removed: false,
+    };
+    match segments.iter().position(Option::is_none) {
+        Some(shmid) => {
+            segments[shmid] = Some(segment);
+            Some(shmid)
+        }
+        None => {
+            segments.push(Some(segment));
+            Some(segments.len() - 1)
+        }
+    }
+}
+
+/// 增加一次映射，返回段的起始物理页号和页数。段不存在或已标记删除时返回 `None`。
+pub fn attach(shmid: usize) -> Option<(PPN<Sv39>, usize)> {
+    let mut segments = SEGMENTS.lock();
+    let segment = segments.get_mut(shmid)?.as_mut()?;
+    if segment.removed {
+        return None;
+    }
+    segment.attached += 1;
+    Some((PPN::new(segment.base >> Sv39::PAGE_BITS), segment.pages))
+}
+
+/// `fork` 时子进程继承父进程的映射，已标记删除的段也可以继承。
+pub fn inherit(shmid: usize) -> PPN<Sv39> {
+    let mut segments = SEGMENTS.lock();
+    let segment = segments[shmid].as_mut().unwrap();
+    segment.attached += 1;
+    PPN::new(segment.base >> Sv39::PAGE_BITS)
+}
+
+/// 减少一次映射，已标记删除且不再被映射时释放。
+pub fn detach(shmid: usize) {
+    let mut segments = SEGMENTS.lock();
+    let segment = segments[shmid].as_mut().unwrap();
+    segment.attached -= 1;
+    if segment.removed && segment.attached == 0 {
+        release(&mut segments[shmid]);
+    }
+}
+
+/// 标记删除，没有被映射时立即释放。段不存在时返回 `false`。
+pub fn remove(shmid: usize) -> bool {
+    let mut segments = SEGMENTS.lock();
+    let Some(Some(segment)) = segments.get_mut(shmid) else {
+        return false;
+    };
+    segment.removed = true;
+    if segment.attached == 0 {
+        release(&mut segments[shmid]);
+    }
+    true
+}
+
+fn release(slot: &mut Option<Segment>) {
+    let segment = slot.take().unwrap();
+    unsafe { dealloc(segment.base as *mut u8, Segment::layout(segment.pages)) };
+}
@@ -3,18 +3,28 @@ use alloc::{
     alloc::{alloc_zeroed, dealloc},
     sync::Arc,
 };
-use core::{alloc::Layout, ptr::NonNull};
+use core::{
+    alloc::Layout,
+    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 use easy_fs::BlockDevice;
 use kernel_vm::page_table::{MmuMeta, Sv39, VAddr, VmFlags};
 use spin::{Lazy, Mutex};
 use virtio_drivers::{Hal, VirtIOBlk, VirtIOHeader};
 
 const VIRTIO0: usize = 0x10001000;
+// 相邻 virtio-mmio 槽位的间隔。
+const VIRTIO_STRIDE: usize = 0x1000;
+
+/// 根文件系统所在的 virtio-mmio 槽位。
+pub static ROOT_SLOT: AtomicUsize = AtomicUsize::new(0);
 
 pub static BLOCK_DEVICE: Lazy<Arc<dyn BlockDevice>> = Lazy::new(|| {
+    let base = VIRTIO0 + ROOT_SLOT.load(Ordering::Relaxed) * VIRTIO_STRIDE;
     Arc::new(unsafe {
         VirtIOBlock(Mutex::new(
-            VirtIOBlk::new(&mut *(VIRTIO0 as *mut VirtIOHeader)).unwrap(),
+            VirtIOBlk::new(&mut *(base as *mut VirtIOHeader)).unwrap(),
         ))
     })
 });
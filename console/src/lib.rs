@@ -6,6 +6,7 @@
 use core::{
     fmt::{self, Write},
     str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
 };
 use spin::Once;
 
@@ -26,6 +27,22 @@ pub trait Console: Sync {
             self.put_char(c);
         }
     }
+
+    /// 向调试通道放置一个字符串。
+    ///
+    /// 日志和 [`eprint!`] 走这个通道。只有一个串口时不需要覆盖，调试输出和普通输出合并在一起；
+    /// 有第二个串口时覆盖这个实现，把调试信息和程序输出分开。
+    #[inline]
+    fn put_debug_str(&self, s: &str) {
+        self.put_str(s);
+    }
+
+    /// 把缓冲中尚未输出的内容写到控制台。
+    ///
+    /// 没有缓冲的实现不需要覆盖。panic 处理也会调用这个方法，
+    /// 所以实现不能等待可能被 panic 的控制流持有的锁。
+    #[inline]
+    fn flush(&self) {}
 }
 
 /// 库找到输出的方法：保存一个对象引用，这是一种单例。
@@ -52,6 +69,21 @@ pub fn set_log_level(env: Option<&str>) {
     log::set_max_level(env.and_then(|s| Lv::from_str(s).ok()).unwrap_or(Lv::Trace));
 }
 
+/// 把控制台缓冲中的内容写出。
+///
+/// panic 处理应该在关机之前调用。如果 flush 本身 panic 了，
+/// panic 处理中再次调用会直接返回，不会无限递归。
+pub fn flush() {
+    static FLUSHING: AtomicBool = AtomicBool::new(false);
+    if FLUSHING.swap(true, Ordering::Acquire) {
+        return;
+    }
+    if let Some(console) = CONSOLE.get() {
+        console.flush();
+    }
+    FLUSHING.store(false, Ordering::Release);
+}
+
 /// 打印一些测试信息。
 pub fn test_log() {
     println!(
@@ -80,6 +112,15 @@ pub fn _print(args: fmt::Arguments) {
     Logger.write_fmt(args).unwrap();
 }
 
+/// 向调试通道打印。
+///
+/// 给宏用的，用户不会直接调它。
+#[doc(hidden)]
+#[inline]
+pub fn _eprint(args: fmt::Arguments) {
+    DebugWriter.write_fmt(args).unwrap();
+}
+
 /// 格式化打印。
 #[macro_export]
 macro_rules! print {
@@ -98,9 +139,38 @@ macro_rules! println {
     }}
 }
 
+/// 向调试通道格式化打印。
+#[macro_export]
+macro_rules! eprint {
+    ($($arg:tt)*) => {
+        $crate::_eprint(core::format_args!($($arg)*));
+    }
+}
+
+/// 向调试通道格式化打印并换行。
+#[macro_export]
+macro_rules! eprintln {
+    () => ($crate::eprint!("\n"));
+    ($($arg:tt)*) => {{
+        $crate::_eprint(core::format_args!($($arg)*));
+        $crate::eprintln!();
+    }}
+}
+
 /// 这个 Unit struct 是 `core::fmt` 要求的。
 struct Logger;
 
+/// 调试通道的 [`Write`] 实现。
+struct DebugWriter;
+
+impl Write for DebugWriter {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> Result<(), fmt::Error> {
+        CONSOLE.get().unwrap().put_debug_str(s);
+        Ok(())
+    }
+}
+
 /// 实现 [`Write`] trait，格式化的基础。
 impl Write for Logger {
     #[inline]
@@ -130,13 +200,13 @@ impl log::Log for Logger {
 
         if let Some(ts) = TIMESTAMP_MS.get().copied() {
             let ts = ts();
-            println!(
+            eprintln!(
                 "\x1b[{color_code}m[{ts:>5} ms] [{:>5}] {}\x1b[0m",
                 record.level(),
                 record.args(),
             );
         } else {
-            println!(
+            eprintln!(
                 "\x1b[{color_code}m[{:>5}] {}\x1b[0m",
                 record.level(),
                 record.args(),
@@ -144,5 +214,8 @@ impl log::Log for Logger {
         }
     }
 
-    fn flush(&self) {}
+    #[inline]
+    fn flush(&self) {
+        flush();
+    }
 }
@@ -140,3 +140,10 @@ pub fn block_cache_sync_all() {
         cache.lock().sync();
     }
 }
+/// Sync the block cache of `block_id` to block device if it is cached
+pub fn block_cache_sync(block_id: usize) {
+    let manager = BLOCK_CACHE_MANAGER.lock();
+    if let Some((_, cache)) = manager.queue.iter().find(|pair| pair.0 == block_id) {
+        cache.lock().sync();
+    }
+}
@@ -18,6 +18,22 @@ pub struct EasyFileSystem {
 }
 
 type DataBlock = [u8; BLOCK_SZ];
+
+/// Usage statistics of a filesystem
+#[derive(Clone, Copy, Debug)]
+pub struct FsStat {
+    /// Size of a block in bytes
+    pub block_size: usize,
+    /// Number of blocks in the data area
+    pub total_blocks: usize,
+    /// Number of free blocks in the data area
+    pub free_blocks: usize,
+    /// Number of inodes
+    pub total_inodes: usize,
+    /// Number of free inodes
+    pub free_inodes: usize,
+}
+
 /// An easy fs over a block device
 impl EasyFileSystem {
     /// A data block of block size
@@ -79,7 +95,10 @@ impl EasyFileSystem {
                 disk_inode.initialize(DiskInodeType::Directory);
             });
         block_cache_sync_all();
-        Arc::new(Mutex::new(efs))
+        let efs = Arc::new(Mutex::new(efs));
+        // "." and ".." of root both point to itself
+        Self::root_inode(&efs).init_dir(0);
+        efs
     }
     /// Open a block device as a filesystem
     pub fn open(block_device: Arc<dyn BlockDevice>) -> Arc<Mutex<Self>> {
@@ -103,6 +122,22 @@ impl EasyFileSystem {
                 Arc::new(Mutex::new(efs))
             })
     }
+    /// Count used and free blocks and inodes from the superblock and bitmaps
+    pub fn stat(&self) -> FsStat {
+        let total_blocks = get_block_cache(0, Arc::clone(&self.block_device))
+            .lock()
+            .read(0, |super_block: &SuperBlock| {
+                super_block.data_area_blocks as usize
+            });
+        let total_inodes = self.inode_bitmap.maximum();
+        FsStat {
+            block_size: BLOCK_SZ,
+            total_blocks,
+            free_blocks: total_blocks - self.data_bitmap.count_allocated(&self.block_device),
+            total_inodes,
+            free_inodes: total_inodes - self.inode_bitmap.count_allocated(&self.block_device),
+        }
+    }
     /// Get the root inode of the filesystem
     pub fn root_inode(efs: &Arc<Mutex<Self>>) -> Inode {
         let block_device = Arc::clone(&efs.lock().block_device);
@@ -121,6 +156,13 @@ impl EasyFileSystem {
             (inode_id % inodes_per_block) as usize * inode_size,
         )
     }
+    /// Get inode id by the position of its disk inode
+    pub fn get_inode_id(&self, block_id: usize, block_offset: usize) -> u32 {
+        let inode_size = core::mem::size_of::<DiskInode>();
+        let inodes_per_block = BLOCK_SZ / inode_size;
+        ((block_id - self.inode_area_start_block as usize) * inodes_per_block
+            + block_offset / inode_size) as u32
+    }
     /// Get data block by id
     pub fn get_data_block_id(&self, data_block_id: u32) -> u32 {
         self.data_area_start_block + data_block_id
@@ -130,6 +172,12 @@ impl EasyFileSystem {
         self.inode_bitmap.alloc(&self.block_device).unwrap() as u32
     }
 
+    /// Deallocate an inode
+    pub fn dealloc_inode(&mut self, inode_id: u32) {
+        self.inode_bitmap
+            .dealloc(&self.block_device, inode_id as usize)
+    }
+
     /// Allocate a data block
     pub fn alloc_data(&mut self) -> u32 {
         self.data_bitmap.alloc(&self.block_device).unwrap() as u32 + self.data_area_start_block
@@ -67,6 +67,10 @@ pub struct FileHandle {
     pub write: bool,
     /// Current offset
     pub offset: usize,
+    /// Descriptor flag: close on exec
+    pub cloexec: bool,
+    /// Status flag: non-blocking I/O
+    pub nonblock: bool,
     // TODO: CH7
     // /// Specify if this is pipe
     // pub pipe: bool,
@@ -79,6 +83,8 @@ impl FileHandle {
             read,
             write,
             offset: 0,
+            cloexec: false,
+            nonblock: false,
         }
     }
 
@@ -88,6 +94,8 @@ impl FileHandle {
             read,
             write,
             offset: 0,
+            cloexec: false,
+            nonblock: false,
         }
     }
 }
@@ -101,15 +109,39 @@ impl FileHandle {
         self.write
     }
 
-    pub fn read(&mut self, mut buf: UserBuffer) -> isize {
+    /// Write dirty blocks of the file back to the block device
+    pub fn sync(&self) {
+        if let Some(inode) = &self.inode {
+            inode.sync();
+        }
+    }
+
+    pub fn read(&mut self, buf: UserBuffer) -> isize {
+        let ret = self.read_at(self.offset, buf);
+        if ret > 0 {
+            self.offset += ret as usize;
+        }
+        ret
+    }
+
+    pub fn write(&mut self, buf: UserBuffer) -> isize {
+        let ret = self.write_at(self.offset, buf);
+        if ret > 0 {
+            self.offset += ret as usize;
+        }
+        ret
+    }
+
+    /// Read from `offset` without moving the current offset
+    pub fn read_at(&self, mut offset: usize, mut buf: UserBuffer) -> isize {
         let mut total_read_size: usize = 0;
         if let Some(inode) = &self.inode {
             for slice in buf.buffers.iter_mut() {
-                let read_size = inode.read_at(self.offset, *slice);
+                let read_size = inode.read_at(offset, *slice);
                 if read_size == 0 {
                     break;
                 }
-                self.offset += read_size;
+                offset += read_size;
                 total_read_size += read_size;
             }
             total_read_size as _
@@ -118,13 +150,14 @@ impl FileHandle {
         }
     }
 
-    pub fn write(&mut self, buf: UserBuffer) -> isize {
+    /// Write at `offset` without moving the current offset
+    pub fn write_at(&self, mut offset: usize, buf: UserBuffer) -> isize {
         let mut total_write_size: usize = 0;
         if let Some(inode) = &self.inode {
             for slice in buf.buffers.iter() {
-                let write_size = inode.write_at(self.offset, *slice);
+                let write_size = inode.write_at(offset, *slice);
                 assert_eq!(write_size, slice.len());
-                self.offset += write_size;
+                offset += write_size;
                 total_write_size += write_size;
             }
             total_write_size as _
@@ -147,6 +180,12 @@ pub trait FSManager {
     /// Remove a hard link
     fn unlink(&self, path: &str) -> isize;
 
+    /// Rename a file, replacing the target if it exists
+    fn rename(&self, src: &str, dst: &str) -> isize;
+
     /// List inodes under the target directory
     fn readdir(&self, path: &str) -> Option<Vec<String>>;
+
+    /// Create a directory
+    fn mkdir(&self, path: &str) -> isize;
 }
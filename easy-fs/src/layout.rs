@@ -8,7 +8,7 @@ const EFS_MAGIC: u32 = 0x3b800001;
 /// The max number of direct inodes
 const INODE_DIRECT_COUNT: usize = 28;
 /// The max length of inode name
-const NAME_LENGTH_LIMIT: usize = 27;
+pub const NAME_LENGTH_LIMIT: usize = 27;
 /// The max number of indirect1 inodes
 const INODE_INDIRECT1_COUNT: usize = BLOCK_SZ / 4;
 /// The max number of indirect2 inodes
@@ -308,6 +308,49 @@ impl DiskInode {
         self.indirect2 = 0;
         v
     }
+    /// Return all blocks used by current disk inode, including index blocks
+    pub fn blocks(&self, block_device: &Arc<dyn BlockDevice>) -> Vec<u32> {
+        let mut v: Vec<u32> = Vec::new();
+        let mut data_blocks = self.data_blocks() as usize;
+        // direct
+        v.extend_from_slice(&self.direct[..data_blocks.min(INODE_DIRECT_COUNT)]);
+        // indirect1 block
+        if data_blocks > INODE_DIRECT_COUNT {
+            v.push(self.indirect1);
+            data_blocks -= INODE_DIRECT_COUNT;
+        } else {
+            return v;
+        }
+        // indirect1
+        get_block_cache(self.indirect1 as usize, Arc::clone(block_device))
+            .lock()
+            .read(0, |indirect1: &IndirectBlock| {
+                v.extend_from_slice(&indirect1[..data_blocks.min(INODE_INDIRECT1_COUNT)]);
+            });
+        // indirect2 block
+        if data_blocks > INODE_INDIRECT1_COUNT {
+            v.push(self.indirect2);
+            data_blocks -= INODE_INDIRECT1_COUNT;
+        } else {
+            return v;
+        }
+        // indirect2
+        get_block_cache(self.indirect2 as usize, Arc::clone(block_device))
+            .lock()
+            .read(0, |indirect2: &IndirectBlock| {
+                let count = data_blocks.div_ceil(INODE_INDIRECT1_COUNT);
+                for (i, entry) in indirect2.iter().take(count).enumerate() {
+                    v.push(*entry);
+                    let len = (data_blocks - i * INODE_INDIRECT1_COUNT).min(INODE_INDIRECT1_COUNT);
+                    get_block_cache(*entry as usize, Arc::clone(block_device))
+                        .lock()
+                        .read(0, |indirect1: &IndirectBlock| {
+                            v.extend_from_slice(&indirect1[..len]);
+                        });
+                }
+            });
+        v
+    }
     /// Read data from current disk inode
     pub fn read_at(
         &self,
@@ -12,9 +12,9 @@ mod vfs;
 /// Use a block size of 512 bytes
 pub const BLOCK_SZ: usize = 512;
 use bitmap::Bitmap;
-use block_cache::{block_cache_sync_all, get_block_cache};
+use block_cache::{block_cache_sync, block_cache_sync_all, get_block_cache};
 pub use block_dev::BlockDevice;
-pub use efs::EasyFileSystem;
+pub use efs::{EasyFileSystem, FsStat};
 pub use file::*;
 use layout::*;
 pub use vfs::Inode;
@@ -1,12 +1,13 @@
 use super::{
-    block_cache_sync_all, get_block_cache, BlockDevice, DirEntry, DiskInode, DiskInodeType,
-    EasyFileSystem, DIRENT_SZ,
+    block_cache_sync, block_cache_sync_all, get_block_cache, BlockDevice, DirEntry, DiskInode,
+    DiskInodeType, EasyFileSystem, FsStat, DIRENT_SZ, NAME_LENGTH_LIMIT,
 };
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use spin::{Mutex, MutexGuard};
 /// Virtual filesystem layer over easy-fs
+#[derive(Clone)]
 pub struct Inode {
     block_id: usize,
     block_offset: usize,
@@ -46,6 +47,12 @@ impl Inode {
 
     /// Find inode under a disk inode by name
     fn find_inode_id(&self, name: &str, disk_inode: &DiskInode) -> Option<u32> {
+        self.find_dirent(name, disk_inode)
+            .map(|(_, inode_id)| inode_id)
+    }
+
+    /// Find the index and inode of a directory entry under a disk inode by name
+    fn find_dirent(&self, name: &str, disk_inode: &DiskInode) -> Option<(usize, u32)> {
         // assert it is a directory
         assert!(disk_inode.is_dir());
         let file_count = (disk_inode.size as usize) / DIRENT_SZ;
@@ -56,16 +63,32 @@ impl Inode {
                 DIRENT_SZ,
             );
             if dirent.name() == name {
-                return Some(dirent.inode_number() as u32);
+                return Some((i, dirent.inode_number() as u32));
             }
         }
         None
     }
 
-    /// Find inode under current inode by name
+    /// Remove the directory entry at `index` by moving the last entry into its place
+    fn remove_dirent(&self, index: usize, disk_inode: &mut DiskInode) {
+        let file_count = (disk_inode.size as usize) / DIRENT_SZ;
+        let last = file_count - 1;
+        if index != last {
+            let mut dirent = DirEntry::empty();
+            disk_inode.read_at(DIRENT_SZ * last, dirent.as_bytes_mut(), &self.block_device);
+            disk_inode.write_at(DIRENT_SZ * index, dirent.as_bytes(), &self.block_device);
+        }
+        // the data block is kept and will be reused when the directory grows again
+        disk_inode.size -= DIRENT_SZ as u32;
+    }
+
+    /// Find inode under current inode by name, return None if current inode is not a directory
     pub fn find(&self, name: &str) -> Option<Arc<Inode>> {
         let fs = self.fs.lock();
         self.read_disk_inode(|disk_inode| {
+            if !disk_inode.is_dir() {
+                return None;
+            }
             self.find_inode_id(name, disk_inode).map(|inode_id| {
                 let (block_id, block_offset) = fs.get_disk_inode_pos(inode_id);
                 Arc::new(Self::new(
@@ -78,6 +101,21 @@ impl Inode {
         })
     }
 
+    /// Find inode by a `/`-separated path relative to current inode
+    pub fn find_path(&self, path: &str) -> Option<Arc<Inode>> {
+        let mut inode = Arc::new(self.clone());
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            inode = inode.find(name)?;
+        }
+        Some(inode)
+    }
+
+    /// Whether current inode is a directory
+    pub fn is_dir(&self) -> bool {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| disk_inode.is_dir())
+    }
+
     /// Increase the size of a disk inode
     fn increase_size(
         &self,
@@ -96,9 +134,59 @@ impl Inode {
         disk_inode.increase_size(new_size, v, &self.block_device);
     }
 
+    /// Append a directory entry to a disk inode
+    fn push_dirent(
+        &self,
+        dirent: DirEntry,
+        disk_inode: &mut DiskInode,
+        fs: &mut MutexGuard<EasyFileSystem>,
+    ) {
+        let file_count = (disk_inode.size as usize) / DIRENT_SZ;
+        let new_size = (file_count + 1) * DIRENT_SZ;
+        // increase size
+        self.increase_size(new_size as u32, disk_inode, fs);
+        // write dirent
+        disk_inode.write_at(
+            file_count * DIRENT_SZ,
+            dirent.as_bytes(),
+            &self.block_device,
+        );
+    }
+
     /// Create inode under current inode by name.
     /// Attention: use find previously to ensure the new file not existing.
     pub fn create(&self, name: &str) -> Option<Arc<Inode>> {
+        self.create_inode(name, DiskInodeType::File)
+    }
+
+    /// Create a directory with `.` and `..` entries under current inode by name.
+    /// Attention: use find previously to ensure the new directory not existing.
+    pub fn create_dir(&self, name: &str) -> Option<Arc<Inode>> {
+        let parent_id = self
+            .fs
+            .lock()
+            .get_inode_id(self.block_id, self.block_offset);
+        let dir = self.create_inode(name, DiskInodeType::Directory)?;
+        dir.init_dir(parent_id);
+        Some(dir)
+    }
+
+    /// Add `.` and `..` entries to an empty directory
+    pub(crate) fn init_dir(&self, parent_id: u32) {
+        let mut fs = self.fs.lock();
+        let inode_id = fs.get_inode_id(self.block_id, self.block_offset);
+        self.modify_disk_inode(|dir| {
+            self.push_dirent(DirEntry::new(".", inode_id), dir, &mut fs);
+            self.push_dirent(DirEntry::new("..", parent_id), dir, &mut fs);
+        });
+        block_cache_sync_all();
+    }
+
+    /// Create inode of `type_` under current inode by name.
+    fn create_inode(&self, name: &str, type_: DiskInodeType) -> Option<Arc<Inode>> {
+        if name.is_empty() || name.len() > NAME_LENGTH_LIMIT {
+            return None;
+        }
         let mut fs = self.fs.lock();
         // create a new file
         // alloc a inode with an indirect block
@@ -108,21 +196,11 @@ impl Inode {
         get_block_cache(new_inode_block_id as usize, Arc::clone(&self.block_device))
             .lock()
             .modify(new_inode_block_offset, |new_inode: &mut DiskInode| {
-                new_inode.initialize(DiskInodeType::File);
+                new_inode.initialize(type_);
             });
         self.modify_disk_inode(|root_inode| {
             // append file in the dirent
-            let file_count = (root_inode.size as usize) / DIRENT_SZ;
-            let new_size = (file_count + 1) * DIRENT_SZ;
-            // increase size
-            self.increase_size(new_size as u32, root_inode, &mut fs);
-            // write dirent
-            let dirent = DirEntry::new(name, new_inode_id);
-            root_inode.write_at(
-                file_count * DIRENT_SZ,
-                dirent.as_bytes(),
-                &self.block_device,
-            );
+            self.push_dirent(DirEntry::new(name, new_inode_id), root_inode, &mut fs);
         });
 
         let (block_id, block_offset) = fs.get_disk_inode_pos(new_inode_id);
@@ -137,6 +215,110 @@ impl Inode {
         // release efs lock automatically by compiler
     }
 
+    /// Move the entry `old_name` under current inode to `new_name` under `new_parent`.
+    /// An existing file named `new_name` is replaced and its inode is released.
+    /// Return false if `old_name` does not exist, `new_name` is an existing directory,
+    /// or `old_name` is a directory and `new_parent` is that directory or one of its descendants.
+    pub fn rename(&self, old_name: &str, new_parent: &Inode, new_name: &str) -> bool {
+        if new_name.is_empty() || new_name.len() > NAME_LENGTH_LIMIT {
+            return false;
+        }
+        if matches!(old_name, "." | "..") || matches!(new_name, "." | "..") {
+            return false;
+        }
+        let mut fs = self.fs.lock();
+        let Some(inode_id) = self.read_disk_inode(|dir| self.find_inode_id(old_name, dir)) else {
+            return false;
+        };
+        let same_dir =
+            self.block_id == new_parent.block_id && self.block_offset == new_parent.block_offset;
+        if same_dir && old_name == new_name {
+            return true;
+        }
+        // a directory cannot be moved into itself or below itself
+        let new_parent_id = fs.get_inode_id(new_parent.block_id, new_parent.block_offset);
+        if self.is_ancestor(inode_id, new_parent_id, &fs) {
+            return false;
+        }
+        let existing = new_parent.read_disk_inode(|dir| new_parent.find_dirent(new_name, dir));
+        if let Some((_, existing_id)) = existing {
+            if existing_id != inode_id
+                && self
+                    .disk_inode_of(existing_id, &fs)
+                    .read_disk_inode(|disk_inode| disk_inode.is_dir())
+            {
+                return false;
+            }
+        }
+        // point `new_name` to the inode, replacing the old target if present
+        let dirent = DirEntry::new(new_name, inode_id);
+        new_parent.modify_disk_inode(|dir| match existing {
+            Some((index, _)) => {
+                dir.write_at(index * DIRENT_SZ, dirent.as_bytes(), &self.block_device);
+            }
+            None => new_parent.push_dirent(dirent, dir, &mut fs),
+        });
+        // drop `old_name`
+        self.modify_disk_inode(|dir| {
+            let (index, _) = self.find_dirent(old_name, dir).unwrap();
+            self.remove_dirent(index, dir);
+        });
+        // a moved directory gets a new `..`
+        let moved = self.disk_inode_of(inode_id, &fs);
+        if !same_dir && moved.read_disk_inode(|disk_inode| disk_inode.is_dir()) {
+            let parent_id = fs.get_inode_id(new_parent.block_id, new_parent.block_offset);
+            moved.modify_disk_inode(|dir| {
+                let (index, _) = moved.find_dirent("..", dir).unwrap();
+                let dirent = DirEntry::new("..", parent_id);
+                dir.write_at(index * DIRENT_SZ, dirent.as_bytes(), &self.block_device);
+            });
+        }
+        // release the replaced inode
+        if let Some((_, replaced)) = existing.filter(|&(_, id)| id != inode_id) {
+            let data_blocks_dealloc = self
+                .disk_inode_of(replaced, &fs)
+                .modify_disk_inode(|disk_inode| disk_inode.clear_size(&self.block_device));
+            for data_block in data_blocks_dealloc.into_iter() {
+                fs.dealloc_data(data_block);
+            }
+            fs.dealloc_inode(replaced);
+        }
+        block_cache_sync_all();
+        true
+    }
+
+    /// Whether `ancestor_id` is directory `dir_id` itself or one of its ancestors,
+    /// found by following `..` up to the root
+    fn is_ancestor(
+        &self,
+        ancestor_id: u32,
+        mut dir_id: u32,
+        fs: &MutexGuard<EasyFileSystem>,
+    ) -> bool {
+        loop {
+            if dir_id == ancestor_id {
+                return true;
+            }
+            let dir = self.disk_inode_of(dir_id, fs);
+            // the root's `..` points to itself
+            match dir.read_disk_inode(|disk_inode| dir.find_inode_id("..", disk_inode)) {
+                Some(parent_id) if parent_id != dir_id => dir_id = parent_id,
+                _ => return false,
+            }
+        }
+    }
+
+    /// Get a vfs inode by inode id
+    fn disk_inode_of(&self, inode_id: u32, fs: &MutexGuard<EasyFileSystem>) -> Inode {
+        let (block_id, block_offset) = fs.get_disk_inode_pos(inode_id);
+        Self::new(
+            block_id,
+            block_offset,
+            self.fs.clone(),
+            self.block_device.clone(),
+        )
+    }
+
     /// List inodes by id under current inode
     pub fn readdir(&self) -> Vec<String> {
         let _fs = self.fs.lock();
@@ -161,17 +343,43 @@ impl Inode {
         self.read_disk_inode(|disk_inode| disk_inode.read_at(offset, buf, &self.block_device))
     }
 
-    /// Write data to current inode
+    /// Write data to current inode.
+    ///
+    /// Write-back policy: file data and size stay in the block cache until the blocks are
+    /// evicted or [`sync`](Self::sync) is called. Block allocation changes the bitmaps and
+    /// index blocks shared by the whole filesystem, so it is written through immediately.
     pub fn write_at(&self, offset: usize, buf: &[u8]) -> usize {
         let mut fs = self.fs.lock();
-        let size = self.modify_disk_inode(|disk_inode| {
+        let (size, allocated) = self.modify_disk_inode(|disk_inode| {
+            let blocks = DiskInode::total_blocks(disk_inode.size);
             self.increase_size((offset + buf.len()) as u32, disk_inode, &mut fs);
-            disk_inode.write_at(offset, buf, &self.block_device)
+            let allocated = DiskInode::total_blocks(disk_inode.size) != blocks;
+            (
+                disk_inode.write_at(offset, buf, &self.block_device),
+                allocated,
+            )
         });
-        block_cache_sync_all();
+        if allocated {
+            block_cache_sync_all();
+        }
         size
     }
 
+    /// Usage statistics of the filesystem this inode lives on
+    pub fn fs_stat(&self) -> FsStat {
+        self.fs.lock().stat()
+    }
+
+    /// Write dirty blocks of current inode back to the block device
+    pub fn sync(&self) {
+        let _fs = self.fs.lock();
+        let blocks = self.read_disk_inode(|disk_inode| disk_inode.blocks(&self.block_device));
+        block_cache_sync(self.block_id);
+        for block_id in blocks {
+            block_cache_sync(block_id as usize);
+        }
+    }
+
     /// Clear the data in current inode
     pub fn clear(&self) {
         let mut fs = self.fs.lock();
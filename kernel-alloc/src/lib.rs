@@ -18,12 +18,7 @@ use customizable_buddy::{BuddyAllocator, LinkedListBuddy, UsizeBuddy};
 /// 参数 `base_address` 表示动态内存区域的起始位置。
 #[inline]
 pub fn init(base_address: usize) {
-    unsafe {
-        HEAP.init(
-            core::mem::size_of::<usize>().trailing_zeros() as _,
-            NonNull::new(base_address as *mut u8).unwrap(),
-        )
-    };
+    unsafe { HEAP.init(MIN_ORDER, NonNull::new(base_address as *mut u8).unwrap()) };
 }
 
 /// 将一个内存块托管到内存分配器。
@@ -38,11 +33,37 @@ pub unsafe fn transfer(region: &'static mut [u8]) {
     HEAP.transfer(ptr, region.len());
 }
 
+/// 当前能分配的最大块的字节数，没有空闲块时返回 0。
+///
+/// 分配器没有公开各层的空闲链表，所以从最高层向下试探分配并立即归还，
+/// 第一个成功的阶就是最高的非空空闲层。
+pub fn largest_free_block() -> usize {
+    for order in (MIN_ORDER..=MAX_ORDER).rev() {
+        let layout = unsafe { Layout::from_size_align_unchecked(1 << order, 1 << order) };
+        if let Ok((ptr, _)) = unsafe { HEAP.allocate_layout::<u8>(layout) } {
+            unsafe { HEAP.deallocate_layout(ptr, layout) };
+            return 1 << order;
+        }
+    }
+    0
+}
+
+/// 分配器层数。
+const LEVELS: usize = 21;
+
+/// 最小块的阶数。
+const MIN_ORDER: usize = core::mem::size_of::<usize>().trailing_zeros() as _;
+
+/// 最大块的阶数。
+///
+/// 超过最大块的请求直接失败，不交给分配器去计算越过 usize 位宽的位移。
+const MAX_ORDER: usize = MIN_ORDER + LEVELS;
+
 /// 堆分配器。
 ///
 /// 最大容量：6 + 21 + 3 = 30 -> 1 GiB。
 /// 不考虑并发使用，因此没有加锁。
-static mut HEAP: BuddyAllocator<21, UsizeBuddy, LinkedListBuddy> = BuddyAllocator::new();
+static mut HEAP: BuddyAllocator<LEVELS, UsizeBuddy, LinkedListBuddy> = BuddyAllocator::new();
 
 struct Global;
 
@@ -52,6 +73,9 @@ static GLOBAL: Global = Global;
 unsafe impl GlobalAlloc for Global {
     #[inline]
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() > 1 << MAX_ORDER || layout.align() > 1 << MAX_ORDER {
+            handle_alloc_error(layout)
+        }
         if let Ok((ptr, _)) = HEAP.allocate_layout::<u8>(layout) {
             ptr.as_ptr()
         } else {
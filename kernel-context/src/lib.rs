@@ -60,6 +60,30 @@ impl LocalContext {
         }
     }
 
+    /// 复制上下文，作为 fork 出的子进程的上下文。
+    ///
+    /// 子进程从同一位置继续执行，但 fork 的返回值是 0。
+    /// 上下文关联到哪个地址空间由调用者负责。
+    #[inline]
+    pub fn fork_child(&self) -> Self {
+        let mut ctx = self.clone();
+        *ctx.a_mut(0) = 0;
+        ctx
+    }
+
+    /// 复制上下文，作为同一地址空间中新线程的上下文。
+    ///
+    /// 新线程从 `entry` 开始执行，栈指针是 `sp`，第一个参数是 `arg`，
+    /// 其他寄存器（如 `gp`）和特权态、中断设置沿用当前上下文。
+    #[inline]
+    pub fn spawn_thread(&self, entry: usize, sp: usize, arg: usize) -> Self {
+        let mut ctx = self.clone();
+        ctx.sepc = entry;
+        *ctx.sp_mut() = sp;
+        *ctx.a_mut(0) = arg;
+        ctx
+    }
+
     /// 读取用户通用寄存器。
     #[inline]
     pub fn x(&self, n: usize) -> usize {
@@ -42,4 +42,22 @@ pub trait PageManager<Meta: VmMeta> {
 
     /// 释放根页表。
     fn drop_root(&mut self);
+
+    /// 让 `pte` 指示的物理页被另一个地址空间写时复制地共享，见 [`AddressSpace::fork`]。
+    ///
+    /// 返回共享之后两个地址空间都使用的页属性。返回 `None` 表示这个页不共享，由调用者复制一份。
+    /// 默认不支持写时复制，总是返回 `None`。
+    #[inline]
+    fn share_cow(&mut self, pte: Pte<Meta>) -> Option<VmFlags<Meta>> {
+        let _ = pte;
+        None
+    }
+
+    /// 补充设备内存映射的页属性。
+    ///
+    /// 支持 Svpbmt 等扩展的实现可以在这里把页标记为不可缓存的 IO 内存，默认不做修改。
+    #[inline]
+    fn device_flags(&self, flags: VmFlags<Meta>) -> VmFlags<Meta> {
+        flags
+    }
 }
@@ -1,4 +1,6 @@
 mod mapper;
+mod protector;
+mod unmapper;
 mod visitor;
 
 extern crate alloc;
@@ -7,7 +9,9 @@ use crate::PageManager;
 use alloc::vec::Vec;
 use core::{fmt, ops::Range, ptr::NonNull};
 use mapper::Mapper;
-use page_table::{PageTable, PageTableFormatter, Pos, VAddr, VmFlags, VmMeta, PPN, VPN};
+use page_table::{PageTable, PageTableFormatter, Pos, Pte, VAddr, VmFlags, VmMeta, PPN, VPN};
+use protector::Protector;
+use unmapper::Unmapper;
 use visitor::Visitor;
 
 /// 地址空间。
@@ -40,7 +44,26 @@ impl<Meta: VmMeta, M: PageManager<Meta>> AddressSpace<Meta, M> {
     }
 
     /// 向地址空间增加映射关系。
+    ///
+    /// `range` 中已有映射时 panic，见 [`try_map_extern`](Self::try_map_extern)。
     pub fn map_extern(&mut self, range: Range<VPN<Meta>>, pbase: PPN<Meta>, flags: VmFlags<Meta>) {
+        if let Err(vpn) = self.try_map_extern(range, pbase, flags) {
+            panic!("{vpn:?} is already mapped");
+        }
+    }
+
+    /// 向地址空间增加映射关系。
+    ///
+    /// `range` 中已有映射时不做任何修改，返回第一个冲突的虚页号。
+    pub fn try_map_extern(
+        &mut self,
+        range: Range<VPN<Meta>>,
+        pbase: PPN<Meta>,
+        flags: VmFlags<Meta>,
+    ) -> Result<(), VPN<Meta>> {
+        if let Some(vpn) = self.find_mapped(range.clone()) {
+            return Err(vpn);
+        }
         self.areas.push(range.start..range.end);
         let count = range.end.val() - range.start.val();
         let mut root = self.root();
@@ -50,30 +73,237 @@ impl<Meta: VmMeta, M: PageManager<Meta>> AddressSpace<Meta, M> {
             // 映射失败，需要回滚吗？
             todo!()
         }
+        Ok(())
+    }
+
+    /// 将物理地址 `pbase` 起的设备寄存器映射到 `range`。
+    ///
+    /// 与 [`map_extern`](Self::map_extern) 相同，地址空间不拥有这些物理页，
+    /// 但页属性会经过 [`PageManager::device_flags`] 补充设备内存（不可缓存、访问有副作用）的属性。
+    /// `flags` 通常是不可执行的 `_WRV`。`range` 中已有映射时 panic。
+    pub fn map_device(&mut self, range: Range<VPN<Meta>>, pbase: PPN<Meta>, flags: VmFlags<Meta>) {
+        let flags = self.page_manager.device_flags(flags);
+        if let Err(vpn) = self.try_map_extern(range, pbase, flags) {
+            panic!("{vpn:?} is already mapped");
+        }
     }
 
     /// 分配新的物理页，拷贝数据并建立映射。
+    ///
+    /// `range` 中已有映射时 panic，见 [`try_map`](Self::try_map)。
     pub fn map(
         &mut self,
         range: Range<VPN<Meta>>,
         data: &[u8],
         offset: usize,
-        mut flags: VmFlags<Meta>,
+        flags: VmFlags<Meta>,
     ) {
+        if let Err(vpn) = self.try_map(range, data, offset, flags) {
+            panic!("{vpn:?} is already mapped");
+        }
+    }
+
+    /// 分配新的物理页，拷贝数据并建立映射。
+    ///
+    /// 每个虚页单独分配一个物理页，所以 [`unmap`](Self::unmap) 可以逐页释放任意一部分。
+    /// `range` 中已有映射时不分配物理页，返回第一个冲突的虚页号。
+    pub fn try_map(
+        &mut self,
+        range: Range<VPN<Meta>>,
+        data: &[u8],
+        offset: usize,
+        flags: VmFlags<Meta>,
+    ) -> Result<(), VPN<Meta>> {
+        if let Some(vpn) = self.find_mapped(range.clone()) {
+            return Err(vpn);
+        }
         let count = range.end.val() - range.start.val();
-        let size = count << Meta::PAGE_BITS;
-        assert!(size >= data.len() + offset);
-        let page = self.page_manager.allocate(count, &mut flags);
-        unsafe {
-            use core::slice::from_raw_parts_mut as slice;
-            let mut ptr = page.as_ptr();
-            slice(ptr, offset).fill(0);
-            ptr = ptr.add(offset);
-            slice(ptr, data.len()).copy_from_slice(data);
-            ptr = ptr.add(data.len());
-            slice(ptr, page.as_ptr().add(size).offset_from(ptr) as _).fill(0);
+        assert!(count << Meta::PAGE_BITS >= data.len() + offset);
+        self.map_pages(range, flags, |i, page| {
+            // 第 `i` 页在整段内容中的字节范围与 `data` 的交集
+            let start = i << Meta::PAGE_BITS;
+            let lo = offset.clamp(start, start + page.len());
+            let hi = (offset + data.len()).clamp(start, start + page.len());
+            page.fill(0);
+            if lo < hi {
+                page[lo - start..hi - start].copy_from_slice(&data[lo - offset..hi - offset]);
+            }
+        });
+        Ok(())
+    }
+
+    /// 为 `range` 中的每个虚页分配一个物理页，用 `fill` 写入第 `i` 页的内容，然后建立映射。
+    ///
+    /// 不检查 `range` 中是否已有映射。
+    fn map_pages(
+        &mut self,
+        range: Range<VPN<Meta>>,
+        mut flags: VmFlags<Meta>,
+        mut fill: impl FnMut(usize, &mut [u8]),
+    ) {
+        self.areas.push(range.start..range.end);
+        let mut root = self.root();
+        let mut vpn = range.start;
+        while vpn < range.end {
+            let page = self.page_manager.allocate(1, &mut flags);
+            let i = vpn.val() - range.start.val();
+            fill(i, unsafe {
+                core::slice::from_raw_parts_mut(page.as_ptr(), 1 << Meta::PAGE_BITS)
+            });
+            let ppn = self.page_manager.v_to_p(page);
+            let mut mapper = Mapper::new(self, ppn..ppn + 1, flags);
+            root.walk_mut(Pos::new(vpn, 0), &mut mapper);
+            if !mapper.ans() {
+                // 映射失败，需要回滚吗？
+                todo!()
+            }
+            vpn += 1;
         }
-        self.map_extern(range, self.page_manager.v_to_p(page), flags)
+    }
+
+    /// 移除 `range` 中的映射关系，并释放地址空间拥有的物理页。
+    ///
+    /// 拥有的物理页都是逐页分配的，见 [`try_map`](Self::try_map)，所以逐页释放。
+    /// `range` 中没有映射的虚页被跳过，中间页表保持不变。返回实际移除映射的页数。
+    pub fn unmap(&mut self, range: Range<VPN<Meta>>) -> usize {
+        let mut root = self.root();
+        let mut count = 0;
+        let mut vpn = range.start;
+        while vpn < range.end {
+            let mut unmapper = Unmapper::new(self);
+            root.walk_mut(Pos::new(vpn, 0), &mut unmapper);
+            if let Some(pte) = unmapper.ans() {
+                if self.page_manager.check_owned(pte) {
+                    self.page_manager.deallocate(pte, 1);
+                }
+                count += 1;
+            }
+            vpn += 1;
+        }
+        // 从虚拟地址块中挖去 `range`
+        for area in core::mem::take(&mut self.areas) {
+            if area.end <= range.start || range.end <= area.start {
+                self.areas.push(area);
+                continue;
+            }
+            if area.start < range.start {
+                self.areas.push(area.start..range.start);
+            }
+            if range.end < area.end {
+                self.areas.push(range.end..area.end);
+            }
+        }
+        count
+    }
+
+    /// 将 `range` 中每个页的 `mask` 属性位替换为 `flags`，物理页号和 `mask` 以外的属性位不变。
+    ///
+    /// `range` 中有未映射的虚页时不做任何修改，返回第一个未映射的虚页号。
+    /// 不会刷新快表，由调用者负责。
+    pub fn protect(
+        &mut self,
+        range: Range<VPN<Meta>>,
+        mask: VmFlags<Meta>,
+        flags: VmFlags<Meta>,
+    ) -> Result<(), VPN<Meta>> {
+        let root = self.root();
+        let mut vpn = range.start;
+        while vpn < range.end {
+            let mut visitor = Visitor::new(self);
+            root.walk(Pos::new(vpn, 0), &mut visitor);
+            if visitor.ans().is_none() {
+                return Err(vpn);
+            }
+            vpn += 1;
+        }
+        let mut root = self.root();
+        let mut vpn = range.start;
+        while vpn < range.end {
+            let mut protector = Protector::new(self, mask, flags);
+            root.walk_mut(Pos::new(vpn, 0), &mut protector);
+            assert!(protector.ans());
+            vpn += 1;
+        }
+        Ok(())
+    }
+
+    /// 复制出一个包含相同映射的新地址空间。
+    ///
+    /// 不属于地址空间的页按引用共享，属性不变。拥有的页在 `cow` 为 `true` 时交给
+    /// [`PageManager::share_cow`]，它同意共享时两个地址空间都指向原来的物理页并换成它返回的属性，
+    /// 否则新地址空间分配新的物理页并复制内容。
+    ///
+    /// 只复制 [`areas`](Self::areas) 记录的映射，直接写入根页表的项（例如与内核共享的页表）需要调用者另外处理。
+    /// 修改了当前地址空间的页属性时不刷新快表，由调用者负责。
+    /// 与 [`cloneself`](Self::cloneself) 不同，这里逐页处理，不要求一个虚拟地址块的物理页连续。
+    pub fn fork(&mut self, cow: bool) -> Self {
+        let mut space = Self::new();
+        for area in self.areas.clone() {
+            let mut vpn = area.start;
+            while vpn < area.end {
+                let range = vpn..vpn + 1;
+                vpn += 1;
+                let Some(pte) = self.pte(range.start) else {
+                    continue;
+                };
+                let flags = pte.flags();
+                if !self.page_manager.check_owned(pte) {
+                    space.map_extern(range, pte.ppn(), flags);
+                    continue;
+                }
+                let shared = if cow {
+                    self.page_manager.share_cow(pte)
+                } else {
+                    None
+                };
+                if let Some(shared) = shared {
+                    self.protect(range.clone(), flags, shared).unwrap();
+                    space.map_extern(range, pte.ppn(), shared);
+                } else {
+                    let page = unsafe {
+                        core::slice::from_raw_parts(
+                            self.page_manager.p_to_v::<u8>(pte.ppn()).as_ptr(),
+                            1 << Meta::PAGE_BITS,
+                        )
+                    };
+                    space.map(range, page, 0, flags);
+                }
+            }
+        }
+        space
+    }
+
+    /// 释放地址空间拥有的全部物理页和页表，见 [`PageManager::drop_root`]。
+    ///
+    /// # Safety
+    ///
+    /// 调用后根页表已经释放，地址空间不能再使用，只能丢弃。
+    /// 与其他地址空间共享的页表项必须先从根页表中移除。
+    pub unsafe fn free(&mut self) {
+        self.areas.clear();
+        self.page_manager.drop_root();
+    }
+
+    /// 找到 `range` 中第一个已经映射的虚页。
+    pub fn find_mapped(&self, range: Range<VPN<Meta>>) -> Option<VPN<Meta>> {
+        let root = self.root();
+        let mut vpn = range.start;
+        while vpn < range.end {
+            let mut visitor = Visitor::new(self);
+            root.walk(Pos::new(vpn, 0), &mut visitor);
+            if visitor.ans().is_some() {
+                return Some(vpn);
+            }
+            vpn += 1;
+        }
+        None
+    }
+
+    /// 查询虚页 `vpn` 的页表项，没有映射时返回 `None`。
+    pub fn pte(&self, vpn: VPN<Meta>) -> Option<Pte<Meta>> {
+        let mut visitor = Visitor::new(self);
+        self.root().walk(Pos::new(vpn, 0), &mut visitor);
+        visitor.ans()
     }
 
     /// 检查 `flags` 的属性要求，然后将地址空间中的一个虚地址翻译成当前地址空间中的指针。
@@ -94,40 +324,55 @@ impl<Meta: VmMeta, M: PageManager<Meta>> AddressSpace<Meta, M> {
             })
     }
 
+    /// 检查 `range` 覆盖的每一页都满足 `flags` 的属性要求，然后把它翻译成当前地址空间中的若干段。
+    ///
+    /// 每段是起始指针和字节数，按虚地址顺序排列，物理上相邻的页合并成一段。
+    /// 任何一页没有映射或属性不符时返回 `None`，空范围返回空表。
+    pub fn translate_range(
+        &self,
+        range: Range<VAddr<Meta>>,
+        flags: VmFlags<Meta>,
+    ) -> Option<Vec<(NonNull<u8>, usize)>> {
+        let page_mask = (1usize << Meta::PAGE_BITS) - 1;
+        // 用裁剪后的地址值计算，范围的结尾恰好是地址空间一半的上界时 `val` 会做符号扩展
+        let raw = |addr: VAddr<Meta>| (addr.floor().val() << Meta::PAGE_BITS) | addr.offset();
+        let end = raw(range.end);
+        let mut addr = raw(range.start);
+        if end < addr {
+            return None;
+        }
+        let mut segments: Vec<(NonNull<u8>, usize)> = Vec::new();
+        while addr < end {
+            let ptr = self.translate::<u8>(VAddr::new(addr), flags)?;
+            let len = end.min((addr | page_mask).saturating_add(1)) - addr;
+            match segments.last_mut() {
+                Some((last, last_len)) if last.as_ptr().wrapping_add(*last_len) == ptr.as_ptr() => {
+                    *last_len += len;
+                }
+                _ => segments.push((ptr, len)),
+            }
+            addr += len;
+        }
+        Some(segments)
+    }
+
     /// 遍历地址空间，将其中的地址映射添加进自己的地址空间中，重新分配物理页并拷贝所有数据及代码
     pub fn cloneself(&self, new_addrspace: &mut AddressSpace<Meta, M>) {
-        let root = self.root();
-        let areas = &self.areas;
-        for (_, range) in areas.iter().enumerate() {
-            let mut visitor = Visitor::new(self);
-            // 虚拟地址块的首地址的 vpn
-            let vpn = range.start;
-            // 利用 visitor 访问页表，并获取这个虚拟地址块的页属性
-            root.walk(Pos::new(vpn, 0), &mut visitor);
-            // 利用 visitor 获取这个虚拟地址块的页属性，以及起始地址
-            let (mut flags, mut data_ptr) = visitor
-                .ans()
+        for range in &self.areas {
+            // 利用虚拟地址块首页的页表项获取这个虚拟地址块的页属性
+            let flags = self
+                .pte(range.start)
                 .filter(|pte| pte.is_valid())
-                .map(|pte| {
-                    (pte.flags(), unsafe {
-                        NonNull::new_unchecked(self.page_manager.p_to_v::<u8>(pte.ppn()).as_ptr())
-                    })
-                })
-                .unwrap();
-            let vpn_range = range.start..range.end;
-            // 虚拟地址块中页数量
-            let count = range.end.val() - range.start.val();
-            let size = count << Meta::PAGE_BITS;
-            // 分配 count 个 flags 属性的物理页面
-            let paddr = new_addrspace.page_manager.allocate(count, &mut flags);
-            let ppn = new_addrspace.page_manager.v_to_p(paddr);
-            unsafe {
-                use core::slice::from_raw_parts_mut as slice;
-                let data = slice(data_ptr.as_mut(), size);
-                let ptr = paddr.as_ptr();
-                slice(ptr, size).copy_from_slice(data);
-            }
-            new_addrspace.map_extern(vpn_range, ppn, flags);
+                .unwrap()
+                .flags();
+            // 物理页逐页分配，虚拟地址块中的页在物理上不一定连续，逐页查询源页再拷贝
+            new_addrspace.map_pages(range.clone(), flags, |i, page| {
+                let pte = self.pte(range.start + i).unwrap();
+                let data = self.page_manager.p_to_v::<u8>(pte.ppn());
+                page.copy_from_slice(unsafe {
+                    core::slice::from_raw_parts(data.as_ptr(), page.len())
+                });
+            });
         }
     }
 }
@@ -0,0 +1,58 @@
+use crate::{AddressSpace, PageManager};
+use core::ptr::NonNull;
+use page_table::{Decorator, Pos, Pte, Update, VmFlags, VmMeta};
+
+pub(super) struct Protector<'a, Meta: VmMeta, M: PageManager<Meta>> {
+    space: &'a AddressSpace<Meta, M>,
+    mask: VmFlags<Meta>,
+    flags: VmFlags<Meta>,
+    done: bool,
+}
+
+impl<'a, Meta: VmMeta, M: PageManager<Meta>> Protector<'a, Meta, M> {
+    #[inline]
+    pub const fn new(
+        space: &'a AddressSpace<Meta, M>,
+        mask: VmFlags<Meta>,
+        flags: VmFlags<Meta>,
+    ) -> Self {
+        Self {
+            space,
+            mask,
+            flags,
+            done: false,
+        }
+    }
+
+    #[inline]
+    pub const fn ans(self) -> bool {
+        self.done
+    }
+}
+
+impl<Meta: VmMeta, M: PageManager<Meta>> Decorator<Meta> for Protector<'_, Meta, M> {
+    #[inline]
+    fn arrive(&mut self, pte: &mut Pte<Meta>, _target_hint: Pos<Meta>) -> Pos<Meta> {
+        if pte.is_valid() {
+            let old = pte.flags();
+            *pte = ((old ^ (old & self.mask)) | self.flags).build_pte(pte.ppn());
+            self.done = true;
+        }
+        Pos::stop()
+    }
+
+    #[inline]
+    fn meet(
+        &mut self,
+        _level: usize,
+        pte: Pte<Meta>,
+        _target_hint: Pos<Meta>,
+    ) -> Option<NonNull<Pte<Meta>>> {
+        Some(self.space.page_manager.p_to_v(pte.ppn()))
+    }
+
+    #[inline]
+    fn block(&mut self, _level: usize, _pte: Pte<Meta>, _target_hint: Pos<Meta>) -> Update<Meta> {
+        Update::Target(Pos::stop())
+    }
+}
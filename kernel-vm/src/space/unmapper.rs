@@ -0,0 +1,46 @@
+use crate::{AddressSpace, PageManager};
+use core::ptr::NonNull;
+use page_table::{Decorator, Pos, Pte, Update, VmMeta};
+
+pub(super) struct Unmapper<'a, Meta: VmMeta, M: PageManager<Meta>> {
+    space: &'a AddressSpace<Meta, M>,
+    ans: Option<Pte<Meta>>,
+}
+
+impl<'a, Meta: VmMeta, M: PageManager<Meta>> Unmapper<'a, Meta, M> {
+    #[inline]
+    pub const fn new(space: &'a AddressSpace<Meta, M>) -> Self {
+        Self { space, ans: None }
+    }
+
+    #[inline]
+    pub const fn ans(self) -> Option<Pte<Meta>> {
+        self.ans
+    }
+}
+
+impl<Meta: VmMeta, M: PageManager<Meta>> Decorator<Meta> for Unmapper<'_, Meta, M> {
+    #[inline]
+    fn arrive(&mut self, pte: &mut Pte<Meta>, _target_hint: Pos<Meta>) -> Pos<Meta> {
+        if pte.is_valid() {
+            self.ans = Some(*pte);
+            *pte = Pte::ZERO;
+        }
+        Pos::stop()
+    }
+
+    #[inline]
+    fn meet(
+        &mut self,
+        _level: usize,
+        pte: Pte<Meta>,
+        _target_hint: Pos<Meta>,
+    ) -> Option<NonNull<Pte<Meta>>> {
+        Some(self.space.page_manager.p_to_v(pte.ppn()))
+    }
+
+    #[inline]
+    fn block(&mut self, _level: usize, _pte: Pte<Meta>, _target_hint: Pos<Meta>) -> Update<Meta> {
+        Update::Target(Pos::stop())
+    }
+}
@@ -1,4 +1,22 @@
-﻿/// 应用程序元数据。
+﻿use crate::KernelLayout;
+use core::ffi::CStr;
+
+/// 应用程序数量的上限，超过这个数的表视为损坏。
+const MAX_APPS: usize = 1024;
+
+/// 每个非位置无关的应用程序占用的槽大小。
+const APP_SLOT: usize = 0x20_0000;
+
+/// 表损坏时使用的空表。
+static EMPTY: AppMeta = AppMeta {
+    base: 0,
+    step: 0,
+    count: 0,
+    names: 0,
+    first: 0,
+};
+
+/// 应用程序元数据。
 /// 
 /// 根据目标架构，数据宽度可能是 32 位或 64 位。
 #[repr(C)]
@@ -6,23 +24,106 @@ pub struct AppMeta {
     base: usize,
     step: usize,
     count: usize,
+    /// 名字表的地址，0 表示没有名字表。
+    ///
+    /// 名字表是依次排列的 `count` 个以 NUL 结尾的字符串。
+    names: usize,
     first: usize,
 }
 
 impl AppMeta {
     /// 定位应用程序。
+    ///
+    /// 表不合法时返回一个空表。
     #[inline]
     pub fn locate() -> &'static Self {
         extern "C" {
             static apps: AppMeta;
         }
-        unsafe { &apps }
+        unsafe { &apps }.checked()
+    }
+
+    /// 检查表是否合法，不合法时返回一个空表。
+    ///
+    /// 要求应用程序数量不超过上限、位置表单调不减，
+    /// 并且拷贝的目标区域不会越界或覆盖内核。
+    pub fn checked(&'static self) -> &'static Self {
+        if self.is_consistent() {
+            self
+        } else {
+            &EMPTY
+        }
+    }
+
+    fn is_consistent(&self) -> bool {
+        if self.count > MAX_APPS {
+            return false;
+        }
+        // 先确定数量不超过上限，再读位置表
+        let table =
+            unsafe { core::slice::from_raw_parts(&self.first as *const usize, self.count + 1) };
+        if table.windows(2).any(|w| w[1] < w[0]) {
+            return false;
+        }
+        if self.base == 0 || self.count == 0 {
+            return true;
+        }
+        let end = (self.count - 1)
+            .checked_mul(self.step)
+            .and_then(|offset| offset.checked_add(self.base))
+            .and_then(|last| last.checked_add(APP_SLOT));
+        match end {
+            Some(end) => {
+                let kernel = KernelLayout::locate();
+                end <= kernel.start() || kernel.end() <= self.base
+            }
+            None => false,
+        }
+    }
+
+    /// 链接进来的应用程序数量。
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// 是否没有链接任何应用程序。
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
     }
 
     /// 遍历链接进来的应用程序。
+    ///
+    /// 表不合法时什么也不产生。
     #[inline]
     pub fn iter(&'static self) -> AppIterator {
-        AppIterator { meta: self, i: 0 }
+        AppIterator {
+            meta: self.checked(),
+            i: 0,
+        }
+    }
+
+    /// 第 `i` 个应用程序的名字，没有名字表或名字不是 UTF-8 时返回 `None`。
+    pub fn name(&'static self, i: usize) -> Option<&'static str> {
+        if self.names == 0 || i >= self.count {
+            return None;
+        }
+        let mut ptr = self.names as *const core::ffi::c_char;
+        unsafe {
+            for _ in 0..i {
+                ptr = ptr.add(CStr::from_ptr(ptr).to_bytes().len() + 1);
+            }
+            CStr::from_ptr(ptr).to_str().ok()
+        }
+    }
+
+    /// 遍历链接进来的应用程序和它们的名字，没有名字表时什么也不产生。
+    #[inline]
+    pub fn named(&'static self) -> impl Iterator<Item = (&'static str, &'static [u8])> {
+        // 先取名字，没有名字时不会去拷贝应用程序
+        let meta = self.checked();
+        (0..meta.count).map_while(|i| meta.name(i)).zip(meta.iter())
     }
 }
 
@@ -42,21 +143,38 @@ impl Iterator for AppIterator {
             let i = self.i;
             self.i += 1;
             unsafe {
-                let slice = core::slice::from_raw_parts(
-                    &self.meta.first as *const _ as *const usize,
-                    self.meta.count + 1,
-                );
-                let pos = slice[i];
-                let size = slice[i + 1] - pos;
+                // 位置表有 `count + 1` 项，只在 `i < count` 时访问第 `i` 和 `i + 1` 项
+                let table = &self.meta.first as *const _ as *const usize;
+                let pos = *table.add(i);
+                let end = *table.add(i + 1);
+                if end < pos {
+                    self.i = self.meta.count;
+                    return None;
+                }
+                let size = end - pos;
+                let data = core::slice::from_raw_parts(pos as *const u8, size);
                 let base = self.meta.base + i * self.meta.step;
-                if base != 0 {
+                // 位置无关的程序由加载器加上偏移，不需要拷贝到固定位置
+                if base != 0 && !is_pie(data) {
+                    if size > APP_SLOT {
+                        self.i = self.meta.count;
+                        return None;
+                    }
                     core::ptr::copy_nonoverlapping::<u8>(pos as _, base as _, size);
-                    core::slice::from_raw_parts_mut(base as *mut u8, 0x20_0000)[size..].fill(0);
+                    core::slice::from_raw_parts_mut(base as *mut u8, APP_SLOT)[size..].fill(0);
                     Some(core::slice::from_raw_parts(base as _, size))
                 } else {
-                    Some(core::slice::from_raw_parts(pos as _, size))
+                    Some(data)
                 }
             }
         }
     }
 }
+
+/// 判断应用程序是不是位置无关（`ET_DYN`）的 ELF 文件。
+fn is_pie(data: &[u8]) -> bool {
+    const ET_DYN: u16 = 3;
+    data.len() >= 18
+        && data.starts_with(b"\x7fELF")
+        && u16::from_le_bytes([data[16], data[17]]) == ET_DYN
+}
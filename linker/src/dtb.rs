@@ -0,0 +1,61 @@
+use core::ffi::CStr;
+
+/// 在物理地址 `dtb` 处的设备树中找到 `/chosen/bootargs` 属性。
+///
+/// 只遍历结构块，不检查版本和保留内存块。`dtb` 为 0、没有对齐或者魔数不对时返回 `None`。
+///
+/// # Safety
+///
+/// `dtb` 非 0 时必须可以直接访问，通常在启用分页之前调用。
+pub unsafe fn bootargs(dtb: usize) -> Option<&'static str> {
+    const MAGIC: u32 = 0xd00d_feed;
+    const BEGIN_NODE: u32 = 1;
+    const END_NODE: u32 = 2;
+    const PROP: u32 = 3;
+    const NOP: u32 = 4;
+
+    let be32 = |addr: usize| u32::from_be((addr as *const u32).read());
+    let cstr = |addr: usize| CStr::from_ptr(addr as _).to_bytes();
+    let align = |addr: usize| (addr + 3) & !3;
+
+    if dtb == 0 || dtb & 3 != 0 || be32(dtb) != MAGIC {
+        return None;
+    }
+    let strings = dtb + be32(dtb + 12) as usize;
+    let mut p = dtb + be32(dtb + 8) as usize;
+    let end = p + be32(dtb + 36) as usize;
+    let mut depth = 0usize;
+    let mut chosen = false;
+    while p < end {
+        let token = be32(p);
+        p += 4;
+        match token {
+            BEGIN_NODE => {
+                let name = cstr(p);
+                p = align(p + name.len() + 1);
+                depth += 1;
+                if depth == 2 {
+                    chosen = name == b"chosen";
+                }
+            }
+            END_NODE => {
+                depth = depth.checked_sub(1)?;
+                chosen &= depth == 2;
+            }
+            PROP => {
+                let len = be32(p) as usize;
+                let name = cstr(strings + be32(p + 4) as usize);
+                p += 8;
+                if chosen && depth == 2 && name == b"bootargs" {
+                    let value = core::slice::from_raw_parts(p as *const u8, len);
+                    let value = value.split(|&b| b == 0).next().unwrap_or(&[]);
+                    return core::str::from_utf8(value).ok();
+                }
+                p = align(p + len);
+            }
+            NOP => {}
+            _ => break,
+        }
+    }
+    None
+}
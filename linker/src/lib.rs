@@ -28,13 +28,17 @@
 //! 启动数据段放在最后，以便启动完成后换栈。届时可放弃启动数据段，将其加入动态内存区。
 //!
 //! 用 [`KernelLayout`] 结构体定位、保存和访问内核内存布局。
+//!
+//! 用 [`bootargs`] 在启动时从设备树读出内核命令行。
 
 #![no_std]
 #![deny(warnings, missing_docs)]
 
 mod app;
+mod dtb;
 
 pub use app::{AppIterator, AppMeta};
+pub use dtb::bootargs;
 
 /// 链接脚本（使用 RustSBI）。
 pub const SCRIPT: &[u8] = b"\
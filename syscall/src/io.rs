@@ -1,3 +1,188 @@
 ﻿pub const STDIN: usize = 0;
 pub const STDOUT: usize = 1;
 pub const STDDEBUG: usize = 2;
+
+/// 文件描述符的上界（不含），内核不会分配更大的描述符。
+pub const MAX_FD: usize = 1 << 10;
+
+/// `access` 模式：只检查文件是否存在。
+pub const F_OK: usize = 0;
+/// `access` 模式：检查是否可执行。
+pub const X_OK: usize = 1;
+/// `access` 模式：检查是否可写。
+pub const W_OK: usize = 2;
+/// `access` 模式：检查是否可读。
+pub const R_OK: usize = 4;
+
+/// `fcntl` 命令：复制到不小于参数的最小空闲描述符，参数不小于 [`MAX_FD`] 时失败。
+pub const F_DUPFD: usize = 0;
+/// `fcntl` 命令：读取描述符标志。
+pub const F_GETFD: usize = 1;
+/// `fcntl` 命令：设置描述符标志。
+pub const F_SETFD: usize = 2;
+/// `fcntl` 命令：读取文件状态标志。
+pub const F_GETFL: usize = 3;
+/// `fcntl` 命令：设置文件状态标志，只有 [`O_NONBLOCK`] 可以修改。
+pub const F_SETFL: usize = 4;
+/// 描述符标志：`exec` 时关闭。
+pub const FD_CLOEXEC: usize = 1;
+/// 文件状态标志：只读。
+pub const O_RDONLY: usize = 0;
+/// 文件状态标志：只写。
+pub const O_WRONLY: usize = 1;
+/// 文件状态标志：读写。
+pub const O_RDWR: usize = 2;
+/// 文件状态标志：非阻塞读写。
+pub const O_NONBLOCK: usize = 0o4000;
+
+/// 分散/聚集 I/O 中的一段缓冲区。
+///
+/// see <https://man7.org/linux/man-pages/man2/readv.2.html>.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct IoVec {
+    /// 缓冲区起始地址。
+    pub base: usize,
+    /// 缓冲区长度。
+    pub len: usize,
+}
+
+/// `ioctl` 请求：读取终端属性到 [`Termios`]。
+pub const TCGETS: usize = 0x5401;
+/// `ioctl` 请求：用 [`Termios`] 设置终端属性。
+pub const TCSETS: usize = 0x5402;
+
+/// 终端属性。
+///
+/// 只保留本地模式，与 Linux 的 `struct termios` 布局不同。
+///
+/// see <https://man7.org/linux/man-pages/man3/termios.3.html>.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct Termios {
+    /// 本地模式，[`ICANON`] 和 [`ECHO`] 的组合。
+    pub lflag: u32,
+}
+
+/// 规范模式：按行读入，否则每读到一个字符就返回。
+pub const ICANON: u32 = 0o2;
+/// 回显读入的字符。
+pub const ECHO: u32 = 0o10;
+
+impl Termios {
+    /// 默认的规范模式，回显输入。
+    pub const COOKED: Self = Self {
+        lflag: ICANON | ECHO,
+    };
+    /// 原始模式，不回显，逐字符读入。
+    pub const RAW: Self = Self { lflag: 0 };
+}
+
+#[cfg(feature = "kernel")]
+impl Termios {
+    /// 按终端属性从控制台读入 `buf`，返回读到的字节数。
+    ///
+    /// `getchar` 取一个字符，与传统 SBI 的 `console_getchar` 一样在没有输入时返回 `usize::MAX`，
+    /// 这时继续等待；`putchar` 回显一个字符。
+    /// 规范模式下读到换行或填满 `buf` 才返回，退格和删除键擦掉本次读入的最后一个字符；
+    /// 否则读到一个字符就原样返回。
+    pub fn read_console(
+        self,
+        buf: &mut [u8],
+        mut getchar: impl FnMut() -> usize,
+        mut putchar: impl FnMut(u8),
+    ) -> usize {
+        const BS: u8 = 0x08;
+        const DEL: u8 = 0x7f;
+        let mut echo = |c: u8| {
+            if self.lflag & ECHO != 0 {
+                putchar(c);
+            }
+        };
+        let canonical = self.lflag & ICANON != 0;
+        let mut len = 0;
+        while len < buf.len() {
+            let c = loop {
+                match getchar() {
+                    usize::MAX => continue,
+                    c => break c as u8,
+                }
+            };
+            if !canonical {
+                echo(c);
+                buf[len] = c;
+                return len + 1;
+            }
+            match c {
+                BS | DEL => {
+                    if len > 0 {
+                        len -= 1;
+                        echo(BS);
+                        echo(b' ');
+                        echo(BS);
+                    }
+                }
+                b'\r' | b'\n' => {
+                    echo(b'\n');
+                    buf[len] = b'\n';
+                    return len + 1;
+                }
+                _ => {
+                    echo(c);
+                    buf[len] = c;
+                    len += 1;
+                }
+            }
+        }
+        len
+    }
+}
+
+/// 文件系统的容量统计，`statfs` 的输出。
+///
+/// 块数只计数据区，不含超级块、位图和索引节点区。
+#[derive(Clone, Copy, Default, Debug)]
+#[repr(C)]
+pub struct Statfs {
+    /// 块的字节数。
+    pub block_size: u64,
+    /// 数据块总数。
+    pub total_blocks: u64,
+    /// 空闲的数据块数。
+    pub free_blocks: u64,
+    /// 索引节点总数。
+    pub total_inodes: u64,
+    /// 空闲的索引节点数。
+    pub free_inodes: u64,
+}
+
+/// `epoll_wait` 报告的一个事件，也是 `epoll_ctl` 登记的兴趣。
+///
+/// riscv 上的 Linux 不压缩这个结构体，`data` 按 8 字节对齐。
+#[derive(Clone, Copy, Default, Debug)]
+#[repr(C)]
+pub struct EpollEvent {
+    /// [`EPOLLIN`]、[`EPOLLOUT`] 等事件位的组合。
+    pub events: u32,
+    /// 登记时由用户指定，内核原样带回。
+    pub data: u64,
+}
+
+/// 可读。
+pub const EPOLLIN: u32 = 0x001;
+/// 可写。
+pub const EPOLLOUT: u32 = 0x004;
+/// 出错，总会报告，不需要登记。
+pub const EPOLLERR: u32 = 0x008;
+/// 挂断，总会报告，不需要登记。
+pub const EPOLLHUP: u32 = 0x010;
+
+/// `epoll_create1` 的标志位：返回的描述符带 close-on-exec。
+pub const EPOLL_CLOEXEC: usize = 0o2000000;
+
+/// 登记一个文件描述符。
+pub const EPOLL_CTL_ADD: usize = 1;
+/// 注销一个文件描述符。
+pub const EPOLL_CTL_DEL: usize = 2;
+/// 修改已登记的兴趣。
+pub const EPOLL_CTL_MOD: usize = 3;
@@ -1,12 +1,19 @@
 #![allow(unused_variables)]
 
-use crate::{ClockId, SyscallId};
+#[cfg(feature = "strict")]
+mod strict;
+
+#[cfg(feature = "strict")]
+pub use strict::{MAX_LEN, USER_END};
+
+use crate::{ClockId, SyscallId, CSIGNAL};
 use spin::Once;
 
 /// 系统调用的发起者信息。
 ///
 /// 没有办法（也没有必要？）调整发起者的描述，只好先用两个 `usize` 了。
 /// 至少在一个类 Linux 的宏内核系统这是够用的。
+#[derive(Clone, Copy)]
 pub struct Caller {
     /// 发起者拥有的资源集的标记，相当于进程号。
     pub entity: usize,
@@ -21,15 +28,79 @@ pub trait Process: Sync {
     fn fork(&self, caller: Caller) -> isize {
         unimplemented!()
     }
+    /// 默认只支持不共享资源、不指定栈的 `clone`，也就是 `fork`。
+    fn clone(&self, caller: Caller, flags: usize, stack: usize) -> isize {
+        if flags & !CSIGNAL == 0 && stack == 0 {
+            self.fork(caller)
+        } else {
+            -1
+        }
+    }
     fn exec(&self, caller: Caller, path: usize, count: usize) -> isize {
         unimplemented!()
     }
+    fn spawn(&self, caller: Caller, path: usize, count: usize) -> isize {
+        unimplemented!()
+    }
+    /// 默认不支持参数，也就是 `exec`。
+    fn execv(&self, caller: Caller, path: usize, count: usize, argv: usize) -> isize {
+        if argv == 0 {
+            self.exec(caller, path, count)
+        } else {
+            -1
+        }
+    }
     fn wait(&self, caller: Caller, pid: isize, exit_code_ptr: usize) -> isize {
         unimplemented!()
     }
+    /// 默认不支持选项和资源统计，也就是 `wait`。
+    fn wait4(
+        &self,
+        caller: Caller,
+        pid: isize,
+        exit_code_ptr: usize,
+        options: usize,
+        rusage: usize,
+    ) -> isize {
+        if options == 0 && rusage == 0 {
+            self.wait(caller, pid, exit_code_ptr)
+        } else {
+            -1
+        }
+    }
     fn getpid(&self, caller: Caller) -> isize {
         unimplemented!()
     }
+    fn getppid(&self, caller: Caller) -> isize {
+        unimplemented!()
+    }
+    fn getuid(&self, caller: Caller) -> isize {
+        unimplemented!()
+    }
+    fn geteuid(&self, caller: Caller) -> isize {
+        unimplemented!()
+    }
+    fn getgid(&self, caller: Caller) -> isize {
+        unimplemented!()
+    }
+    fn getegid(&self, caller: Caller) -> isize {
+        unimplemented!()
+    }
+    fn prctl(&self, caller: Caller, option: usize, arg2: usize) -> isize {
+        unimplemented!()
+    }
+    fn ps(&self, caller: Caller, buf: usize, len: usize) -> isize {
+        unimplemented!()
+    }
+    fn trapstats(&self, caller: Caller, buf: usize, len: usize) -> isize {
+        unimplemented!()
+    }
+    fn sysinfo(&self, caller: Caller, info: usize) -> isize {
+        unimplemented!()
+    }
+    fn reboot(&self, caller: Caller, magic1: usize, magic2: usize, cmd: usize) -> isize {
+        unimplemented!()
+    }
 }
 
 pub trait IO: Sync {
@@ -39,12 +110,77 @@ pub trait IO: Sync {
     fn write(&self, caller: Caller, fd: usize, buf: usize, count: usize) -> isize {
         unimplemented!()
     }
+    fn readv(&self, caller: Caller, fd: usize, iov: usize, iovcnt: usize) -> isize {
+        unimplemented!()
+    }
+    fn writev(&self, caller: Caller, fd: usize, iov: usize, iovcnt: usize) -> isize {
+        unimplemented!()
+    }
     fn open(&self, caller: Caller, path: usize, flags: usize) -> isize {
         unimplemented!()
     }
     fn close(&self, caller: Caller, fd: usize) -> isize {
         unimplemented!()
     }
+    fn rename(&self, caller: Caller, old_path: usize, new_path: usize) -> isize {
+        unimplemented!()
+    }
+    fn mkdir(&self, caller: Caller, path: usize) -> isize {
+        unimplemented!()
+    }
+    fn chdir(&self, caller: Caller, path: usize) -> isize {
+        unimplemented!()
+    }
+    fn getcwd(&self, caller: Caller, buf: usize, len: usize) -> isize {
+        unimplemented!()
+    }
+    fn access(&self, caller: Caller, path: usize, mode: usize) -> isize {
+        unimplemented!()
+    }
+    fn fsync(&self, caller: Caller, fd: usize) -> isize {
+        unimplemented!()
+    }
+    fn ioctl(&self, caller: Caller, fd: usize, request: usize, arg: usize) -> isize {
+        unimplemented!()
+    }
+    fn fcntl(&self, caller: Caller, fd: usize, cmd: usize, arg: usize) -> isize {
+        unimplemented!()
+    }
+    fn pread(&self, caller: Caller, fd: usize, buf: usize, count: usize, offset: usize) -> isize {
+        unimplemented!()
+    }
+    fn pwrite(&self, caller: Caller, fd: usize, buf: usize, count: usize, offset: usize) -> isize {
+        unimplemented!()
+    }
+    fn statfs(&self, caller: Caller, path: usize, buf: usize) -> isize {
+        unimplemented!()
+    }
+    fn sendfile(
+        &self,
+        caller: Caller,
+        out_fd: usize,
+        in_fd: usize,
+        offset: usize,
+        count: usize,
+    ) -> isize {
+        unimplemented!()
+    }
+    fn epoll_create(&self, caller: Caller, flags: usize) -> isize {
+        unimplemented!()
+    }
+    fn epoll_ctl(&self, caller: Caller, epfd: usize, op: usize, fd: usize, event: usize) -> isize {
+        unimplemented!()
+    }
+    fn epoll_wait(
+        &self,
+        caller: Caller,
+        epfd: usize,
+        events: usize,
+        maxevents: usize,
+        timeout: isize,
+    ) -> isize {
+        unimplemented!()
+    }
 }
 
 pub trait Memory: Sync {
@@ -64,18 +200,70 @@ pub trait Memory: Sync {
     fn munmap(&self, caller: Caller, addr: usize, length: usize) -> isize {
         unimplemented!()
     }
+
+    fn brk(&self, caller: Caller, addr: usize) -> isize {
+        unimplemented!()
+    }
+
+    fn madvise(&self, caller: Caller, addr: usize, length: usize, advice: usize) -> isize {
+        unimplemented!()
+    }
+
+    fn mprotect(&self, caller: Caller, addr: usize, length: usize, prot: i32) -> isize {
+        unimplemented!()
+    }
+
+    fn shmget(&self, caller: Caller, key: usize, size: usize, flags: usize) -> isize {
+        unimplemented!()
+    }
+
+    fn shmat(&self, caller: Caller, shmid: usize, addr: usize, flags: usize) -> isize {
+        unimplemented!()
+    }
+
+    fn shmdt(&self, caller: Caller, addr: usize) -> isize {
+        unimplemented!()
+    }
+
+    fn shmctl(&self, caller: Caller, shmid: usize, cmd: usize, buf: usize) -> isize {
+        unimplemented!()
+    }
 }
 
 pub trait Scheduling: Sync {
     fn sched_yield(&self, caller: Caller) -> isize {
         unimplemented!()
     }
+
+    fn yield_to(&self, caller: Caller, pid: usize) -> isize {
+        unimplemented!()
+    }
+
+    fn sched_getquantum(&self, caller: Caller) -> isize {
+        unimplemented!()
+    }
 }
 
 pub trait Clock: Sync {
     fn clock_gettime(&self, caller: Caller, clock_id: ClockId, tp: usize) -> isize {
         unimplemented!()
     }
+    fn gettimeofday(&self, caller: Caller, tv: usize, tz: usize) -> isize {
+        unimplemented!()
+    }
+    fn nanosleep(&self, caller: Caller, req: usize, rem: usize) -> isize {
+        unimplemented!()
+    }
+    fn clock_nanosleep(
+        &self,
+        caller: Caller,
+        clock_id: ClockId,
+        flags: usize,
+        req: usize,
+        rem: usize,
+    ) -> isize {
+        unimplemented!()
+    }
 }
 
 pub trait Signal: Sync {
@@ -106,6 +294,12 @@ pub trait Thread: Sync {
     fn gettid(&self, caller: Caller) -> isize {
         unimplemented!()
     }
+    fn set_tid_address(&self, caller: Caller, tidptr: usize) -> isize {
+        unimplemented!()
+    }
+    fn set_robust_list(&self, caller: Caller, head: usize, len: usize) -> isize {
+        unimplemented!()
+    }
 }
 
 pub trait SyncMutex: Sync {
@@ -194,25 +388,91 @@ pub enum SyscallResult {
 
 pub fn handle(caller: Caller, id: SyscallId, args: [usize; 6]) -> SyscallResult {
     use SyscallId as Id;
+    #[cfg(feature = "strict")]
+    if !strict::validate(id, &args) {
+        return SyscallResult::Done(-1);
+    }
     match id {
         Id::WRITE => IO.call(id, |io| io.write(caller, args[0], args[1], args[2])),
         Id::READ => IO.call(id, |io| io.read(caller, args[0], args[1], args[2])),
+        Id::WRITEV => IO.call(id, |io| io.writev(caller, args[0], args[1], args[2])),
+        Id::READV => IO.call(id, |io| io.readv(caller, args[0], args[1], args[2])),
         Id::OPENAT => IO.call(id, |io| io.open(caller, args[0], args[1])),
         Id::CLOSE => IO.call(id, |io| io.close(caller, args[0])),
+        Id::RENAMEAT2 => IO.call(id, |io| io.rename(caller, args[0], args[1])),
+        Id::MKDIRAT => IO.call(id, |io| io.mkdir(caller, args[0])),
+        Id::CHDIR => IO.call(id, |io| io.chdir(caller, args[0])),
+        Id::GETCWD => IO.call(id, |io| io.getcwd(caller, args[0], args[1])),
+        Id::FACCESSAT => IO.call(id, |io| io.access(caller, args[0], args[1])),
+        Id::FSYNC => IO.call(id, |io| io.fsync(caller, args[0])),
+        Id::IOCTL => IO.call(id, |io| io.ioctl(caller, args[0], args[1], args[2])),
+        Id::FCNTL => IO.call(id, |io| io.fcntl(caller, args[0], args[1], args[2])),
+        Id::PREAD64 => IO.call(id, |io| {
+            io.pread(caller, args[0], args[1], args[2], args[3])
+        }),
+        Id::PWRITE64 => IO.call(id, |io| {
+            io.pwrite(caller, args[0], args[1], args[2], args[3])
+        }),
+        Id::STATFS => IO.call(id, |io| io.statfs(caller, args[0], args[1])),
+        Id::SENDFILE => IO.call(id, |io| {
+            io.sendfile(caller, args[0], args[1], args[2], args[3])
+        }),
+        Id::EPOLL_CREATE1 => IO.call(id, |io| io.epoll_create(caller, args[0])),
+        Id::EPOLL_CTL => IO.call(id, |io| {
+            io.epoll_ctl(caller, args[0], args[1], args[2], args[3])
+        }),
+        Id::EPOLL_PWAIT => IO.call(id, |io| {
+            io.epoll_wait(caller, args[0], args[1], args[2], args[3] as _)
+        }),
         Id::EXIT => PROCESS.call(id, |proc| proc.exit(caller, args[0])),
-        Id::CLONE => PROCESS.call(id, |proc| proc.fork(caller)),
-        Id::EXECVE => PROCESS.call(id, |proc| proc.exec(caller, args[0], args[1])),
-        Id::WAIT4 => PROCESS.call(id, |proc| proc.wait(caller, args[0] as _, args[1])),
+        Id::CLONE => PROCESS.call(id, |proc| proc.clone(caller, args[0], args[1])),
+        Id::EXECVE => PROCESS.call(id, |proc| proc.execv(caller, args[0], args[1], args[2])),
+        Id::SPAWN => PROCESS.call(id, |proc| proc.spawn(caller, args[0], args[1])),
+        Id::WAIT4 => PROCESS.call(id, |proc| {
+            proc.wait4(caller, args[0] as _, args[1], args[2], args[3])
+        }),
         Id::GETPID => PROCESS.call(id, |proc| proc.getpid(caller)),
+        Id::GETPPID => PROCESS.call(id, |proc| proc.getppid(caller)),
+        Id::GETUID => PROCESS.call(id, |proc| proc.getuid(caller)),
+        Id::GETEUID => PROCESS.call(id, |proc| proc.geteuid(caller)),
+        Id::GETGID => PROCESS.call(id, |proc| proc.getgid(caller)),
+        Id::GETEGID => PROCESS.call(id, |proc| proc.getegid(caller)),
+        Id::PRCTL => PROCESS.call(id, |proc| proc.prctl(caller, args[0], args[1])),
+        Id::PS => PROCESS.call(id, |proc| proc.ps(caller, args[0], args[1])),
+        Id::TRAPSTATS => PROCESS.call(id, |proc| proc.trapstats(caller, args[0], args[1])),
+        Id::SYSINFO => PROCESS.call(id, |proc| proc.sysinfo(caller, args[0])),
+        Id::REBOOT => PROCESS.call(id, |proc| proc.reboot(caller, args[0], args[1], args[2])),
         Id::CLOCK_GETTIME => CLOCK.call(id, |clock| {
             clock.clock_gettime(caller, ClockId(args[0]), args[1])
         }),
+        Id::GETTIMEOFDAY => CLOCK.call(id, |clock| clock.gettimeofday(caller, args[0], args[1])),
+        Id::NANOSLEEP => CLOCK.call(id, |clock| clock.nanosleep(caller, args[0], args[1])),
+        Id::CLOCK_NANOSLEEP => CLOCK.call(id, |clock| {
+            clock.clock_nanosleep(caller, ClockId(args[0]), args[1], args[2], args[3])
+        }),
         Id::SCHED_YIELD => SCHEDULING.call(id, |sched| sched.sched_yield(caller)),
+        Id::YIELD_TO => SCHEDULING.call(id, |sched| sched.yield_to(caller, args[0])),
+        Id::SCHED_GETQUANTUM => SCHEDULING.call(id, |sched| sched.sched_getquantum(caller)),
         Id::MUNMAP => MEMORY.call(id, |memory| memory.munmap(caller, args[0], args[1])),
+        Id::BRK => MEMORY.call(id, |memory| memory.brk(caller, args[0])),
+        Id::MADVISE => MEMORY.call(id, |memory| {
+            memory.madvise(caller, args[0], args[1], args[2])
+        }),
+        Id::MPROTECT => MEMORY.call(id, |memory| {
+            memory.mprotect(caller, args[0], args[1], args[2] as _)
+        }),
         Id::MMAP => MEMORY.call(id, |memory| {
             let [addr, length, prot, flags, fd, offset] = args;
             memory.mmap(caller, addr, length, prot as _, flags as _, fd as _, offset)
         }),
+        Id::SHMGET => MEMORY.call(id, |memory| {
+            memory.shmget(caller, args[0], args[1], args[2])
+        }),
+        Id::SHMAT => MEMORY.call(id, |memory| memory.shmat(caller, args[0], args[1], args[2])),
+        Id::SHMDT => MEMORY.call(id, |memory| memory.shmdt(caller, args[0])),
+        Id::SHMCTL => MEMORY.call(id, |memory| {
+            memory.shmctl(caller, args[0], args[1], args[2])
+        }),
         Id::KILL => SIGNAL.call(id, |signal| signal.kill(caller, args[0] as _, args[1] as _)),
         Id::RT_SIGACTION => SIGNAL.call(id, |signal| {
             signal.sigaction(caller, args[0] as _, args[1], args[2])
@@ -221,6 +481,10 @@ pub fn handle(caller: Caller, id: SyscallId, args: [usize; 6]) -> SyscallResult
         Id::RT_SIGRETURN => SIGNAL.call(id, |signal| signal.sigreturn(caller)),
         Id::WAITID => THREAD.call(id, |thread| thread.waittid(caller, args[0])),
         Id::GETTID => THREAD.call(id, |thread| thread.gettid(caller)),
+        Id::SET_TID_ADDRESS => THREAD.call(id, |thread| thread.set_tid_address(caller, args[0])),
+        Id::SET_ROBUST_LIST => THREAD.call(id, |thread| {
+            thread.set_robust_list(caller, args[0], args[1])
+        }),
         Id::THREAD_CREATE => {
             THREAD.call(id, |thread| thread.thread_create(caller, args[0], args[1]))
         }
@@ -0,0 +1,96 @@
+//! 严格模式：分发之前检查系统调用参数。
+//!
+//! 这里只检查参数的形式（指针是否落在用户地址范围内、长度和文件描述符是否合理），
+//! 不检查指针是否真的映射了，那需要内核查询地址空间。
+
+use crate::{
+    EpollEvent, IoVec, SyscallId, EPOLL_CTL_DEL, F_DUPFD, MAX_FD, PR_GET_NAME, PR_SET_NAME, TCGETS,
+    TCSETS,
+};
+
+/// 用户地址空间的上界（不含）。
+///
+/// Sv39 的用户空间是低半部分。
+#[cfg(target_pointer_width = "64")]
+pub const USER_END: usize = 1 << 38;
+
+/// 用户地址空间的上界（不含）。
+///
+/// Sv32 没有划分用户空间，只检查地址不回绕。
+#[cfg(target_pointer_width = "32")]
+pub const USER_END: usize = usize::MAX;
+
+/// 单次传输的最大长度。
+pub const MAX_LEN: usize = 16 << 20;
+
+/// 检查 `args` 是否满足 `id` 的参数约定。
+pub(super) fn validate(id: SyscallId, args: &[usize; 6]) -> bool {
+    use SyscallId as Id;
+    match id {
+        Id::WRITE | Id::READ | Id::PREAD64 | Id::PWRITE64 => fd(args[0]) && buf(args[1], args[2]),
+        Id::WRITEV | Id::READV => {
+            fd(args[0])
+                && args[2]
+                    .checked_mul(core::mem::size_of::<IoVec>())
+                    .is_some_and(|len| buf(args[1], len))
+        }
+        Id::CLOSE | Id::FSYNC => fd(args[0]),
+        Id::SENDFILE => fd(args[0]) && fd(args[1]) && nullable(args[2]),
+        Id::FCNTL => fd(args[0]) && (args[1] != F_DUPFD || fd(args[2])),
+        Id::IOCTL => fd(args[0]) && (!matches!(args[1], TCGETS | TCSETS) || ptr(args[2])),
+        Id::EPOLL_CTL => fd(args[0]) && fd(args[2]) && (args[1] == EPOLL_CTL_DEL || ptr(args[3])),
+        Id::EPOLL_PWAIT => {
+            fd(args[0])
+                && args[2]
+                    .checked_mul(core::mem::size_of::<EpollEvent>())
+                    .is_some_and(|len| buf(args[1], len))
+        }
+        Id::STATFS => ptr(args[0]) && ptr(args[1]),
+        Id::OPENAT | Id::MKDIRAT | Id::CHDIR | Id::FACCESSAT => ptr(args[0]),
+        Id::RENAMEAT2 => ptr(args[0]) && ptr(args[1]),
+        Id::GETCWD | Id::PS | Id::TRAPSTATS | Id::EXECVE | Id::SPAWN => buf(args[0], args[1]),
+        Id::WAIT4 => nullable(args[1]) && nullable(args[3]),
+        Id::PRCTL => !matches!(args[0], PR_SET_NAME | PR_GET_NAME) || ptr(args[1]),
+        Id::CLOCK_GETTIME => ptr(args[1]),
+        Id::GETTIMEOFDAY => nullable(args[0]) && nullable(args[1]),
+        Id::NANOSLEEP => ptr(args[0]) && nullable(args[1]),
+        Id::CLOCK_NANOSLEEP => ptr(args[2]) && nullable(args[3]),
+        Id::MMAP | Id::MUNMAP | Id::MADVISE | Id::MPROTECT => range(args[0], args[1]),
+        Id::SHMAT => nullable(args[1]),
+        Id::SHMDT => ptr(args[0]),
+        Id::BRK => nullable(args[0]),
+        Id::RT_SIGACTION => nullable(args[1]) && nullable(args[2]),
+        Id::SET_TID_ADDRESS | Id::SET_ROBUST_LIST => nullable(args[0]),
+        _ => true,
+    }
+}
+
+/// 文件描述符在范围内。
+#[inline]
+fn fd(fd: usize) -> bool {
+    fd < MAX_FD
+}
+
+/// 非空指针，且指向用户空间。
+#[inline]
+fn ptr(addr: usize) -> bool {
+    addr != 0 && addr < USER_END
+}
+
+/// 空指针，或者指向用户空间。
+#[inline]
+fn nullable(addr: usize) -> bool {
+    addr == 0 || ptr(addr)
+}
+
+/// `addr..addr + len` 整个位于用户空间。
+#[inline]
+fn range(addr: usize, len: usize) -> bool {
+    addr.checked_add(len).is_some_and(|end| end <= USER_END)
+}
+
+/// 用于传输的缓冲区：长度不超过 [`MAX_LEN`]，非空时位于用户空间。
+#[inline]
+fn buf(addr: usize, len: usize) -> bool {
+    len <= MAX_LEN && (len == 0 || (ptr(addr) && range(addr, len)))
+}
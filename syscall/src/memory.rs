@@ -0,0 +1,53 @@
+//! see <https://github.com/torvalds/linux/blob/master/include/uapi/asm-generic/mman-common.h>.
+
+/// 页不可访问。
+pub const PROT_NONE: i32 = 0;
+/// 页可读。
+pub const PROT_READ: i32 = 1;
+/// 页可写。
+pub const PROT_WRITE: i32 = 2;
+/// 页可执行。
+pub const PROT_EXEC: i32 = 4;
+
+/// 共享映射，修改对映射同一对象的其他进程可见。
+pub const MAP_SHARED: i32 = 0x01;
+/// 私有映射，修改只对本进程可见。
+pub const MAP_PRIVATE: i32 = 0x02;
+/// 必须映射到给定的地址。
+pub const MAP_FIXED: i32 = 0x10;
+/// 匿名映射，不对应任何文件，内容清零。
+pub const MAP_ANONYMOUS: i32 = 0x20;
+
+/// `madvise` 建议：没有特殊建议。
+pub const MADV_NORMAL: usize = 0;
+/// `madvise` 建议：不再需要这些页，释放它们占用的物理页。
+pub const MADV_DONTNEED: usize = 4;
+
+/// 系统信息。
+///
+/// 只保留内核能提供的字段，与 Linux 的 `struct sysinfo` 布局不同。
+///
+/// see <https://man7.org/linux/man-pages/man2/sysinfo.2.html>.
+#[derive(Clone, Copy, Default, Debug)]
+#[repr(C)]
+pub struct SysInfo {
+    /// 启动以来的秒数。
+    pub uptime: usize,
+    /// 进程数。
+    pub procs: usize,
+    /// 用作页表的物理页数。
+    pub table_frames: usize,
+    /// 用作数据的物理页数。
+    pub data_frames: usize,
+}
+
+/// `shmget` 的键：总是新建一个段，其他进程不能按键找到它。
+pub const IPC_PRIVATE: usize = 0;
+/// `shmget` 标志位：键不存在时新建段。
+pub const IPC_CREAT: usize = 0o1000;
+/// `shmget` 标志位：与 [`IPC_CREAT`] 一起使用，键已存在时失败。
+pub const IPC_EXCL: usize = 0o2000;
+/// `shmctl` 命令：标记删除，最后一个进程分离后释放。
+pub const IPC_RMID: usize = 0;
+/// `shmat` 标志位：只读映射。
+pub const SHM_RDONLY: usize = 0o10000;
@@ -0,0 +1,108 @@
+//! see <https://github.com/torvalds/linux/blob/master/include/uapi/linux/prctl.h>
+//! <https://github.com/torvalds/linux/blob/master/include/uapi/linux/reboot.h>
+//! <https://github.com/torvalds/linux/blob/master/include/uapi/linux/sched.h>
+//! and <https://github.com/torvalds/linux/blob/master/include/uapi/linux/resource.h>.
+
+use crate::TimeVal;
+
+/// `prctl` 选项：设置调用者的进程名。
+pub const PR_SET_NAME: usize = 15;
+/// `prctl` 选项：读取调用者的进程名。
+pub const PR_GET_NAME: usize = 16;
+
+/// `prctl` 选项：`arg2` 为 0 时禁止调用者再创建子进程。本内核的扩展。
+///
+/// 这个能力由子进程继承，一旦清除就不能重新设置，所以被限制的进程无法自行解除限制。
+pub const PR_SET_SPAWN: usize = 0x5350_0000;
+/// `prctl` 选项：读取调用者能否创建子进程，能则返回 1。本内核的扩展。
+pub const PR_GET_SPAWN: usize = 0x5350_0001;
+
+/// 进程名缓冲区的长度，包括结尾的 `\0`。
+pub const TASK_COMM_LEN: usize = 16;
+
+/// `reboot` 的第一个魔数。
+pub const REBOOT_MAGIC1: usize = 0xfee1_dead;
+/// `reboot` 的第二个魔数。
+pub const REBOOT_MAGIC2: usize = 0x2812_1969;
+
+/// `reboot` 命令：冷重启。
+pub const REBOOT_CMD_RESTART: usize = 0x0123_4567;
+/// `reboot` 命令：停机，和关机一样处理。
+pub const REBOOT_CMD_HALT: usize = 0xcdef_0123;
+/// `reboot` 命令：关机。
+pub const REBOOT_CMD_POWER_OFF: usize = 0x4321_fedc;
+/// `reboot` 命令：热重启。Linux 用它带一个命令字符串，这里不带参数。
+pub const REBOOT_CMD_RESTART2: usize = 0xa1b2_c3d4;
+
+/// `clone` 标志：低 8 位是子任务退出时发给父进程的信号，这里忽略。
+pub const CSIGNAL: usize = 0xff;
+/// `clone` 标志：共享地址空间。
+pub const CLONE_VM: usize = 0x100;
+/// `clone` 标志：共享文件描述符表。
+pub const CLONE_FILES: usize = 0x400;
+
+/// `wait4` 选项：没有已退出的子进程时立即返回 0。
+pub const WNOHANG: usize = 1;
+
+/// 资源使用统计。
+///
+/// 与 Linux 的 `struct rusage` 布局相同，内核只填写用户态时间和内存占用，其他字段为 0。
+///
+/// see <https://man7.org/linux/man-pages/man2/getrusage.2.html>.
+#[derive(Clone, Copy, Default, Debug)]
+#[repr(C)]
+pub struct RUsage {
+    /// 用户态运行时间。
+    pub ru_utime: TimeVal,
+    /// 内核态运行时间。
+    pub ru_stime: TimeVal,
+    /// 最大常驻内存（KiB）。
+    pub ru_maxrss: usize,
+    pub ru_ixrss: usize,
+    pub ru_idrss: usize,
+    pub ru_isrss: usize,
+    pub ru_minflt: usize,
+    pub ru_majflt: usize,
+    pub ru_nswap: usize,
+    pub ru_inblock: usize,
+    pub ru_oublock: usize,
+    pub ru_msgsnd: usize,
+    pub ru_msgrcv: usize,
+    pub ru_nsignals: usize,
+    pub ru_nvcsw: usize,
+    pub ru_nivcsw: usize,
+}
+
+/// 健壮互斥锁链表的表头，由用户态维护，用 `set_robust_list` 登记给内核。
+///
+/// 链表的每一项是一个 [`RobustList`]，锁字在节点地址加上 `futex_offset` 处，
+/// 低 30 位是持有者的 tid。链表是环形的，最后一项指回表头。
+///
+/// see <https://man7.org/linux/man-pages/man2/set_robust_list.2.html>.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct RobustListHead {
+    /// 链表的第一项，空链表指向表头自己。
+    pub list: RobustList,
+    /// 锁字相对节点的偏移。
+    pub futex_offset: isize,
+    /// 正在加锁或解锁、还没有挂到链表上的节点，0 表示没有。
+    pub list_op_pending: usize,
+}
+
+/// 健壮互斥锁链表的节点，嵌在用户态的锁对象里。
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct RobustList {
+    /// 下一项的地址。
+    pub next: usize,
+}
+
+/// 锁字中持有者 tid 的部分。
+pub const FUTEX_TID_MASK: u32 = 0x3fff_ffff;
+/// 锁字标志：持有者没有解锁就退出了。
+pub const FUTEX_OWNER_DIED: u32 = 0x4000_0000;
+/// 锁字标志：有线程在等待这个锁。
+pub const FUTEX_WAITERS: u32 = 0x8000_0000;
+/// 内核最多遍历的健壮锁链表项数，防止用户态把链表连成不回到表头的环。
+pub const ROBUST_LIST_LIMIT: usize = 2048;
@@ -19,6 +19,9 @@ impl ClockId {
     pub const CLOCK_TAI: Self = Self(11);
 }
 
+/// `clock_nanosleep` 的标志位：`request` 是绝对时间而非相对时长。
+pub const TIMER_ABSTIME: usize = 1;
+
 #[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Debug)]
 #[repr(C)]
 pub struct TimeSpec {
@@ -55,6 +58,21 @@ impl TimeSpec {
             tv_nsec: millsecond % 1_000 * 1_000_000,
         }
     }
+    pub fn from_nanosecond(nanosecond: usize) -> Self {
+        Self {
+            tv_sec: nanosecond / 1_000_000_000,
+            tv_nsec: nanosecond % 1_000_000_000,
+        }
+    }
+    /// 换算成纳秒，按 `u64` 计算，溢出或 `tv_nsec` 不小于一秒时返回 `None`。
+    pub fn to_nanosecond(self) -> Option<u64> {
+        if self.tv_nsec >= 1_000_000_000 {
+            return None;
+        }
+        (self.tv_sec as u64)
+            .checked_mul(1_000_000_000)?
+            .checked_add(self.tv_nsec as u64)
+    }
 }
 
 impl core::ops::Add<TimeSpec> for TimeSpec {
@@ -72,6 +90,35 @@ impl core::ops::Add<TimeSpec> for TimeSpec {
     }
 }
 
+/// see <https://man7.org/linux/man-pages/man2/gettimeofday.2.html>.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[repr(C)]
+pub struct TimeVal {
+    // seconds
+    pub tv_sec: usize,
+    // microseconds
+    pub tv_usec: usize,
+}
+
+/// 时区。内核没有时区，总是报告 UTC。
+///
+/// see <https://man7.org/linux/man-pages/man2/gettimeofday.2.html>.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct TimeZone {
+    // minutes west of Greenwich
+    pub tz_minuteswest: i32,
+    // type of DST correction
+    pub tz_dsttime: i32,
+}
+
+impl TimeZone {
+    pub const UTC: Self = Self {
+        tz_minuteswest: 0,
+        tz_dsttime: 0,
+    };
+}
+
 impl core::fmt::Display for TimeSpec {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(f, "TimeSpec({}.{:09})", self.tv_sec, self.tv_nsec)
@@ -1,4 +1,7 @@
-use crate::{ClockId, SignalAction, SignalNo, SyscallId, TimeSpec};
+use crate::{
+    ClockId, EpollEvent, IoVec, RUsage, RobustListHead, SignalAction, SignalNo, Statfs, SysInfo,
+    SyscallId, Termios, TimeSpec, TimeVal, TimeZone, REBOOT_MAGIC1, REBOOT_MAGIC2, TCGETS, TCSETS,
+};
 use bitflags::*;
 use native::*;
 
@@ -13,6 +16,18 @@ pub fn read(fd: usize, buffer: &[u8]) -> isize {
     unsafe { syscall3(SyscallId::READ, fd, buffer.as_ptr() as _, buffer.len()) }
 }
 
+/// see <https://man7.org/linux/man-pages/man2/writev.2.html>.
+#[inline]
+pub fn writev(fd: usize, iov: &[IoVec]) -> isize {
+    unsafe { syscall3(SyscallId::WRITEV, fd, iov.as_ptr() as _, iov.len()) }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/readv.2.html>.
+#[inline]
+pub fn readv(fd: usize, iov: &[IoVec]) -> isize {
+    unsafe { syscall3(SyscallId::READV, fd, iov.as_ptr() as _, iov.len()) }
+}
+
 bitflags! {
     pub struct OpenFlags: u32 {
         const RDONLY = 0;
@@ -39,35 +54,405 @@ pub fn close(fd: usize) -> isize {
     unsafe { syscall1(SyscallId::CLOSE, fd) }
 }
 
+/// see <https://man7.org/linux/man-pages/man2/fsync.2.html>.
+#[inline]
+pub fn fsync(fd: usize) -> isize {
+    unsafe { syscall1(SyscallId::FSYNC, fd) }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/fcntl.2.html>.
+#[inline]
+pub fn fcntl(fd: usize, cmd: usize, arg: usize) -> isize {
+    unsafe { syscall3(SyscallId::FCNTL, fd, cmd, arg) }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/pread.2.html>.
+#[inline]
+pub fn pread(fd: usize, buffer: &mut [u8], offset: isize) -> isize {
+    unsafe {
+        syscall4(
+            SyscallId::PREAD64,
+            fd,
+            buffer.as_mut_ptr() as _,
+            buffer.len(),
+            offset as _,
+        )
+    }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/pwrite.2.html>.
+#[inline]
+pub fn pwrite(fd: usize, buffer: &[u8], offset: isize) -> isize {
+    unsafe {
+        syscall4(
+            SyscallId::PWRITE64,
+            fd,
+            buffer.as_ptr() as _,
+            buffer.len(),
+            offset as _,
+        )
+    }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/sendfile.2.html>.
+#[inline]
+pub fn sendfile(out_fd: usize, in_fd: usize, offset: Option<&mut isize>, count: usize) -> isize {
+    let offset = offset.map_or(core::ptr::null_mut(), |offset| offset as *mut isize);
+    unsafe { syscall4(SyscallId::SENDFILE, out_fd, in_fd, offset as _, count) }
+}
+
+/// 创建一个 epoll 实例，返回它的文件描述符。
+///
+/// see <https://man7.org/linux/man-pages/man2/epoll_create.2.html>.
+#[inline]
+pub fn epoll_create() -> isize {
+    unsafe { syscall1(SyscallId::EPOLL_CREATE1, 0) }
+}
+
+/// 在 `epfd` 上登记、修改或注销对 `fd` 的兴趣。
+///
+/// see <https://man7.org/linux/man-pages/man2/epoll_ctl.2.html>.
+#[inline]
+pub fn epoll_ctl(epfd: usize, op: usize, fd: usize, event: &EpollEvent) -> isize {
+    unsafe { syscall4(SyscallId::EPOLL_CTL, epfd, op, fd, event as *const _ as _) }
+}
+
+/// 等待 `epfd` 上登记的文件描述符就绪，返回写入 `events` 的事件数。
+///
+/// `timeout` 以毫秒计，0 表示立即返回，负数表示一直等待。
+/// 内核没有就绪事件且不该立即返回时返回 -2，由这里让出处理器后重试，直到超时。
+///
+/// see <https://man7.org/linux/man-pages/man2/epoll_wait.2.html>.
+#[inline]
+pub fn epoll_wait(epfd: usize, events: &mut [EpollEvent], timeout: isize) -> isize {
+    let deadline = (timeout > 0).then(|| {
+        let mut now = TimeSpec::ZERO;
+        clock_gettime(ClockId::CLOCK_MONOTONIC, &mut now);
+        now + TimeSpec::from_millsecond(timeout as _)
+    });
+    loop {
+        let ret = unsafe {
+            syscall4(
+                SyscallId::EPOLL_PWAIT,
+                epfd,
+                events.as_mut_ptr() as _,
+                events.len(),
+                timeout as _,
+            )
+        };
+        if ret != -2 {
+            return ret;
+        }
+        if let Some(deadline) = deadline {
+            let mut now = TimeSpec::ZERO;
+            clock_gettime(ClockId::CLOCK_MONOTONIC, &mut now);
+            if now >= deadline {
+                return 0;
+            }
+        }
+        sched_yield();
+    }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/ioctl.2.html>.
+#[inline]
+pub fn ioctl(fd: usize, request: usize, arg: usize) -> isize {
+    unsafe { syscall3(SyscallId::IOCTL, fd, request, arg) }
+}
+
+/// 读取 `fd` 所在终端的属性。
+#[inline]
+pub fn tcgetattr(fd: usize, termios: &mut Termios) -> isize {
+    ioctl(fd, TCGETS, termios as *mut _ as _)
+}
+
+/// 设置 `fd` 所在终端的属性。
+#[inline]
+pub fn tcsetattr(fd: usize, termios: &Termios) -> isize {
+    ioctl(fd, TCSETS, termios as *const _ as _)
+}
+
+/// 将 `old_path` 重命名为 `new_path`，`new_path` 已存在时被替换。
+/// 目录不能移到它自己或它的子目录下。
+///
+/// 与 [`open`] 相同，路径需要以 `\0` 结尾。
+#[inline]
+pub fn rename(old_path: &str, new_path: &str) -> isize {
+    unsafe {
+        syscall2(
+            SyscallId::RENAMEAT2,
+            old_path.as_ptr() as usize,
+            new_path.as_ptr() as usize,
+        )
+    }
+}
+
+/// 创建目录，路径需要以 `\0` 结尾。
+#[inline]
+pub fn mkdir(path: &str) -> isize {
+    unsafe { syscall1(SyscallId::MKDIRAT, path.as_ptr() as usize) }
+}
+
+/// 切换当前工作目录，路径需要以 `\0` 结尾。
+#[inline]
+pub fn chdir(path: &str) -> isize {
+    unsafe { syscall1(SyscallId::CHDIR, path.as_ptr() as usize) }
+}
+
+/// 检查文件是否存在，以及能否以 `mode`（[`R_OK`]、[`W_OK`]、[`X_OK`] 的组合）访问。
+///
+/// 路径需要以 `\0` 结尾。不会打开文件。
+#[inline]
+pub fn access(path: &str, mode: usize) -> isize {
+    unsafe { syscall2(SyscallId::FACCESSAT, path.as_ptr() as usize, mode) }
+}
+
+/// 查询 `path` 所在文件系统的容量，路径需要以 `\0` 结尾。
+///
+/// see <https://man7.org/linux/man-pages/man2/statfs.2.html>.
+#[inline]
+pub fn statfs(path: &str, buf: &mut Statfs) -> isize {
+    unsafe {
+        syscall2(
+            SyscallId::STATFS,
+            path.as_ptr() as usize,
+            buf as *mut _ as usize,
+        )
+    }
+}
+
+/// 将当前工作目录写入 `buf`，以 `\0` 结尾。
+///
+/// 成功时返回 `buf` 的地址，`buf` 不够长时返回 -1。
+#[inline]
+pub fn getcwd(buf: &mut [u8]) -> isize {
+    unsafe { syscall2(SyscallId::GETCWD, buf.as_mut_ptr() as _, buf.len()) }
+}
+
 /// see <https://man7.org/linux/man-pages/man2/exit.2.html>.
 #[inline]
 pub fn exit(exit_code: i32) -> isize {
     unsafe { syscall1(SyscallId::EXIT, exit_code as _) }
 }
 
+/// 把程序断点移到 `addr`，返回移动后的断点，失败时断点不变。`addr` 为 0 时只查询当前断点。
+///
+/// see <https://man7.org/linux/man-pages/man2/brk.2.html>.
+#[inline]
+pub fn brk(addr: usize) -> isize {
+    unsafe { syscall1(SyscallId::BRK, addr) }
+}
+
+/// 把程序断点移动 `increment` 字节，返回原来的断点，失败时返回 -1。
+///
+/// see <https://man7.org/linux/man-pages/man2/sbrk.2.html>.
+pub fn sbrk(increment: isize) -> isize {
+    let old = brk(0);
+    if increment == 0 {
+        return old;
+    }
+    match (old as usize).checked_add_signed(increment) {
+        Some(new) if brk(new) == new as isize => old,
+        _ => -1,
+    }
+}
+
+/// 映射 `len` 字节的匿名内存，返回映射的地址，失败时返回 -1。
+///
+/// `addr` 为 0 时由内核选择地址。
+///
+/// see <https://man7.org/linux/man-pages/man2/mmap.2.html>.
+#[inline]
+pub fn mmap(addr: usize, len: usize, prot: i32, flags: i32) -> isize {
+    unsafe {
+        syscall6(
+            SyscallId::MMAP,
+            addr,
+            len,
+            prot as _,
+            flags as _,
+            usize::MAX,
+            0,
+        )
+    }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/munmap.2.html>.
+#[inline]
+pub fn munmap(addr: usize, len: usize) -> isize {
+    unsafe { syscall2(SyscallId::MUNMAP, addr, len) }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/madvise.2.html>.
+#[inline]
+pub fn madvise(addr: usize, len: usize, advice: usize) -> isize {
+    unsafe { syscall3(SyscallId::MADVISE, addr, len, advice) }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/mprotect.2.html>.
+#[inline]
+pub fn mprotect(addr: usize, len: usize, prot: i32) -> isize {
+    unsafe { syscall3(SyscallId::MPROTECT, addr, len, prot as _) }
+}
+
+/// 按 `key` 查找或新建一个至少 `size` 字节的共享内存段，返回段号。
+///
+/// see <https://man7.org/linux/man-pages/man2/shmget.2.html>.
+#[inline]
+pub fn shmget(key: usize, size: usize, flags: usize) -> isize {
+    unsafe { syscall3(SyscallId::SHMGET, key, size, flags) }
+}
+
+/// 把共享内存段映射到 `addr`，`addr` 为 0 时由内核选择地址。返回映射的地址。
+///
+/// see <https://man7.org/linux/man-pages/man2/shmat.2.html>.
+#[inline]
+pub fn shmat(shmid: usize, addr: usize, flags: usize) -> isize {
+    unsafe { syscall3(SyscallId::SHMAT, shmid, addr, flags) }
+}
+
+/// 解除 `addr` 处的共享内存段映射。
+///
+/// see <https://man7.org/linux/man-pages/man2/shmdt.2.html>.
+#[inline]
+pub fn shmdt(addr: usize) -> isize {
+    unsafe { syscall1(SyscallId::SHMDT, addr) }
+}
+
+/// 控制共享内存段，只支持 [`IPC_RMID`](crate::IPC_RMID)。
+///
+/// see <https://man7.org/linux/man-pages/man2/shmctl.2.html>.
+#[inline]
+pub fn shmctl(shmid: usize, cmd: usize) -> isize {
+    unsafe { syscall3(SyscallId::SHMCTL, shmid, cmd, 0) }
+}
+
 /// see <https://man7.org/linux/man-pages/man2/sched_yield.2.html>.
+///
+/// 与 Linux 不同，有其他任务因此被调度时返回 1，调用者立即被重新调度时返回 0。
 #[inline]
 pub fn sched_yield() -> isize {
     unsafe { syscall0(SyscallId::SCHED_YIELD) }
 }
 
+/// 让出处理器，并让 `pid` 进程下一个运行。
+///
+/// `pid` 不是就绪的进程时与 [`sched_yield`] 相同。
+#[inline]
+pub fn yield_to(pid: usize) -> isize {
+    unsafe { syscall1(SyscallId::YIELD_TO, pid) }
+}
+
+/// 查询抢占调度的时间片长度（毫秒）。
+///
+/// 内核不做抢占时返回 0。
+#[inline]
+pub fn sched_getquantum() -> isize {
+    unsafe { syscall0(SyscallId::SCHED_GETQUANTUM) }
+}
+
 /// see <https://man7.org/linux/man-pages/man2/clock_gettime.2.html>.
 #[inline]
 pub fn clock_gettime(clockid: ClockId, tp: *mut TimeSpec) -> isize {
     unsafe { syscall2(SyscallId::CLOCK_GETTIME, clockid.0, tp as _) }
 }
 
+/// see <https://man7.org/linux/man-pages/man2/gettimeofday.2.html>.
+///
+/// `tz` 非空时总是填入 [`TimeZone::UTC`]。
+#[inline]
+pub fn gettimeofday(tv: *mut TimeVal, tz: *mut TimeZone) -> isize {
+    unsafe { syscall2(SyscallId::GETTIMEOFDAY, tv as _, tz as _) }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/nanosleep.2.html>.
+#[inline]
+pub fn nanosleep(req: *const TimeSpec, rem: *mut TimeSpec) -> isize {
+    unsafe { syscall2(SyscallId::NANOSLEEP, req as _, rem as _) }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/clock_nanosleep.2.html>.
+#[inline]
+pub fn clock_nanosleep(
+    clockid: ClockId,
+    flags: usize,
+    req: *const TimeSpec,
+    rem: *mut TimeSpec,
+) -> isize {
+    unsafe {
+        syscall4(
+            SyscallId::CLOCK_NANOSLEEP,
+            clockid.0,
+            flags,
+            req as _,
+            rem as _,
+        )
+    }
+}
+
 pub fn fork() -> isize {
-    unsafe { syscall0(SyscallId::CLONE) }
+    unsafe { syscall2(SyscallId::CLONE, 0, 0) }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/clone.2.html>.
+///
+/// 子任务从本次调用返回 0，`stack` 非 0 时换到这个栈上继续执行。
+///
+/// # Safety
+///
+/// 设置了 [`CLONE_VM`] 时子任务和调用者共享地址空间，却不再拥有调用者的栈帧，
+/// 子任务应当只访问静态变量，然后直接 [`exit`]。
+#[inline]
+pub unsafe fn clone(flags: usize, stack: usize) -> isize {
+    syscall2(SyscallId::CLONE, flags, stack)
 }
 
 pub fn exec(path: &str) -> isize {
-    unsafe { syscall2(SyscallId::EXECVE, path.as_ptr() as usize, path.len()) }
+    unsafe { syscall3(SyscallId::EXECVE, path.as_ptr() as usize, path.len(), 0) }
+}
+
+/// 创建运行应用程序 `path` 的子进程，返回子进程号，找不到应用程序时返回 -1。
+#[inline]
+pub fn spawn(path: &str) -> isize {
+    unsafe { syscall2(SyscallId::SPAWN, path.as_ptr() as usize, path.len()) }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/execve.2.html>.
+///
+/// `argv` 的每一项指向以 NUL 结尾的字符串，最后一项必须是空指针。
+#[inline]
+pub fn execv(path: &str, argv: &[*const u8]) -> isize {
+    assert!(argv.last().is_some_and(|arg| arg.is_null()));
+    unsafe {
+        syscall3(
+            SyscallId::EXECVE,
+            path.as_ptr() as usize,
+            path.len(),
+            argv.as_ptr() as usize,
+        )
+    }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/wait4.2.html>.
+///
+/// 内核不会阻塞：子进程还在运行时，带 [`WNOHANG`] 返回 0，否则返回 -2。
+/// `rusage` 非空时填入被回收子进程的资源使用统计。
+#[inline]
+pub fn wait4(pid: isize, exit_code_ptr: *mut i32, options: usize, rusage: *mut RUsage) -> isize {
+    unsafe {
+        syscall4(
+            SyscallId::WAIT4,
+            pid as _,
+            exit_code_ptr as _,
+            options,
+            rusage as _,
+        )
+    }
 }
 
 pub fn wait(exit_code_ptr: *mut i32) -> isize {
     loop {
-        match unsafe { syscall2(SyscallId::WAIT4, usize::MAX, exit_code_ptr as usize) } {
+        match wait4(-1, exit_code_ptr, 0, core::ptr::null_mut()) {
             -2 => {
                 sched_yield();
             }
@@ -78,7 +463,7 @@ pub fn wait(exit_code_ptr: *mut i32) -> isize {
 
 pub fn waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
     loop {
-        match unsafe { syscall2(SyscallId::WAIT4, pid as usize, exit_code_ptr as usize) } {
+        match wait4(pid, exit_code_ptr, 0, core::ptr::null_mut()) {
             -2 => {
                 sched_yield();
             }
@@ -91,6 +476,72 @@ pub fn getpid() -> isize {
     unsafe { syscall0(SyscallId::GETPID) }
 }
 
+/// see <https://man7.org/linux/man-pages/man2/getppid.2.html>.
+#[inline]
+pub fn getppid() -> isize {
+    unsafe { syscall0(SyscallId::GETPPID) }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/getuid.2.html>.
+#[inline]
+pub fn getuid() -> isize {
+    unsafe { syscall0(SyscallId::GETUID) }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/geteuid.2.html>.
+#[inline]
+pub fn geteuid() -> isize {
+    unsafe { syscall0(SyscallId::GETEUID) }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/getgid.2.html>.
+#[inline]
+pub fn getgid() -> isize {
+    unsafe { syscall0(SyscallId::GETGID) }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/getegid.2.html>.
+#[inline]
+pub fn getegid() -> isize {
+    unsafe { syscall0(SyscallId::GETEGID) }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/prctl.2.html>.
+#[inline]
+pub fn prctl(option: usize, arg2: usize) -> isize {
+    unsafe { syscall2(SyscallId::PRCTL, option, arg2) }
+}
+
+/// 将所有进程的信息格式化到 `buf`，每行一个进程。
+///
+/// 返回完整输出需要的长度，大于 `buf.len()` 时不写入。
+#[inline]
+pub fn ps(buf: &mut [u8]) -> isize {
+    unsafe { syscall2(SyscallId::PS, buf.as_mut_ptr() as _, buf.len()) }
+}
+
+/// 将内核统计的各类陷入次数格式化到 `buf`，每行一项。
+///
+/// 返回完整输出需要的长度，大于 `buf.len()` 时不写入。
+#[inline]
+pub fn trapstats(buf: &mut [u8]) -> isize {
+    unsafe { syscall2(SyscallId::TRAPSTATS, buf.as_mut_ptr() as _, buf.len()) }
+}
+
+/// see <https://man7.org/linux/man-pages/man2/sysinfo.2.html>.
+#[inline]
+pub fn sysinfo(info: &mut SysInfo) -> isize {
+    unsafe { syscall1(SyscallId::SYSINFO, info as *mut _ as _) }
+}
+
+/// 以 `cmd` 指定的方式关机或重启，成功时不返回。
+///
+/// see <https://man7.org/linux/man-pages/man2/reboot.2.html>.
+#[inline]
+pub fn reboot(cmd: usize) -> isize {
+    unsafe { syscall3(SyscallId::REBOOT, REBOOT_MAGIC1, REBOOT_MAGIC2, cmd) }
+}
+
 #[inline]
 pub fn kill(pid: isize, signum: SignalNo) -> isize {
     unsafe { syscall2(SyscallId::KILL, pid as _, signum as _) }
@@ -132,6 +583,28 @@ pub fn gettid() -> isize {
     unsafe { syscall0(SyscallId::GETTID) }
 }
 
+/// 线程退出时内核把 `tidptr` 指向的值清零。返回调用者的 tid。
+///
+/// see <https://man7.org/linux/man-pages/man2/set_tid_address.2.html>.
+#[inline]
+pub fn set_tid_address(tidptr: *mut u32) -> isize {
+    unsafe { syscall1(SyscallId::SET_TID_ADDRESS, tidptr as _) }
+}
+
+/// 登记调用者的健壮互斥锁链表，线程退出时内核给它仍持有的锁标记 [`FUTEX_OWNER_DIED`]。
+///
+/// see <https://man7.org/linux/man-pages/man2/set_robust_list.2.html>.
+#[inline]
+pub fn set_robust_list(head: *const RobustListHead) -> isize {
+    unsafe {
+        syscall2(
+            SyscallId::SET_ROBUST_LIST,
+            head as _,
+            core::mem::size_of::<RobustListHead>(),
+        )
+    }
+}
+
 #[inline]
 pub fn waittid(tid: usize) -> isize {
     loop {
@@ -46,6 +46,16 @@ impl<P, MP: Manage<P, ProcId> + Schedule<ProcId>> PManager<P, MP> {
     pub fn set_manager(&mut self, manager: MP) {
         self.manager = Some(manager);
     }
+    /// 让 `id` 成为下一个被调度的进程，它不在就绪队列中时返回 `false`
+    #[inline]
+    pub fn schedule_next(&mut self, id: ProcId) -> bool {
+        self.manager.as_mut().unwrap().prioritize(id)
+    }
+    /// 就绪队列中是否有等待调度的进程，不包括当前进程
+    #[inline]
+    pub fn has_ready(&self) -> bool {
+        !self.manager.as_ref().unwrap().is_empty()
+    }
     /// 阻塞当前进程
     pub fn make_current_suspend(&mut self) {
         let id = self.current.unwrap();
@@ -87,6 +97,16 @@ impl<P, MP: Manage<P, ProcId> + Schedule<ProcId>> PManager<P, MP> {
         let id = self.current.unwrap();
         self.manager.as_mut().unwrap().get_mut(id)
     }
+    /// 当前进程 Id
+    #[inline]
+    pub fn current_id(&self) -> Option<ProcId> {
+        self.current
+    }
+    /// 遍历所有进程的 Id 及其父进程 Id
+    #[inline]
+    pub fn iter_rel(&self) -> impl Iterator<Item = (ProcId, ProcId)> + '_ {
+        self.rel_map.iter().map(|(id, rel)| (*id, rel.parent))
+    }
     /// 获取某个进程
     #[inline]
     pub fn get_task(&mut self, id: ProcId) -> Option<&mut P> {
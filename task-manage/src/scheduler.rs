@@ -4,4 +4,13 @@ pub trait Schedule<I: Copy + Ord> {
     fn add(&mut self, id: I);
     /// 出队
     fn fetch(&mut self) -> Option<I>;
+    /// 队列中没有等待调度的任务
+    fn is_empty(&self) -> bool;
+    /// 把 `id` 移到队首，使它下一个出队，`id` 不在队列中时返回 `false`
+    ///
+    /// 默认不支持插队。
+    #[inline]
+    fn prioritize(&mut self, _id: I) -> bool {
+        false
+    }
 }
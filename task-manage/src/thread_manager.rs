@@ -64,6 +64,11 @@ impl<P, T, MT: Manage<T, ThreadId> + Schedule<ThreadId>, MP: Manage<P, ProcId>>
     pub fn set_proc_manager(&mut self, proc_manager: MP) {
         self.proc_manager = Some(proc_manager);
     }
+    /// 就绪队列中是否有等待调度的线程，不包括当前线程
+    #[inline]
+    pub fn has_ready(&self) -> bool {
+        !self.manager.as_ref().unwrap().is_empty()
+    }
     /// 当前线程重新入队
     pub fn make_current_suspend(&mut self) {
         if let Some(id) = self.current {
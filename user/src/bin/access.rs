@@ -0,0 +1,22 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{access, close, open, OpenFlags, F_OK, R_OK, W_OK, X_OK};
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let fd = open("access_test\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    close(fd as usize);
+    assert_eq!(access("access_test\0", F_OK), 0);
+    assert_eq!(access("access_test\0", R_OK | W_OK), 0);
+    assert_eq!(access("/\0", X_OK), 0);
+    assert_eq!(access("access_missing\0", F_OK), -1);
+    // 未知的模式位
+    assert_eq!(access("access_test\0", 8), -1);
+    println!("Test access OK!");
+    0
+}
@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{madvise, MADV_DONTNEED};
+
+const PAGE_SIZE: usize = 4096;
+
+/// 独占一整页的栈上缓冲区。
+#[repr(C, align(4096))]
+struct Page([usize; PAGE_SIZE / core::mem::size_of::<usize>()]);
+
+/// 释放 `addr` 处的物理页，下一次访问会缺页。
+fn drop_page(addr: usize) {
+    assert_eq!(madvise(addr, PAGE_SIZE, MADV_DONTNEED), 0);
+}
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let mut page = Page([0; PAGE_SIZE / core::mem::size_of::<usize>()]);
+    let ptr = page.0.as_mut_ptr();
+    let atomic = unsafe { AtomicUsize::from_ptr(ptr) };
+    // AMO 缺页，内核补上零页后重新执行，只生效一次
+    drop_page(ptr as usize);
+    assert_eq!(atomic.fetch_add(1, Ordering::SeqCst), 0);
+    assert_eq!(atomic.load(Ordering::SeqCst), 1);
+    // LR 缺页
+    drop_page(ptr as usize);
+    assert_eq!(
+        atomic.compare_exchange(0, 2, Ordering::SeqCst, Ordering::SeqCst),
+        Ok(0)
+    );
+    assert_eq!(atomic.load(Ordering::SeqCst), 2);
+    println!("Test amo_fault OK!");
+    0
+}
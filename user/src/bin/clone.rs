@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{clone, exit, waitpid, waittid, CLONE_FILES, CLONE_VM};
+
+const STACK_SIZE: usize = 4096;
+
+#[repr(align(16))]
+struct Stack([u8; STACK_SIZE]);
+
+static mut STACK: Stack = Stack([0; STACK_SIZE]);
+static SHARED: AtomicUsize = AtomicUsize::new(0);
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let top = unsafe { core::ptr::addr_of_mut!(STACK) as usize } + STACK_SIZE;
+    // 共享地址空间必须给出栈，文件描述符表只能和地址空间一起共享
+    assert_eq!(unsafe { clone(CLONE_VM | CLONE_FILES, 0) }, -1);
+    assert_eq!(unsafe { clone(CLONE_VM, top) }, -1);
+    assert_eq!(unsafe { clone(CLONE_FILES, 0) }, -1);
+
+    // 不共享任何资源就是 fork，子进程的修改父进程看不到
+    let pid = unsafe { clone(0, 0) };
+    if pid == 0 {
+        SHARED.store(1, Ordering::Relaxed);
+        exit(0);
+        unreachable!();
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(SHARED.load(Ordering::Relaxed), 0);
+
+    // 共享地址空间的线程
+    let tid = unsafe { clone(CLONE_VM | CLONE_FILES, top) };
+    if tid == 0 {
+        SHARED.store(42, Ordering::Relaxed);
+        exit(7);
+        unreachable!();
+    }
+    assert!(tid > 0);
+    assert_eq!(waittid(tid as usize), 7);
+    assert_eq!(SHARED.load(Ordering::Relaxed), 42);
+    println!("Test clone OK!");
+    0
+}
@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork};
+
+const PAGE: usize = 4096;
+
+static mut BUFFER: [u8; 4 * PAGE] = [0; 4 * PAGE];
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    let buffer = unsafe { &mut *core::ptr::addr_of_mut!(BUFFER) };
+    buffer.fill(1);
+    let pid = fork();
+    if pid == 0 {
+        // 父进程先运行完，它的写入分开了共享页，子进程仍然看到 fork 时的内容
+        assert!(buffer.iter().all(|&b| b == 1));
+        // 父进程已经退出，最后一个持有者写入时直接恢复写权限
+        buffer[0] = 3;
+        buffer[PAGE] = 3;
+        assert_eq!(buffer[0], 3);
+        assert_eq!(buffer[PAGE], 3);
+        assert!(buffer[PAGE + 1..].iter().all(|&b| b == 1));
+        println!("cow child: pages separated");
+        exit(0);
+    }
+    assert!(pid > 0);
+    // 每页写一次，逐页触发写时复制
+    for page in buffer.chunks_mut(PAGE) {
+        page[0] = 2;
+    }
+    assert!(buffer.chunks(PAGE).all(|page| page[0] == 2 && page[1] == 1));
+    println!("cow parent: child = {pid}");
+    0
+}
@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{access, close, open, statfs, write, OpenFlags, Statfs, F_OK};
+
+fn stat() -> Statfs {
+    let mut buf = Statfs::default();
+    assert_eq!(statfs("/\0", &mut buf), 0);
+    buf
+}
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    let before = stat();
+    println!("Filesystem      Size      Used     Avail   Inodes   IFree");
+    println!(
+        "easy-fs   {:>7}K {:>8}K {:>8}K {:>8} {:>7}",
+        before.total_blocks * before.block_size / 1024,
+        (before.total_blocks - before.free_blocks) * before.block_size / 1024,
+        before.free_blocks * before.block_size / 1024,
+        before.total_inodes,
+        before.free_inodes,
+    );
+    assert!(before.free_blocks <= before.total_blocks);
+    assert!(before.free_inodes < before.total_inodes);
+    assert_eq!(statfs("no_such_file\0", &mut Statfs::default()), -1);
+
+    // 新文件占用一个索引节点，写入的数据占用数据块。
+    // 上次运行留下的文件会被清空重写，占用不变
+    let existed = access("df_test\0", F_OK) == 0;
+    let fd = open("df_test\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd > 0);
+    assert_eq!(write(fd as usize, &[0u8; 1024]), 1024);
+    close(fd as usize);
+    let after = stat();
+    if existed {
+        assert_eq!(after.free_inodes, before.free_inodes);
+        assert_eq!(after.free_blocks, before.free_blocks);
+    } else {
+        assert_eq!(after.free_inodes, before.free_inodes - 1);
+        assert!(after.free_blocks + 2 <= before.free_blocks);
+    }
+    println!("df passed!");
+    0
+}
@@ -0,0 +1,14 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    println!("Into Test ebreak, we will execute an ebreak as a checkpoint...");
+    println!("Kernel should dump registers, then kill this application unless built with `debug-ebreak`!");
+    unsafe { core::arch::asm!("ebreak") };
+    println!("Continued past ebreak.");
+    0
+}
@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, epoll_create, epoll_ctl, epoll_wait, open, EpollEvent, OpenFlags, EPOLLIN, EPOLLOUT,
+    EPOLL_CTL_ADD, EPOLL_CTL_DEL, EPOLL_CTL_MOD,
+};
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    let epfd = epoll_create();
+    assert!(epfd > 0);
+    let epfd = epfd as usize;
+    let file = open(
+        "epoll_file\0",
+        OpenFlags::CREATE | OpenFlags::RDWR | OpenFlags::TRUNC,
+    );
+    assert!(file > 0);
+    let file = file as usize;
+
+    let mut events = [EpollEvent::default(); 4];
+    // 没有登记任何描述符时立即返回
+    assert_eq!(epoll_wait(epfd, &mut events, 0), 0);
+
+    let out = EpollEvent {
+        events: EPOLLOUT,
+        data: 1,
+    };
+    let rw = EpollEvent {
+        events: EPOLLIN,
+        data: 2,
+    };
+    assert_eq!(epoll_ctl(epfd, EPOLL_CTL_ADD, 1, &out), 0);
+    assert_eq!(epoll_ctl(epfd, EPOLL_CTL_ADD, file, &rw), 0);
+    assert_eq!(epoll_ctl(epfd, EPOLL_CTL_ADD, file, &rw), -1);
+    assert_eq!(epoll_ctl(epfd, EPOLL_CTL_ADD, epfd, &rw), -1);
+
+    // 水平触发：没有读写也会反复报告
+    for _ in 0..2 {
+        assert_eq!(epoll_wait(epfd, &mut events, 0), 2);
+        assert_eq!((events[0].data, events[0].events), (1, EPOLLOUT));
+        assert_eq!((events[1].data, events[1].events), (2, EPOLLIN));
+    }
+    assert_eq!(epoll_wait(epfd, &mut events[..1], 0), 1);
+
+    let both = EpollEvent {
+        events: EPOLLIN | EPOLLOUT,
+        data: 3,
+    };
+    assert_eq!(epoll_ctl(epfd, EPOLL_CTL_MOD, file, &both), 0);
+    assert_eq!(epoll_ctl(epfd, EPOLL_CTL_DEL, 1, &out), 0);
+    assert_eq!(epoll_ctl(epfd, EPOLL_CTL_DEL, 1, &out), -1);
+    assert_eq!(epoll_wait(epfd, &mut events, 0), 1);
+    assert_eq!((events[0].data, events[0].events), (3, EPOLLIN | EPOLLOUT));
+
+    // 关闭的描述符自动注销，等到超时返回 0
+    close(file);
+    assert_eq!(epoll_wait(epfd, &mut events, 20), 0);
+    assert_eq!(epoll_ctl(epfd, EPOLL_CTL_MOD, file, &both), -1);
+
+    close(epfd);
+    assert_eq!(epoll_wait(epfd, &mut events, 0), -1);
+    println!("epoll passed!");
+    0
+}
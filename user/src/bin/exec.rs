@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exec, execv, exit, fork, mmap, munmap, waitpid, MAP_ANONYMOUS, MAP_PRIVATE, PROT_READ,
+    PROT_WRITE,
+};
+
+const PAGE_SIZE: usize = 4096;
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    assert_eq!(exec("no_such_app"), -1);
+    // 第二个参数从第一页末尾开始，跨到第二页
+    let addr = mmap(
+        0,
+        2 * PAGE_SIZE,
+        PROT_READ | PROT_WRITE,
+        MAP_PRIVATE | MAP_ANONYMOUS,
+    );
+    assert!(addr > 0);
+    let addr = addr as usize;
+    let area = unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, 2 * PAGE_SIZE) };
+    let arg = &mut area[PAGE_SIZE - 3..];
+    arg[..6].copy_from_slice(b"hello\0");
+    let arg = arg.as_ptr();
+    let pid = fork();
+    if pid == 0 {
+        let argv = [b"exec_target\0".as_ptr(), arg, core::ptr::null()];
+        execv("exec_target", &argv);
+        // 只有 exec 失败才会回到这里
+        exit(-1);
+    }
+    assert!(pid > 0);
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    // exec_target 以参数个数为退出码
+    assert_eq!(exit_code, 2);
+    // 参数过长或者读到未映射的页还没有遇到 NUL 时失败，进程保持不变
+    area[PAGE_SIZE - 3..].fill(b'x');
+    let argv = [b"exec_target\0".as_ptr(), arg, core::ptr::null()];
+    assert_eq!(execv("exec_target", &argv), -1);
+    assert_eq!(munmap(addr + PAGE_SIZE, PAGE_SIZE), 0);
+    assert_eq!(execv("exec_target", &argv), -1);
+    assert_eq!(munmap(addr, PAGE_SIZE), 0);
+    println!("Test exec OK!");
+    0
+}
@@ -0,0 +1,18 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::args;
+
+/// 打印参数，以参数个数为退出码。
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    let mut argc = 0;
+    for (i, arg) in args().enumerate() {
+        println!("argv[{i}] = {arg}");
+        argc += 1;
+    }
+    argc
+}
@@ -0,0 +1,31 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::format;
+use user_lib::{sched_yield, write, STDOUT};
+
+/// 每个进程的轮数。
+const TURNS: usize = 50;
+
+/// 轮转调度的公平性测试。
+///
+/// 同一个程序在用例列表中出现多次，被加载到不同的基址，用 `main` 的地址区分各个进程。
+/// 每轮打印一行 `fairness <id> turn <n>`，做一段计算后主动让出。
+/// `cargo xtask test-matrix` 统计第一个进程做完时各进程的轮数，要求大致相等。
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let id = main as usize;
+    let mut x = id;
+    for turn in 0..TURNS {
+        // 一行用一次系统调用输出，不会和别的进程交错
+        write(STDOUT, format!("fairness {id:#x} turn {turn}\n").as_bytes());
+        for _ in 0..10_000 {
+            x = core::hint::black_box(x.wrapping_mul(31).wrapping_add(7));
+        }
+        sched_yield();
+    }
+    write(STDOUT, format!("fairness {id:#x} done\n").as_bytes());
+    0
+}
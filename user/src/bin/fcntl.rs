@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, fcntl, open, pread, write, OpenFlags, FD_CLOEXEC, F_DUPFD, F_GETFD, F_GETFL, F_SETFD,
+    F_SETFL, O_NONBLOCK, O_RDWR,
+};
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    let fd = open("fcntl_test\0", OpenFlags::CREATE | OpenFlags::RDWR);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    // 描述符标志
+    assert_eq!(fcntl(fd, F_GETFD, 0), 0);
+    assert_eq!(fcntl(fd, F_SETFD, FD_CLOEXEC), 0);
+    assert_eq!(fcntl(fd, F_GETFD, 0), FD_CLOEXEC as isize);
+    // 复制到不小于 10 的最小空闲描述符，新描述符没有 close-on-exec 标志
+    let dup = fcntl(fd, F_DUPFD, 10);
+    assert_eq!(dup, 10);
+    let dup = dup as usize;
+    assert_eq!(fcntl(dup, F_GETFD, 0), 0);
+    assert_eq!(fcntl(fd, F_DUPFD, 10), 11);
+    close(11);
+    assert_eq!(write(dup, b"fcntl"), 5);
+    let mut buffer = [0u8; 8];
+    assert_eq!(pread(fd, &mut buffer, 0), 5);
+    assert_eq!(&buffer[..5], b"fcntl");
+    // 文件状态标志
+    assert_eq!(fcntl(fd, F_GETFL, 0), O_RDWR as isize);
+    assert_eq!(fcntl(fd, F_SETFL, O_NONBLOCK), 0);
+    assert_eq!(fcntl(fd, F_GETFL, 0), (O_RDWR | O_NONBLOCK) as isize);
+    // 不支持的命令和无效的描述符
+    assert_eq!(fcntl(fd, 1024, 0), -1);
+    assert_eq!(fcntl(100, F_GETFD, 0), -1);
+    close(dup);
+    close(fd);
+    println!("fcntl passed!");
+    0
+}
@@ -0,0 +1,27 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork};
+
+static mut COUNTER: usize = 0;
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    unsafe { COUNTER = 42 };
+    let local = [7u8; 64];
+    let pid = fork();
+    if pid == 0 {
+        // 子进程看到的是 fork 时的副本，看不到父进程之后的修改
+        assert_eq!(unsafe { COUNTER }, 42);
+        assert!(local.iter().all(|&b| b == 7));
+        println!("fork child: copy intact");
+        exit(0);
+    }
+    assert!(pid > 0);
+    unsafe { COUNTER = 100 };
+    println!("fork parent: child = {pid}");
+    0
+}
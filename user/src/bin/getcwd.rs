@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{chdir, getcwd, mkdir};
+
+const PAGE: usize = 4096;
+
+/// 两页的缓冲区，其中至少有一个页边界。
+static mut SPAN: [u8; 2 * PAGE] = [0; 2 * PAGE];
+
+/// 读取当前工作目录，去掉结尾的 `\0`。
+fn cwd(buf: &mut [u8]) -> &str {
+    assert_eq!(getcwd(buf), buf.as_ptr() as isize);
+    let len = buf.iter().position(|&b| b == 0).unwrap();
+    core::str::from_utf8(&buf[..len]).unwrap()
+}
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let mut buf = [0u8; 64];
+    assert_eq!(cwd(&mut buf), "/");
+    // 目录可能在上一次运行时已经建好
+    mkdir("cwd_test\0");
+    assert_eq!(chdir("cwd_test\0"), 0);
+    assert_eq!(cwd(&mut buf), "/cwd_test");
+    // 缓冲区放不下路径和结尾的 `\0`
+    assert_eq!(getcwd(&mut buf[..9]), -1);
+    // 路径跨越页边界
+    let span = unsafe { &mut *core::ptr::addr_of_mut!(SPAN) };
+    let boundary = PAGE - span.as_ptr() as usize % PAGE;
+    let at = (boundary + PAGE - 3) % PAGE;
+    assert_eq!(cwd(&mut span[at..at + 16]), "/cwd_test");
+    assert_eq!(chdir("..\0"), 0);
+    assert_eq!(cwd(&mut buf), "/");
+    println!("Test getcwd OK!");
+    0
+}
@@ -0,0 +1,28 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpid, getppid, waitpid};
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    let pid = getpid();
+    let ppid = getppid();
+    println!("parent: pid = {pid}, ppid = {ppid}");
+    assert!(pid > 0);
+    let child = fork();
+    if child == 0 {
+        println!("child: pid = {}, ppid = {}", getpid(), getppid());
+        assert_eq!(getppid(), pid);
+        assert_ne!(getpid(), pid);
+        exit(0);
+    }
+    assert!(child > 0);
+    let mut exit_code = 0;
+    assert_eq!(waitpid(child, &mut exit_code), child);
+    assert_eq!(exit_code, 0);
+    println!("Test getppid OK!");
+    0
+}
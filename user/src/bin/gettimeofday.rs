@@ -0,0 +1,31 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{gettimeofday, TimeVal, TimeZone};
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let mut tv = TimeVal {
+        tv_sec: 0,
+        tv_usec: 0,
+    };
+    let mut tz = TimeZone {
+        tz_minuteswest: -1,
+        tz_dsttime: -1,
+    };
+    // 时区总是 UTC
+    assert_eq!(gettimeofday(&mut tv, &mut tz), 0);
+    assert_eq!(tz, TimeZone::UTC);
+    assert!(tv.tv_usec < 1_000_000);
+    let first = tv;
+    // 时区可以为空
+    assert_eq!(gettimeofday(&mut tv, core::ptr::null_mut()), 0);
+    assert!((tv.tv_sec, tv.tv_usec) >= (first.tv_sec, first.tv_usec));
+    // 时区指针不可写
+    assert_eq!(gettimeofday(&mut tv, 8 as *mut TimeZone), -1);
+    println!("Test gettimeofday OK!");
+    0
+}
@@ -0,0 +1,17 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{getegid, geteuid, getgid, getuid};
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    assert_eq!(getuid(), 0);
+    assert_eq!(geteuid(), 0);
+    assert_eq!(getgid(), 0);
+    assert_eq!(getegid(), 0);
+    println!("Test getuid OK!");
+    0
+}
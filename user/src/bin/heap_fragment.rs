@@ -0,0 +1,93 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+extern crate alloc;
+
+use alloc::alloc::{alloc, dealloc};
+use core::alloc::Layout;
+use user_lib::largest_free_block;
+
+/// 同时存活的分配数量。
+const SLOTS: usize = 32;
+/// 测量可用容量时使用的块大小。
+const PROBE: usize = 256;
+
+/// 线性同余随机数，保证每次运行的分配序列相同。
+struct Lcg(u32);
+
+impl Lcg {
+    fn next(&mut self) -> usize {
+        self.0 = self.0.wrapping_mul(1103515245).wrapping_add(12345);
+        (self.0 >> 16) as usize
+    }
+}
+
+/// 不断分配 `PROBE` 字节的块直到失败，全部释放后返回块数。
+fn capacity() -> usize {
+    let layout = Layout::from_size_align(PROBE, 8).unwrap();
+    let mut blocks = [core::ptr::null_mut(); 64];
+    let mut n = 0;
+    while n < blocks.len() {
+        let ptr = unsafe { alloc(layout) };
+        if ptr.is_null() {
+            break;
+        }
+        blocks[n] = ptr;
+        n += 1;
+    }
+    for &ptr in &blocks[..n] {
+        unsafe { dealloc(ptr, layout) };
+    }
+    n
+}
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let before = capacity();
+    assert!(before > 0);
+    let largest = largest_free_block();
+    assert!(largest >= PROBE);
+
+    let mut rng = Lcg(2023);
+    let mut slots: [Option<(*mut u8, Layout)>; SLOTS] = [None; SLOTS];
+    for _ in 0..4096 {
+        let i = rng.next() % SLOTS;
+        match slots[i].take() {
+            Some((ptr, layout)) => unsafe {
+                // 内容没被别的分配覆盖
+                for j in 0..layout.size() {
+                    assert_eq!(*ptr.add(j), i as u8);
+                }
+                dealloc(ptr, layout);
+            },
+            None => {
+                let size = 8 << (rng.next() % 8);
+                let layout = Layout::from_size_align(size, 8).unwrap();
+                let ptr = unsafe { alloc(layout) };
+                // 碎片化时分配失败是允许的，但必须返回空指针
+                if !ptr.is_null() {
+                    unsafe { ptr.write_bytes(i as u8, size) };
+                    slots[i] = Some((ptr, layout));
+                }
+            }
+        }
+    }
+    for (ptr, layout) in slots.iter_mut().filter_map(Option::take) {
+        unsafe { dealloc(ptr, layout) };
+    }
+    // 全部释放后应当合并回原来的状态
+    assert_eq!(capacity(), before);
+    assert_eq!(largest_free_block(), largest);
+
+    // 超出托管区域和超出最大层级的请求都应该失败而不是越界
+    let too_big = Layout::from_size_align(64 << 10, 8).unwrap();
+    assert!(unsafe { alloc(too_big) }.is_null());
+    let huge = Layout::from_size_align(1 << (usize::BITS - 2), 8).unwrap();
+    assert!(unsafe { alloc(huge) }.is_null());
+
+    println!("Test heap fragment OK!");
+    0
+}
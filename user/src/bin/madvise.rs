@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::{read_volatile, write_volatile};
+use user_lib::{madvise, MADV_DONTNEED, MADV_NORMAL};
+
+const PAGE_SIZE: usize = 4096;
+
+/// 独占一整页的栈上缓冲区。
+#[repr(C, align(4096))]
+struct Page([u8; PAGE_SIZE]);
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let mut page = Page([0; PAGE_SIZE]);
+    let ptr = page.0.as_mut_ptr();
+    let addr = ptr as usize;
+    unsafe {
+        for i in 0..PAGE_SIZE {
+            write_volatile(ptr.add(i), 0xab);
+        }
+    }
+    assert_eq!(madvise(addr + 1, PAGE_SIZE, MADV_DONTNEED), -1);
+    assert_eq!(madvise(addr, PAGE_SIZE + 1, MADV_DONTNEED), -1);
+    assert_eq!(madvise(addr, PAGE_SIZE, MADV_NORMAL), 0);
+    assert_eq!(unsafe { read_volatile(ptr) }, 0xab);
+    // 释放之后再访问，得到一个全零的新页
+    assert_eq!(madvise(addr, PAGE_SIZE, MADV_DONTNEED), 0);
+    for i in 0..PAGE_SIZE {
+        assert_eq!(unsafe { read_volatile(ptr.add(i)) }, 0);
+    }
+    unsafe { write_volatile(ptr, 1) };
+    assert_eq!(unsafe { read_volatile(ptr) }, 1);
+    println!("Test madvise OK!");
+    0
+}
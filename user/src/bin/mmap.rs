@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::{read_volatile, write_volatile};
+use user_lib::{
+    mmap, munmap, MAP_ANONYMOUS, MAP_FIXED, MAP_PRIVATE, MAP_SHARED, PROT_READ, PROT_WRITE,
+};
+
+const PAGE_SIZE: usize = 4096;
+const RW: i32 = PROT_READ | PROT_WRITE;
+const ANON: i32 = MAP_PRIVATE | MAP_ANONYMOUS;
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let addr = mmap(0, 3 * PAGE_SIZE, RW, ANON);
+    assert!(addr > 0);
+    let addr = addr as usize;
+    assert_eq!(addr % PAGE_SIZE, 0);
+    let ptr = addr as *mut u8;
+    for i in 0..3 * PAGE_SIZE {
+        assert_eq!(unsafe { read_volatile(ptr.add(i)) }, 0);
+        unsafe { write_volatile(ptr.add(i), 0xab) };
+    }
+    // 不对齐、重叠和不支持的映射都失败
+    assert_eq!(mmap(addr + 1, PAGE_SIZE, RW, ANON), -1);
+    assert_eq!(mmap(addr + PAGE_SIZE, PAGE_SIZE, RW, ANON | MAP_FIXED), -1);
+    assert_eq!(mmap(0, PAGE_SIZE, RW, MAP_SHARED | MAP_ANONYMOUS), -1);
+    assert_eq!(mmap(0, 0, RW, ANON), -1);
+    assert_eq!(munmap(addr + 1, PAGE_SIZE), -1);
+    // 在中间挖一个洞，再把它映射回来，得到的是清零的新页
+    assert_eq!(munmap(addr + PAGE_SIZE, PAGE_SIZE), 0);
+    assert_eq!(
+        mmap(addr + PAGE_SIZE, PAGE_SIZE, RW, ANON | MAP_FIXED),
+        (addr + PAGE_SIZE) as isize
+    );
+    for i in 0..PAGE_SIZE {
+        assert_eq!(unsafe { read_volatile(ptr.add(PAGE_SIZE + i)) }, 0);
+    }
+    assert_eq!(unsafe { read_volatile(ptr) }, 0xab);
+    assert_eq!(unsafe { read_volatile(ptr.add(2 * PAGE_SIZE)) }, 0xab);
+    // 不足一页的长度向上取整
+    assert_eq!(munmap(addr, 3 * PAGE_SIZE - 1), 0);
+    let again = mmap(addr, PAGE_SIZE, RW, ANON);
+    assert_eq!(again, addr as isize);
+    assert_eq!(munmap(addr, PAGE_SIZE), 0);
+    println!("Test mmap OK!");
+    0
+}
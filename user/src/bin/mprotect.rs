@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::{read_volatile, write_volatile};
+use user_lib::{mprotect, PROT_EXEC, PROT_NONE, PROT_READ, PROT_WRITE};
+
+const PAGE_SIZE: usize = 4096;
+
+/// 独占一整页的栈上缓冲区。
+#[repr(C, align(4096))]
+struct Page([u8; PAGE_SIZE]);
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let mut page = Page([0; PAGE_SIZE]);
+    let ptr = page.0.as_mut_ptr();
+    let addr = ptr as usize;
+    unsafe { write_volatile(ptr, 0xab) };
+    assert_eq!(mprotect(addr + 1, PAGE_SIZE, PROT_READ), -1);
+    assert_eq!(mprotect(addr, PAGE_SIZE + 1, PROT_READ), -1);
+    assert_eq!(mprotect(addr, PAGE_SIZE, PROT_NONE), -1);
+    assert_eq!(mprotect(addr, PAGE_SIZE, PROT_WRITE | PROT_EXEC), -1);
+    // 未映射的页
+    assert_eq!(mprotect(0, PAGE_SIZE, PROT_READ), -1);
+    // 只读之后仍然可以读到原来的数据
+    assert_eq!(mprotect(addr, PAGE_SIZE, PROT_READ), 0);
+    assert_eq!(unsafe { read_volatile(ptr) }, 0xab);
+    // 恢复可写
+    assert_eq!(mprotect(addr, PAGE_SIZE, PROT_READ | PROT_WRITE), 0);
+    unsafe { write_volatile(ptr, 1) };
+    assert_eq!(unsafe { read_volatile(ptr) }, 1);
+    println!("Test mprotect OK!");
+    0
+}
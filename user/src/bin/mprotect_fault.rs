@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::{read_volatile, write_volatile};
+use user_lib::{
+    exit, fork, mmap, mprotect, waitpid, MAP_ANONYMOUS, MAP_PRIVATE, PROT_READ, PROT_WRITE,
+};
+
+const PAGE_SIZE: usize = 4096;
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let addr = mmap(
+        0,
+        PAGE_SIZE,
+        PROT_READ | PROT_WRITE,
+        MAP_PRIVATE | MAP_ANONYMOUS,
+    );
+    assert!(addr > 0);
+    let ptr = addr as *mut u8;
+    unsafe { write_volatile(ptr, 0xab) };
+    assert_eq!(mprotect(addr as usize, PAGE_SIZE, PROT_READ), 0);
+    let pid = fork();
+    if pid == 0 {
+        // 只读之后仍然可以读，写入触发写缺页，进程被杀死
+        assert_eq!(unsafe { read_volatile(ptr) }, 0xab);
+        unsafe { write_volatile(ptr, 1) };
+        exit(0);
+    }
+    assert!(pid > 0);
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_ne!(exit_code, 0);
+    assert_eq!(unsafe { read_volatile(ptr) }, 0xab);
+    println!("Test mprotect fault OK!");
+    0
+}
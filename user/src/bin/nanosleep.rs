@@ -0,0 +1,20 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{clock_gettime, nanosleep, ClockId, TimeSpec};
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let mut start = TimeSpec::ZERO;
+    assert_eq!(clock_gettime(ClockId::CLOCK_MONOTONIC, &mut start), 0);
+    let req = TimeSpec::from_millsecond(50);
+    assert_eq!(nanosleep(&req, core::ptr::null_mut()), 0);
+    let mut end = TimeSpec::ZERO;
+    assert_eq!(clock_gettime(ClockId::CLOCK_MONOTONIC, &mut end), 0);
+    assert!(end >= start + req);
+    println!("Test nanosleep OK!");
+    0
+}
@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::read_unaligned;
+use user_lib::{
+    clock_gettime, exit, fork, mmap, munmap, waitpid, write, ClockId, TimeSpec, MAP_ANONYMOUS,
+    MAP_PRIVATE, PROT_READ, PROT_WRITE, STDOUT,
+};
+
+const PAGE_SIZE: usize = 4096;
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let addr = mmap(
+        0,
+        2 * PAGE_SIZE,
+        PROT_READ | PROT_WRITE,
+        MAP_PRIVATE | MAP_ANONYMOUS,
+    );
+    assert!(addr > 0);
+    let addr = addr as usize;
+    let area = unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, 2 * PAGE_SIZE) };
+    // 输出跨页的缓冲区
+    let text = b"this line crosses a page boundary\n";
+    let start = PAGE_SIZE - text.len() / 2;
+    area[start..][..text.len()].copy_from_slice(text);
+    let buf = &area[start..][..text.len()];
+    assert_eq!(write(STDOUT, buf), text.len() as isize);
+    // 跨页写回时间
+    let tp = (addr + PAGE_SIZE - 4) as *mut TimeSpec;
+    assert_eq!(clock_gettime(ClockId::CLOCK_MONOTONIC, tp), 0);
+    let time = unsafe { read_unaligned(tp) };
+    assert!(time.tv_sec > 0 || time.tv_nsec > 0);
+    // fork 之后两页都是写时复制页，内核写入之前先复制，父进程的内容不变
+    let pid = fork();
+    if pid == 0 {
+        exit(clock_gettime(ClockId::CLOCK_MONOTONIC, tp) as i32);
+    }
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(unsafe { read_unaligned(tp) }, time);
+    // 第二页移除之后，跨页的缓冲区整体失败
+    assert_eq!(munmap(addr + PAGE_SIZE, PAGE_SIZE), 0);
+    let buf = unsafe { core::slice::from_raw_parts(buf.as_ptr(), buf.len()) };
+    assert_eq!(write(STDOUT, buf), -1);
+    assert_eq!(clock_gettime(ClockId::CLOCK_MONOTONIC, tp), -1);
+    assert_eq!(munmap(addr, PAGE_SIZE), 0);
+    println!("Test page span OK!");
+    0
+}
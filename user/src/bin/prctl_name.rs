@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{prctl, PR_GET_NAME, PR_SET_NAME};
+
+const PAGE: usize = 4096;
+const TASK_COMM_LEN: usize = 16;
+
+/// 两页的缓冲区，其中至少有一个页边界，名字放在它的两侧。
+static mut BUFFER: [u8; 2 * PAGE] = [0; 2 * PAGE];
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    let buffer = unsafe { &mut *core::ptr::addr_of_mut!(BUFFER) };
+    // 缓冲区里的页边界前 5 个字节处
+    let boundary = PAGE - buffer.as_ptr() as usize % PAGE;
+    let at = (boundary + PAGE - 5) % PAGE;
+
+    // 跨页的名字
+    buffer[at..at + 12].copy_from_slice(b"cross-page\0\0");
+    assert_eq!(prctl(PR_SET_NAME, buffer[at..].as_ptr() as usize), 0);
+    buffer.fill(0xff);
+    assert_eq!(prctl(PR_GET_NAME, buffer[at..].as_ptr() as usize), 0);
+    assert_eq!(&buffer[at..at + 11], b"cross-page\0");
+    assert!(buffer[at + 11..at + TASK_COMM_LEN].iter().all(|&c| c == 0));
+
+    // 超长的名字被截断到 `TASK_COMM_LEN - 1` 字节
+    buffer[at..at + 20].copy_from_slice(b"a-very-long-name-123");
+    assert_eq!(prctl(PR_SET_NAME, buffer[at..].as_ptr() as usize), 0);
+    assert_eq!(prctl(PR_GET_NAME, buffer[at..].as_ptr() as usize), 0);
+    assert_eq!(&buffer[at..at + TASK_COMM_LEN], b"a-very-long-nam\0");
+
+    println!("Test prctl_name OK!");
+    0
+}
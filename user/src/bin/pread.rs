@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, open, pread, pwrite, write, OpenFlags};
+
+const PAGE: usize = 4096;
+
+/// 跨越几页的缓冲区，每页单独翻译。
+static mut SPAN: [u8; 3 * PAGE] = [0; 3 * PAGE];
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    let fd = open("pread_test\0", OpenFlags::CREATE | OpenFlags::RDWR);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    assert_eq!(write(fd, b"hello world"), 11);
+    // 定位写不改变文件偏移
+    assert_eq!(pwrite(fd, b"WORLD", 6), 5);
+    assert_eq!(write(fd, b"!"), 1);
+
+    let mut buffer = [0u8; 32];
+    let len = pread(fd, &mut buffer, 0);
+    assert_eq!(len, 12);
+    assert_eq!(&buffer[..len as usize], b"hello WORLD!");
+    let len = pread(fd, &mut buffer, 6);
+    assert_eq!(&buffer[..len as usize], b"WORLD!");
+    assert_eq!(pread(fd, &mut buffer, -1), -1);
+
+    // 从页中间开始、跨越两个页边界的缓冲区
+    let span = unsafe { &mut *core::ptr::addr_of_mut!(SPAN) };
+    let range = 100..span.len() - 100;
+    for (i, b) in span[range.clone()].iter_mut().enumerate() {
+        *b = (i % 251) as u8;
+    }
+    let len = range.len() as isize;
+    assert_eq!(pwrite(fd, &span[range.clone()], 12), len);
+    span.fill(0);
+    assert_eq!(pread(fd, &mut span[range.clone()], 12), len);
+    assert!(span[range]
+        .iter()
+        .enumerate()
+        .all(|(i, &b)| b == (i % 251) as u8));
+    close(fd);
+    println!("pread passed!");
+    0
+}
@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{clock_gettime, exit, fork, ClockId, TimeSpec};
+
+/// 每个进程忙等的时长（毫秒）。
+const SPIN_MS: usize = 100;
+
+fn now_ns() -> usize {
+    let mut time = TimeSpec::ZERO;
+    clock_gettime(ClockId::CLOCK_MONOTONIC, &mut time as *mut _ as _);
+    time.to_nanosecond().unwrap() as usize
+}
+
+/// 忙等 `SPIN_MS` 毫秒，从不让出，返回相邻两次读时间之间最长的间隔（纳秒）。
+fn spin() -> usize {
+    let start = now_ns();
+    let mut last = start;
+    let mut max_gap = 0;
+    while last - start < SPIN_MS * 1_000_000 {
+        let now = now_ns();
+        max_gap = max_gap.max(now - last);
+        last = now;
+    }
+    max_gap
+}
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let pid = fork();
+    assert!(pid >= 0);
+    let max_gap = spin();
+    if pid == 0 {
+        exit(0);
+    }
+    // 两个进程都不让出，只有被时钟中断抢占时才会出现毫秒级的间隔
+    println!("longest gap = {} us", max_gap / 1000);
+    assert!(max_gap >= 1_000_000);
+    println!("Test preempt OK!");
+    0
+}
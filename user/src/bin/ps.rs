@@ -0,0 +1,28 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+extern crate alloc;
+
+use alloc::vec;
+use user_lib::ps;
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let mut buf = vec![0u8; 256];
+    loop {
+        let len = ps(&mut buf);
+        if len < 0 {
+            println!("ps failed");
+            return -1;
+        }
+        let len = len as usize;
+        if len <= buf.len() {
+            print!("{}", core::str::from_utf8(&buf[..len]).unwrap());
+            return 0;
+        }
+        buf.resize(len, 0);
+    }
+}
@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, open, readv, writev, IoVec, OpenFlags};
+
+fn iov(buf: &[u8]) -> IoVec {
+    IoVec {
+        base: buf.as_ptr() as usize,
+        len: buf.len(),
+    }
+}
+
+fn iov_mut(buf: &mut [u8]) -> IoVec {
+    IoVec {
+        base: buf.as_mut_ptr() as usize,
+        len: buf.len(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    let fd = open("readv_test\0", OpenFlags::CREATE | OpenFlags::RDWR);
+    assert!(fd > 0);
+    let fd = fd as usize;
+    assert_eq!(writev(fd, &[iov(b"hello "), iov(b"world")]), 11);
+    // 文件偏移已经在末尾，什么也读不到，返回 0 而不是错误
+    let mut a = [0u8; 4];
+    let mut b = [0u8; 16];
+    assert_eq!(readv(fd, &[iov_mut(&mut a), iov_mut(&mut b)]), 0);
+    assert_eq!(readv(fd, &[]), 0);
+    close(fd);
+
+    // 第一段读满之后接着读第二段，读到文件末尾时停止
+    let fd = open("readv_test\0", OpenFlags::RDONLY) as usize;
+    assert_eq!(readv(fd, &[iov_mut(&mut a), iov_mut(&mut b)]), 11);
+    assert_eq!(&a, b"hell");
+    assert_eq!(&b[..7], b"o world");
+    a.fill(0);
+    b.fill(0);
+    assert_eq!(readv(fd, &[iov_mut(&mut a), iov_mut(&mut b)]), 0);
+    close(fd);
+    println!("Test readv OK!");
+    0
+}
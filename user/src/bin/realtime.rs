@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    clock_gettime, mmap, munmap, ClockId, TimeSpec, MAP_ANONYMOUS, MAP_PRIVATE, PROT_READ,
+    PROT_WRITE,
+};
+
+const PAGE_SIZE: usize = 4096;
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let mut monotonic = TimeSpec::ZERO;
+    let mut realtime = TimeSpec::ZERO;
+    assert_eq!(clock_gettime(ClockId::CLOCK_MONOTONIC, &mut monotonic), 0);
+    assert_eq!(clock_gettime(ClockId::CLOCK_REALTIME, &mut realtime), 0);
+    // 墙上时间是启动时刻的 Unix 时间加上单调时钟，不会比单调时钟小
+    assert!(realtime >= monotonic);
+    assert!(realtime.tv_nsec < 1_000_000_000);
+    println!("realtime: {realtime}");
+    // 不可写的 `tp` 仍然失败
+    let addr = mmap(
+        0,
+        PAGE_SIZE,
+        PROT_READ | PROT_WRITE,
+        MAP_PRIVATE | MAP_ANONYMOUS,
+    );
+    assert!(addr > 0);
+    assert_eq!(munmap(addr as usize, PAGE_SIZE), 0);
+    let tp = addr as *mut TimeSpec;
+    assert_eq!(clock_gettime(ClockId::CLOCK_REALTIME, tp), -1);
+    println!("Test realtime OK!");
+    0
+}
@@ -0,0 +1,16 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::reboot;
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    // 只检查非法命令被拒绝，合法命令会让整个系统关机或重启
+    assert_eq!(reboot(0), -1);
+    assert_eq!(reboot(!0), -1);
+    println!("Test reboot OK!");
+    0
+}
@@ -0,0 +1,23 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mkdir, rename};
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    // 目录可能在上一次运行时已经建好
+    mkdir("rename_a\0");
+    mkdir("rename_a/b\0");
+    // 目录不能移到它自己或它的子目录下
+    assert_eq!(rename("rename_a\0", "rename_a/c\0"), -1);
+    assert_eq!(rename("rename_a\0", "rename_a/b/c\0"), -1);
+    assert_eq!(rename("rename_a/b\0", "rename_a/b/c\0"), -1);
+    // 移到别处再移回来
+    assert_eq!(rename("rename_a/b\0", "rename_b\0"), 0);
+    assert_eq!(rename("rename_b\0", "rename_a/b\0"), 0);
+    println!("Test rename_dir OK!");
+    0
+}
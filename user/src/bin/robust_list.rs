@@ -0,0 +1,75 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::{
+    mem::offset_of,
+    ptr::addr_of_mut,
+    sync::atomic::{AtomicU32, Ordering},
+};
+use user_lib::{
+    exit, gettid, set_robust_list, thread_create, waittid, RobustList, RobustListHead,
+    FUTEX_OWNER_DIED, FUTEX_TID_MASK, FUTEX_WAITERS,
+};
+
+/// 健壮互斥锁，链表节点嵌在锁里。
+#[repr(C)]
+struct RobustMutex {
+    list: RobustList,
+    futex: AtomicU32,
+}
+
+impl RobustMutex {
+    const fn new() -> Self {
+        Self {
+            list: RobustList { next: 0 },
+            futex: AtomicU32::new(0),
+        }
+    }
+}
+
+static mut HEAD: RobustListHead = RobustListHead {
+    list: RobustList { next: 0 },
+    futex_offset: offset_of!(RobustMutex, futex) as _,
+    list_op_pending: 0,
+};
+/// 子线程持有着退出的锁，有线程在等待它。
+static mut HELD: RobustMutex = RobustMutex::new();
+/// 同一个链表上、属于别的线程的锁，不应被改动。
+static mut OTHERS: RobustMutex = RobustMutex::new();
+
+fn thread_main(other: usize) -> isize {
+    let tid = gettid() as u32;
+    unsafe {
+        let head = addr_of_mut!(HEAD);
+        let held = addr_of_mut!(HELD);
+        let others = addr_of_mut!(OTHERS);
+        (*held).futex.store(tid | FUTEX_WAITERS, Ordering::SeqCst);
+        (*others).futex.store(other as u32, Ordering::SeqCst);
+        // 表头 -> HELD -> OTHERS -> 表头
+        (*head).list.next = held as usize;
+        (*held).list.next = others as usize;
+        (*others).list.next = head as usize;
+        assert_eq!(set_robust_list(head), 0);
+    }
+    // 不解锁就退出
+    exit(0)
+}
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    let main_tid = gettid() as usize;
+    let tid = thread_create(thread_main as usize, main_tid);
+    assert!(tid > 0);
+    assert_eq!(waittid(tid as usize), 0);
+    let held = unsafe { (*addr_of_mut!(HELD)).futex.load(Ordering::SeqCst) };
+    assert_eq!(held & FUTEX_TID_MASK, 0);
+    assert_ne!(held & FUTEX_OWNER_DIED, 0);
+    assert_ne!(held & FUTEX_WAITERS, 0);
+    let others = unsafe { (*addr_of_mut!(OTHERS)).futex.load(Ordering::SeqCst) };
+    assert_eq!(others, main_tid as u32);
+    println!("Test robust_list OK!");
+    0
+}
@@ -0,0 +1,29 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, prctl, waitpid, PR_GET_SPAWN, PR_SET_SPAWN};
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    assert_eq!(prctl(PR_GET_SPAWN, 0), 1);
+    let pid = fork();
+    if pid == 0 {
+        // 子进程放弃创建子进程的能力，之后不能再恢复
+        assert_eq!(prctl(PR_SET_SPAWN, 0), 0);
+        assert_eq!(prctl(PR_GET_SPAWN, 0), 0);
+        assert_eq!(fork(), -1);
+        assert_eq!(prctl(PR_SET_SPAWN, 1), -1);
+        assert_eq!(prctl(PR_GET_SPAWN, 0), 0);
+        exit(0);
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    // 父进程不受影响
+    assert_eq!(prctl(PR_GET_SPAWN, 0), 1);
+    println!("Test sandbox OK!");
+    0
+}
@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use user_lib::{brk, sbrk};
+
+const PAGE: usize = 4096;
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    // 直接移动断点：新页清零可写，收缩之后断点回到原处
+    let old = sbrk(0);
+    assert!(old > 0 && old as usize % PAGE == 0);
+    assert_eq!(sbrk(2 * PAGE as isize), old);
+    let area = unsafe { core::slice::from_raw_parts_mut(old as *mut u8, 2 * PAGE) };
+    assert!(area.iter().all(|&b| b == 0));
+    area.fill(0x5a);
+    assert_eq!(sbrk(-(2 * PAGE as isize)), old + 2 * PAGE as isize);
+    assert_eq!(sbrk(0), old);
+    // 断点不能移到堆的起始地址之下
+    assert_eq!(brk(old as usize - PAGE), old);
+    // 超出 16 KiB 的静态空间之后，分配器向内核要更多空间
+    let mut blocks = Vec::new();
+    for i in 0..8 {
+        let mut block = Vec::<u8>::with_capacity(16 << 10);
+        block.resize(16 << 10, i as u8);
+        blocks.push(block);
+    }
+    for (i, block) in blocks.iter().enumerate() {
+        assert!(block.iter().all(|&b| b == i as u8));
+    }
+    assert!(sbrk(0) > old);
+    println!("sbrk: heap grew by {} bytes", sbrk(0) - old);
+    0
+}
@@ -0,0 +1,23 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::sched_getquantum;
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let quantum = sched_getquantum();
+    if quantum < 0 {
+        println!("sched_getquantum failed");
+        return -1;
+    }
+    if quantum == 0 {
+        println!("preemption disabled");
+    } else {
+        println!("quantum = {quantum} ms");
+    }
+    println!("Test sched_getquantum OK!");
+    0
+}
@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, open, pread, sendfile, write, OpenFlags};
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    let src = open(
+        "sendfile_src\0",
+        OpenFlags::CREATE | OpenFlags::RDWR | OpenFlags::TRUNC,
+    );
+    let dst = open(
+        "sendfile_dst\0",
+        OpenFlags::CREATE | OpenFlags::RDWR | OpenFlags::TRUNC,
+    );
+    assert!(src > 0 && dst > 0);
+    let (src, dst) = (src as usize, dst as usize);
+    // 超过一个块，检查分块搬运
+    let mut content = [0u8; 1000];
+    for (i, b) in content.iter_mut().enumerate() {
+        *b = b'a' + (i % 26) as u8;
+    }
+    assert_eq!(write(src, &content), 1000);
+
+    // 给出偏移指针时不改变源文件偏移，源文件偏移此时在末尾
+    let mut offset = 0isize;
+    assert_eq!(sendfile(dst, src, Some(&mut offset), 600), 600);
+    assert_eq!(offset, 600);
+    assert_eq!(sendfile(dst, src, Some(&mut offset), 600), 400);
+    assert_eq!(offset, 1000);
+    assert_eq!(sendfile(dst, src, None, 100), 0);
+
+    let mut buffer = [0u8; 1024];
+    assert_eq!(pread(dst, &mut buffer, 0), 1000);
+    assert_eq!(&buffer[..1000], &content[..]);
+
+    // 不给偏移指针时从源文件偏移处读并推进它，输出到标准输出
+    let again = open("sendfile_src\0", OpenFlags::RDONLY);
+    assert!(again > 0);
+    let again = again as usize;
+    assert_eq!(sendfile(1, again, None, 26), 26);
+    assert_eq!(sendfile(1, again, None, 26), 26);
+    println!();
+    assert_eq!(pread(again, &mut buffer[..1], 52), 1);
+    assert_eq!(sendfile(again, dst, None, 1), -1);
+    assert_eq!(sendfile(dst, 0, None, 1), -1);
+    close(again);
+    close(src);
+    close(dst);
+    println!("sendfile passed!");
+    0
+}
@@ -0,0 +1,31 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use user_lib::{exit, gettid, sched_yield, set_tid_address, thread_create, waittid};
+
+/// 子线程登记的地址，退出时由内核清零。
+static CHILD_TID: AtomicU32 = AtomicU32::new(u32::MAX);
+
+fn thread_main() -> isize {
+    let tid = set_tid_address(CHILD_TID.as_ptr());
+    assert_eq!(tid, gettid());
+    CHILD_TID.store(tid as _, Ordering::SeqCst);
+    exit(7)
+}
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    let tid = thread_create(thread_main as usize, 0);
+    assert!(tid > 0);
+    // 像 pthread_join 一样等到内核把登记的地址清零
+    while CHILD_TID.load(Ordering::SeqCst) != 0 {
+        sched_yield();
+    }
+    assert_eq!(waittid(tid as usize), 7);
+    println!("Test set_tid_address OK!");
+    0
+}
@@ -0,0 +1,61 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, shmat, shmctl, shmdt, shmget, waitpid, IPC_CREAT, IPC_EXCL, IPC_PRIVATE, IPC_RMID,
+};
+
+const KEY: usize = 0x5348;
+const SIZE: usize = 8192;
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    // 同一个键找到同一个段
+    let shmid = shmget(KEY, SIZE, IPC_CREAT);
+    assert!(shmid >= 0);
+    let shmid = shmid as usize;
+    assert_eq!(shmget(KEY, SIZE, 0), shmid as isize);
+    assert_eq!(shmget(KEY, SIZE, IPC_CREAT | IPC_EXCL), -1);
+    assert_eq!(shmget(KEY, SIZE * 2, 0), -1);
+    let private = shmget(IPC_PRIVATE, SIZE, IPC_CREAT);
+    assert!(private >= 0 && private != shmid as isize);
+    assert_eq!(shmctl(private as usize, IPC_RMID), 0);
+
+    let addr = shmat(shmid, 0, 0);
+    assert!(addr > 0);
+    let words = unsafe { core::slice::from_raw_parts_mut(addr as *mut usize, SIZE / 8) };
+    assert!(words.iter().all(|&w| w == 0));
+    words[0] = 1;
+
+    // 子进程继承映射，写入对父进程可见
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(words[0], 1);
+        words[SIZE / 8 - 1] = 0xdead;
+        // 再映射一次，两处地址是同一组物理页
+        let again = shmat(shmid, 0, 0);
+        assert!(again > 0 && again != addr);
+        unsafe { *(again as *mut usize) = 2 };
+        assert_eq!(words[0], 2);
+        assert_eq!(shmdt(again as usize), 0);
+        exit(0);
+    }
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(words[0], 2);
+    assert_eq!(words[SIZE / 8 - 1], 0xdead);
+
+    // 标记删除后不能再映射，已有的映射仍然有效
+    assert_eq!(shmctl(shmid, IPC_RMID), 0);
+    assert_eq!(shmat(shmid, 0, 0), -1);
+    assert_eq!(shmget(KEY, SIZE, 0), -1);
+    words[1] = 3;
+    assert_eq!(shmdt(addr as usize), 0);
+    assert_eq!(shmdt(addr as usize), -1);
+    println!("shm passed!");
+    0
+}
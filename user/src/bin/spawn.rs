@@ -0,0 +1,20 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{spawn, waitpid};
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    assert_eq!(spawn("no_such_app"), -1);
+    let pid = spawn("exec_target");
+    assert!(pid > 0);
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    // 不带参数启动，exec_target 的退出码是 0
+    assert_eq!(exit_code, 0);
+    println!("Test spawn OK!");
+    0
+}
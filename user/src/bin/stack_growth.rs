@@ -0,0 +1,27 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::hint::black_box;
+
+/// 每层递归占用至少 512 字节栈空间。
+fn recurse(depth: usize) -> usize {
+    let frame = black_box([depth as u8; 512]);
+    if depth == 0 {
+        0
+    } else {
+        recurse(depth - 1) + frame[511] as usize
+    }
+}
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    // 约 24 KiB，需要栈增长数次
+    let depth = 48;
+    let sum = recurse(depth);
+    assert_eq!(sum, (1..=depth).sum::<usize>());
+    println!("Test stack growth OK!");
+    0
+}
@@ -0,0 +1,36 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{clock_gettime, getpid, ClockId, TimeSpec};
+
+/// 测量的系统调用次数。
+const ROUNDS: usize = 1_000_000;
+
+fn now() -> TimeSpec {
+    let mut time = TimeSpec::ZERO;
+    clock_gettime(ClockId::CLOCK_MONOTONIC, &mut time as *mut _);
+    time
+}
+
+/// 测量一次系统调用往返（陷入、传送门、返回）的平均耗时。
+///
+/// `getpid` 在内核里几乎不做事，耗时主要就是往返本身。
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let pid = getpid();
+    let start = now();
+    for _ in 0..ROUNDS {
+        assert_eq!(getpid(), pid);
+    }
+    let end = now();
+    let total = (end.to_nanosecond().unwrap() - start.to_nanosecond().unwrap()) as usize;
+    println!(
+        "{ROUNDS} getpid calls in {} ms, {} ns per syscall",
+        total / 1_000_000,
+        total / ROUNDS,
+    );
+    0
+}
@@ -0,0 +1,23 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{sysinfo, SysInfo};
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let mut info = SysInfo::default();
+    assert_eq!(sysinfo(&mut info), 0);
+    println!(
+        "uptime = {}s, procs = {}, table frames = {}, data frames = {}",
+        info.uptime, info.procs, info.table_frames, info.data_frames
+    );
+    // 至少有调用者自己的根页表和代码页
+    assert!(info.procs >= 1);
+    assert!(info.table_frames > 0);
+    assert!(info.data_frames > 0);
+    println!("Test sysinfo OK!");
+    0
+}
@@ -0,0 +1,23 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{ioctl, tcgetattr, tcsetattr, Termios, ECHO, ICANON, STDIN, STDOUT};
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let mut termios = Termios::RAW;
+    assert_eq!(tcgetattr(STDIN, &mut termios), 0);
+    assert_eq!(termios.lflag & (ICANON | ECHO), ICANON | ECHO);
+    // 标准输入输出共用同一个终端
+    assert_eq!(tcsetattr(STDOUT, &Termios::RAW), 0);
+    assert_eq!(tcgetattr(STDIN, &mut termios), 0);
+    assert_eq!(termios.lflag, 0);
+    assert_eq!(tcsetattr(STDIN, &Termios::COOKED), 0);
+    // 不支持的请求
+    assert_eq!(ioctl(STDIN, 0, 0), -1);
+    println!("Test ioctl OK!");
+    0
+}
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{clock_gettime, exit, sched_yield, thread_create, waittid, ClockId, TimeSpec};
+
+/// 每个线程让出的次数。
+const ROUNDS: usize = 100_000;
+
+fn now() -> TimeSpec {
+    let mut time = TimeSpec::ZERO;
+    clock_gettime(ClockId::CLOCK_MONOTONIC, &mut time as *mut _);
+    time
+}
+
+fn ping_pong() -> ! {
+    for _ in 0..ROUNDS {
+        sched_yield();
+    }
+    exit(0);
+    unreachable!()
+}
+
+/// 测量同一进程的两个线程互相让出时，一次切换的平均耗时。
+///
+/// 两个线程在同一个地址空间，与 `syscall_bench` 的结果比较可以看出切换线程额外的开销。
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let start = now();
+    let a = thread_create(ping_pong as usize, 0);
+    let b = thread_create(ping_pong as usize, 0);
+    assert!(a > 0 && b > 0);
+    assert_eq!(waittid(a as usize), 0);
+    assert_eq!(waittid(b as usize), 0);
+    let end = now();
+    let total = (end.to_nanosecond().unwrap() - start.to_nanosecond().unwrap()) as usize;
+    println!(
+        "{} thread switches in {} ms, {} ns per switch",
+        2 * ROUNDS,
+        total / 1_000_000,
+        total / (2 * ROUNDS),
+    );
+    0
+}
@@ -0,0 +1,32 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+extern crate alloc;
+
+use alloc::vec;
+use user_lib::{sched_yield, trapstats};
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    // 先制造几次系统调用
+    for _ in 0..3 {
+        sched_yield();
+    }
+    let mut buf = vec![0u8; 64];
+    loop {
+        let len = trapstats(&mut buf);
+        if len < 0 {
+            println!("trapstats failed");
+            return -1;
+        }
+        let len = len as usize;
+        if len <= buf.len() {
+            print!("{}", core::str::from_utf8(&buf[..len]).unwrap());
+            return 0;
+        }
+        buf.resize(len, 0);
+    }
+}
@@ -1,63 +1,45 @@
-#![no_std]
-#![no_main]
-#![allow(clippy::println_empty_string)]
-
-extern crate alloc;
-
-#[macro_use]
-extern crate user_lib;
-
-const LF: u8 = 0x0au8;
-const CR: u8 = 0x0du8;
-const DL: u8 = 0x7fu8;
-const BS: u8 = 0x08u8;
-
-use alloc::string::String;
-use user_lib::{exec, fork, getchar, waitpid};
-
-#[no_mangle]
-pub extern "C" fn main() -> i32 {
-    println!("Rust user shell");
-    let mut line: String = String::new(); // 记录着当前输入的命令
-    print!(">> ");
-    loop {
-        let c = getchar();
-        match c {
-            LF | CR => {
-                // 换行
-                println!();
-                if !line.is_empty() {
-                    let pid = fork();
-                    if pid == 0 {
-                        // child process
-                        if exec(line.as_str()) == -1 {
-                            println!("Error when executing!");
-                            return -4;
-                        }
-                        unreachable!();
-                    } else {
-                        let mut exit_code: i32 = 0;
-                        let exit_pid = waitpid(pid as isize, &mut exit_code);
-                        assert_eq!(pid, exit_pid);
-                        println!("Shell: Process {} exited with code {}", pid, exit_code);
-                    }
-                    line.clear();
-                }
-                print!(">> ");
-            }
-            BS | DL => {
-                // backspace
-                if !line.is_empty() {
-                    print!("{}", BS as char);
-                    print!(" ");
-                    print!("{}", BS as char);
-                    line.pop();
-                }
-            }
-            _ => {
-                print!("{}", c as char);
-                line.push(c as char);
-            }
-        }
-    }
-}
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exec, fork, read, waitpid, STDIN};
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    println!("Rust user shell");
+    // 记录着当前输入的命令，回显和行编辑由内核完成
+    let mut buf = [0u8; 256];
+    loop {
+        print!(">> ");
+        let len = read(STDIN, &mut buf);
+        if len <= 0 {
+            continue;
+        }
+        let line = match core::str::from_utf8(&buf[..len as usize]) {
+            Ok(line) => line.trim(),
+            Err(_) => {
+                println!("Invalid input!");
+                continue;
+            }
+        };
+        if line.is_empty() {
+            continue;
+        }
+        let pid = fork();
+        if pid == 0 {
+            // child process
+            if exec(line) == -1 {
+                println!("Error when executing!");
+                return -4;
+            }
+            unreachable!();
+        } else {
+            let mut exit_code: i32 = 0;
+            let exit_pid = waitpid(pid as isize, &mut exit_code);
+            assert_eq!(pid, exit_pid);
+            println!("Shell: Process {} exited with code {}", pid, exit_code);
+        }
+    }
+}
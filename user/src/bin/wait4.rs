@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, sched_yield, wait4, RUsage, WNOHANG};
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        // 子进程在用户态忙一会儿，留下可以统计的运行时间
+        let mut x = 0usize;
+        for i in 0..10_000_000 {
+            x = x.wrapping_mul(31).wrapping_add(i);
+        }
+        core::hint::black_box(x);
+        exit(7);
+    }
+    let mut exit_code: i32 = 0;
+    let mut usage = RUsage::default();
+    // 不支持的选项
+    assert_eq!(wait4(pid, &mut exit_code, 2, &mut usage), -1);
+    // 子进程还在运行时 WNOHANG 立即返回 0
+    loop {
+        match wait4(pid, &mut exit_code, WNOHANG, &mut usage) {
+            0 => {
+                sched_yield();
+            }
+            ret => {
+                assert_eq!(ret, pid);
+                break;
+            }
+        }
+    }
+    assert_eq!(exit_code, 7);
+    let utime = usage.ru_utime.tv_sec * 1_000_000 + usage.ru_utime.tv_usec;
+    println!("child utime = {utime}us, maxrss = {}KiB", usage.ru_maxrss);
+    assert!(utime > 0);
+    assert!(usage.ru_maxrss > 0);
+    // 已经回收过的子进程
+    assert_eq!(wait4(pid, &mut exit_code, WNOHANG, &mut usage), -1);
+    println!("Test wait4 OK!");
+    0
+}
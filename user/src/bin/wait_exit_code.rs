@@ -0,0 +1,28 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, wait, waitpid};
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        // 孙进程的父进程先退出，它成为进程树的根，退出后不留给任何进程回收
+        if fork() == 0 {
+            exit(1);
+        }
+        exit(42);
+    }
+    assert!(pid > 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(wait(&mut exit_code), pid);
+    assert_eq!(exit_code, 42);
+    // 没有子进程了，孙进程不会被当作子进程
+    assert_eq!(wait(&mut exit_code), -1);
+    assert_eq!(waitpid(pid, &mut exit_code), -1);
+    println!("Test wait exit code OK!");
+    0
+}
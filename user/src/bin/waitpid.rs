@@ -0,0 +1,24 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, waitpid};
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exit(7);
+    }
+    assert!(pid > 0);
+    let mut exit_code: i32 = 0;
+    // 子进程还没有运行时阻塞等待
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(exit_code, 7);
+    // 已经回收过的进程
+    assert_eq!(waitpid(pid, &mut exit_code), -1);
+    println!("Test waitpid OK!");
+    0
+}
@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, sched_yield, trapstats};
+
+const ROUNDS: usize = 5;
+
+/// 所有进程累计的 `sched_yield` 次数，从 `trapstats` 的输出中解析。
+fn yield_count() -> usize {
+    let mut buf = [0u8; 512];
+    let len = trapstats(&mut buf);
+    assert!(0 <= len && len as usize <= buf.len());
+    core::str::from_utf8(&buf[..len as usize])
+        .unwrap()
+        .lines()
+        .find_map(|line| line.strip_prefix("syscall 124: "))
+        .map_or(0, |n| n.parse().unwrap())
+}
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let pid = fork();
+    assert!(pid >= 0);
+    let tag = if pid == 0 { "child" } else { "parent" };
+    // 让出之后其他进程先运行，再轮到自己时累计次数多于自己这一次
+    let mut interleaved = 0;
+    for i in 0..ROUNDS {
+        println!("{tag}: round {i}");
+        let before = yield_count();
+        sched_yield();
+        if yield_count() > before + 1 {
+            interleaved += 1;
+        }
+    }
+    if pid == 0 {
+        exit(0);
+    }
+    assert!(interleaved > 0);
+    println!("Test yield interleave OK!");
+    0
+}
@@ -0,0 +1,34 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpid, waitpid, yield_to};
+
+const ROUNDS: usize = 3;
+
+#[no_mangle]
+extern "C" fn main() -> i32 {
+    let parent = getpid() as usize;
+    let child = fork();
+    if child == 0 {
+        // 每一轮处理完都直接交还给父进程
+        for i in 0..ROUNDS {
+            println!("consumer: got item {i}");
+            yield_to(parent);
+        }
+        exit(0);
+    }
+    assert!(child > 0);
+    for i in 0..ROUNDS {
+        println!("producer: put item {i}");
+        assert_eq!(yield_to(child as usize), 0);
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(child, &mut exit_code), child);
+    // 不存在的进程，退化为普通的 yield
+    assert_eq!(yield_to(usize::MAX), 0);
+    println!("Test yield_to OK!");
+    0
+}
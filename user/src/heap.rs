@@ -1,33 +1,78 @@
-﻿use alloc::alloc::handle_alloc_error;
-use core::{
+﻿use core::{
     alloc::{GlobalAlloc, Layout},
-    ptr::NonNull,
+    ptr::{null_mut, NonNull},
 };
 use customizable_buddy::{BuddyAllocator, LinkedListBuddy, UsizeBuddy};
 
 /// 初始化全局分配器和内核堆分配器。
+///
+/// 先托管一段静态空间，用完之后再通过 [`grow`] 向内核要，没有打开 `brk` 特性时直接分配失败。
 pub fn init() {
     // 托管空间 16 KiB
     const MEMORY_SIZE: usize = 16 << 10;
     static mut MEMORY: [u8; MEMORY_SIZE] = [0u8; MEMORY_SIZE];
     unsafe {
-        HEAP.init(
-            core::mem::size_of::<usize>().trailing_zeros() as _,
-            NonNull::new(MEMORY.as_mut_ptr()).unwrap(),
-        );
+        HEAP.init(MIN_ORDER, NonNull::new(MEMORY.as_mut_ptr()).unwrap());
         HEAP.transfer(NonNull::new_unchecked(MEMORY.as_mut_ptr()), MEMORY.len());
     }
 }
 
+/// 当前能分配的最大块的字节数，没有空闲块时返回 0。
+///
+/// 分配器没有公开各层的空闲链表，所以从最高层向下试探分配并立即归还，
+/// 第一个成功的阶就是最高的非空空闲层。
+pub fn largest_free_block() -> usize {
+    for order in (MIN_ORDER..=MAX_ORDER).rev() {
+        let layout = unsafe { Layout::from_size_align_unchecked(1 << order, 1 << order) };
+        if let Ok((ptr, _)) = unsafe { HEAP.allocate_layout::<u8>(layout) } {
+            unsafe { HEAP.deallocate_layout(ptr, layout) };
+            return 1 << order;
+        }
+    }
+    0
+}
+
+/// 托管空间不够时，用 `sbrk` 向内核申请一段能放下 `layout` 的空间交给分配器。
+///
+/// 新空间只保证按页对齐，申请块大小的两倍才能保证其中有一个对齐的完整块。
+#[cfg(feature = "brk")]
+fn grow(layout: Layout) -> bool {
+    const PAGE_SIZE: usize = 4096;
+    let size = layout.size().max(layout.align()).next_power_of_two() * 2;
+    let size = (size + PAGE_SIZE - 1) & !(PAGE_SIZE - 1);
+    let base = syscall::sbrk(size as _);
+    if base < 0 {
+        return false;
+    }
+    unsafe { HEAP.transfer(NonNull::new_unchecked(base as *mut u8), size) };
+    true
+}
+
+/// 内核不支持 `brk`，无法扩充托管空间。
+#[cfg(not(feature = "brk"))]
+fn grow(_layout: Layout) -> bool {
+    false
+}
+
 type MutAllocator<const N: usize> = BuddyAllocator<N, UsizeBuddy, LinkedListBuddy>;
 
 // RV64: 使用 32 层 buddy allocator (64-bit usize 支持更大的层级)
 #[cfg(target_pointer_width = "64")]
-static mut HEAP: MutAllocator<32> = MutAllocator::new();
+const LEVELS: usize = 32;
 
 // RV32: 使用 20 层 buddy allocator (32-bit usize 会在层级过高时导致位移溢出)
 #[cfg(target_pointer_width = "32")]
-static mut HEAP: MutAllocator<20> = MutAllocator::new();
+const LEVELS: usize = 20;
+
+/// 最小块的阶数。
+const MIN_ORDER: usize = core::mem::size_of::<usize>().trailing_zeros() as _;
+
+/// 最大块的阶数。
+///
+/// 超过最大块的请求直接失败，不交给分配器去计算越过 usize 位宽的位移。
+const MAX_ORDER: usize = MIN_ORDER + LEVELS;
+
+static mut HEAP: MutAllocator<LEVELS> = MutAllocator::new();
 
 struct Global;
 
@@ -37,10 +82,18 @@ static GLOBAL: Global = Global;
 unsafe impl GlobalAlloc for Global {
     #[inline]
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() > 1 << MAX_ORDER || layout.align() > 1 << MAX_ORDER {
+            return null_mut();
+        }
         if let Ok((ptr, _)) = HEAP.allocate_layout::<u8>(layout) {
-            ptr.as_ptr()
-        } else {
-            handle_alloc_error(layout)
+            return ptr.as_ptr();
+        }
+        if !grow(layout) {
+            return null_mut();
+        }
+        match HEAP.allocate_layout::<u8>(layout) {
+            Ok((ptr, _)) => ptr.as_ptr(),
+            Err(_) => null_mut(),
         }
     }
 
@@ -7,12 +7,23 @@ extern crate alloc;
 use core::alloc::Layout;
 use rcore_console::log;
 
-pub use rcore_console::{print, println};
+pub use heap::largest_free_block;
+pub use rcore_console::{eprint, eprintln, print, println};
 pub use syscall::*;
 
+/// 参数个数和参数字符串指针数组，由 `exec` 放在用户栈上，通过 `a0`、`a1` 传入。
+static mut ARGC: usize = 0;
+static mut ARGV: *const *const u8 = core::ptr::null();
+
 #[no_mangle]
 #[link_section = ".text.entry"]
-pub extern "C" fn _start() -> ! {
+pub extern "C" fn _start(argc: usize, argv: *const *const u8) -> ! {
+    if !argv.is_null() {
+        unsafe {
+            ARGC = argc;
+            ARGV = argv;
+        }
+    }
     rcore_console::init_console(&Console);
     rcore_console::set_log_level(option_env!("LOG"));
     heap::init();
@@ -37,6 +48,15 @@ fn panic_handler(panic_info: &core::panic::PanicInfo) -> ! {
     unreachable!()
 }
 
+/// 程序的参数，不是由带参数的 `exec` 启动时为空。
+pub fn args() -> impl Iterator<Item = &'static str> {
+    (0..unsafe { ARGC }).map(|i| {
+        unsafe { core::ffi::CStr::from_ptr(*ARGV.add(i) as _) }
+            .to_str()
+            .unwrap()
+    })
+}
+
 pub fn getchar() -> u8 {
     let mut c = [0u8; 1];
     read(STDIN, &mut c);
@@ -55,6 +75,11 @@ impl rcore_console::Console for Console {
     fn put_str(&self, s: &str) {
         syscall::write(STDOUT, s.as_bytes());
     }
+
+    #[inline]
+    fn put_debug_str(&self, s: &str) {
+        syscall::write(STDDEBUG, s.as_bytes());
+    }
 }
 
 pub fn sleep(period_ms: usize) {
@@ -23,13 +23,13 @@ impl BlockDevice for BlockFile {
     }
 }
 
-pub fn easy_fs_pack(cases: &Vec<String>, target: &str) -> std::io::Result<()> {
+pub fn easy_fs_pack(cases: &Vec<String>, target: &str, image: &str) -> std::io::Result<()> {
     let block_file = Arc::new(BlockFile(Mutex::new({
         let f = OpenOptions::new()
             .read(true)
             .write(true)
             .create(true)
-            .open(format!("{}/{}", target, "fs.img"))?;
+            .open(format!("{}/{}", target, image))?;
         f.set_len(64 * 2048 * 512).unwrap();
         f
     })));
@@ -12,6 +12,8 @@ use std::{
     ffi::OsString,
     fs,
     path::{Path, PathBuf},
+    process::ExitStatus,
+    time::{Duration, Instant},
 };
 
 const TARGET_ARCH_RV64: &str = "riscv64gc-unknown-none-elf";
@@ -37,6 +39,8 @@ enum Commands {
     Make(BuildArgs),
     Asm(AsmArgs),
     Qemu(QemuArgs),
+    Bench(BenchArgs),
+    TestMatrix(TestMatrixArgs),
 }
 
 fn main() {
@@ -47,6 +51,8 @@ fn main() {
         }
         Asm(args) => args.dump(),
         Qemu(args) => args.run(),
+        Bench(args) => args.bench(),
+        TestMatrix(args) => args.run(),
     }
 }
 
@@ -101,6 +107,12 @@ struct BuildArgs {
     /// build without BIOS (M-Mode entry)
     #[clap(long)]
     nobios: bool,
+    /// link only this user program instead of the cases listed in user/cases.toml
+    #[clap(long)]
+    case: Option<String>,
+    /// start only the app with this index at boot (ch4)
+    #[clap(long)]
+    init: Option<usize>,
 }
 
 impl BuildArgs {
@@ -111,7 +123,7 @@ impl BuildArgs {
         let package = match self.ch {
             1 => if self.lab { "ch1-lab" } else { "ch1" }.to_string(),
             2..=8 => {
-                user::build_for(self.ch, false, self.arch);
+                user::build_for(self.ch, false, self.arch, self.case.as_deref());
                 env.insert(
                     "APP_ASM",
                     target_dir
@@ -120,6 +132,12 @@ impl BuildArgs {
                         .as_os_str()
                         .to_os_string(),
                 );
+                // 额外的镜像按顺序挂在 `fs.img` 之后，内核按名字找到槽位
+                let images = user::fs_images(self.ch, self.case.as_deref());
+                if !images.is_empty() {
+                    let names = images.into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+                    env.insert("FS_IMAGES", names.join(" ").into());
+                }
                 format!("ch{}", self.ch)
             }
             _ => unreachable!(),
@@ -134,6 +152,9 @@ impl BuildArgs {
             .optional(&self.log, |cargo, log| {
                 cargo.env("LOG", log);
             })
+            .optional(&self.init, |cargo, init| {
+                cargo.env("INIT_APP", init.to_string());
+            })
             .conditional(self.release, |cargo| {
                 cargo.release();
             })
@@ -185,10 +206,18 @@ struct QemuArgs {
     /// Port for gdb to connect. If set, qemu will block and wait gdb to connect.
     #[clap(long)]
     gdb: Option<u16>,
+    /// Kernel command line, passed to the kernel as `/chosen/bootargs` in the device tree.
+    #[clap(long)]
+    append: Option<String>,
 }
 
 impl QemuArgs {
     fn run(self) {
+        self.command().invoke();
+    }
+
+    /// 构建内核并生成启动它的 qemu 命令。
+    fn command(&self) -> Qemu {
         let target_arch = self.build.arch.target();
         let target_dir = get_target_dir(target_arch);
         let elf = self.build.make();
@@ -225,33 +254,217 @@ impl QemuArgs {
             .args(&["-m", "64M"])
             .args(&["-serial", "mon:stdio"]);
         if self.build.ch > 5 {
-            // Add VirtIO Device
-            qemu.args(&[
-                "-drive",
-                format!(
-                    "file={},if=none,format=raw,id=x0",
-                    target_dir
-                        .join(if self.build.release {
-                            "release"
-                        } else {
-                            "debug"
-                        })
-                        .join("fs.img")
-                        .into_os_string()
-                        .into_string()
-                        .unwrap()
-                )
-                .as_str(),
-            ])
-            .args(&[
-                "-device",
-                "virtio-blk-device,drive=x0,bus=virtio-mmio-bus.0",
-            ]);
+            // Add VirtIO Device，默认镜像在第 0 个槽位，额外的镜像依次排在后面
+            let images = user::fs_images(self.build.ch, self.build.case.as_deref())
+                .into_iter()
+                .map(|(name, _)| format!("fs-{name}.img"));
+            for (i, image) in std::iter::once("fs.img".to_string())
+                .chain(images)
+                .enumerate()
+            {
+                let file = target_dir
+                    .join(if self.build.release {
+                        "release"
+                    } else {
+                        "debug"
+                    })
+                    .join(image);
+                qemu.args(&[
+                    "-drive",
+                    format!(
+                        "file={},if=none,format=raw,id=x{i}",
+                        file.into_os_string().into_string().unwrap()
+                    )
+                    .as_str(),
+                ])
+                .args(&[
+                    "-device",
+                    format!("virtio-blk-device,drive=x{i},bus=virtio-mmio-bus.{i}").as_str(),
+                ]);
+            }
         }
+        qemu.optional(&self.append, |qemu, append| {
+            qemu.args(&["-append", append]);
+        });
         qemu.optional(&self.gdb, |qemu, gdb| {
             qemu.args(&["-S", "-gdb", &format!("tcp::{gdb}")]);
-        })
-        .invoke();
+        });
+        qemu
+    }
+}
+
+#[derive(Args)]
+struct BenchArgs {
+    #[clap(flatten)]
+    qemu: QemuArgs,
+    /// Number of runs.
+    #[clap(short, long, default_value_t = 5)]
+    runs: usize,
+}
+
+impl BenchArgs {
+    /// 重复启动内核直到关机，统计每次的墙上时间。
+    ///
+    /// 内核的输出被捕获而不是打印，所以只适合不需要交互、能自行关机的章节。
+    fn bench(self) {
+        let mut qemu = self.qemu.command();
+        let mut times = Vec::with_capacity(self.runs);
+        for i in 0..self.runs {
+            let Boot {
+                time,
+                status,
+                lines,
+                panicked,
+                ..
+            } = Boot::run(&mut qemu);
+            println!(
+                "run {i}: {:.3}s, {lines} lines of output, {panicked} panics, {status}",
+                time.as_secs_f64(),
+            );
+            times.push(time);
+        }
+        if times.is_empty() {
+            return;
+        }
+        let min = times.iter().min().unwrap();
+        let max = times.iter().max().unwrap();
+        let mean = times.iter().sum::<Duration>() / times.len() as u32;
+        println!(
+            "ch{} boot-to-shutdown over {} runs: min {:.3}s, mean {:.3}s, max {:.3}s",
+            self.qemu.build.ch,
+            times.len(),
+            min.as_secs_f64(),
+            mean.as_secs_f64(),
+            max.as_secs_f64(),
+        );
+    }
+}
+
+/// 一次启动到关机的结果。
+struct Boot {
+    /// 墙上时间。
+    time: Duration,
+    /// qemu 的退出状态，内核正常关机时是成功。
+    status: ExitStatus,
+    /// 内核输出的行数。
+    lines: usize,
+    /// 输出中 panic 的次数。
+    panicked: usize,
+    /// 内核的全部输出。
+    log: String,
+}
+
+impl Boot {
+    /// 启动内核并捕获输出，直到 qemu 退出。
+    fn run(qemu: &mut Qemu) -> Self {
+        let start = Instant::now();
+        let output = qemu.output();
+        let time = start.elapsed();
+        let log = String::from_utf8_lossy(&output.stdout).into_owned();
+        Self {
+            time,
+            status: output.status,
+            lines: log.lines().count(),
+            panicked: log.lines().filter(|line| line.contains("panicked")).count(),
+            log,
+        }
+    }
+
+    /// 检查 `fairness` 用例的各个进程轮到的次数是否大致相等，没有这个用例时通过。
+    ///
+    /// 只统计第一个进程做完之前的轮数，此后进程数变少，不再可比。
+    fn check_fairness(&self) -> Result<(), String> {
+        const TOLERANCE: usize = 2;
+        let mut turns = HashMap::<&str, usize>::new();
+        for line in self.log.lines() {
+            let Some(pos) = line.find("fairness ") else {
+                continue;
+            };
+            let mut words = line[pos..].split_whitespace().skip(1);
+            match (words.next(), words.next()) {
+                (Some(id), Some("turn")) => *turns.entry(id).or_default() += 1,
+                (Some(_), Some("done")) => break,
+                _ => {}
+            }
+        }
+        let (Some(min), Some(max)) = (turns.values().min(), turns.values().max()) else {
+            return Ok(());
+        };
+        if max - min > TOLERANCE {
+            Err(format!("unfair round-robin turns: {turns:?}"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// 正常关机并且没有 panic。
+    fn clean(&self) -> bool {
+        self.status.success() && self.panicked == 0
+    }
+}
+
+/// 架构测试矩阵：每一章和它要测试的架构。
+///
+/// 只有 ch3、ch4 有 M 态入口，能以 `--nobios` 在 riscv32 上运行；
+/// 第五章起内核启动 shell 等待输入，不会自行关机，所以不在矩阵里。
+const TEST_MATRIX: &[(u8, &[Arch])] = &[
+    (1, &[Arch::Riscv64]),
+    (2, &[Arch::Riscv64]),
+    (3, &[Arch::Riscv64, Arch::Riscv32]),
+    (4, &[Arch::Riscv64, Arch::Riscv32]),
+];
+
+#[derive(Args)]
+struct TestMatrixArgs {
+    /// build in release mode
+    #[clap(long)]
+    release: bool,
+}
+
+impl TestMatrixArgs {
+    /// 逐格构建并启动内核，要求每一格都正常关机，并且轮转调度是公平的。
+    ///
+    /// 有失败的格子时以非零状态退出。
+    fn run(self) {
+        let mut failed = Vec::new();
+        for &(ch, archs) in TEST_MATRIX {
+            for &arch in archs {
+                let qemu = QemuArgs {
+                    build: BuildArgs {
+                        ch,
+                        arch,
+                        release: self.release,
+                        nobios: arch == Arch::Riscv32,
+                        ..Default::default()
+                    },
+                    qemu_dir: None,
+                    smp: None,
+                    gdb: None,
+                    append: None,
+                };
+                let boot = Boot::run(&mut qemu.command());
+                let fairness = boot.check_fairness();
+                let passed = boot.clean() && fairness.is_ok();
+                let result = if passed { "ok" } else { "FAILED" };
+                println!(
+                    "ch{ch} {arch:?}: {result} in {:.3}s, {} panics, {}",
+                    boot.time.as_secs_f64(),
+                    boot.panicked,
+                    boot.status,
+                );
+                if let Err(e) = fairness {
+                    println!("  {e}");
+                }
+                if !passed {
+                    failed.push(format!("ch{ch} {arch:?}"));
+                }
+            }
+        }
+        if !failed.is_empty() {
+            eprintln!("test matrix failed: {}", failed.join(", "));
+            std::process::exit(1);
+        }
+        println!("test matrix passed");
     }
 }
 
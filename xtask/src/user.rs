@@ -1,13 +1,26 @@
 use crate::{fs_pack::easy_fs_pack, get_target_dir, objcopy, Arch, PROJECT};
 use os_xtask_utils::{Cargo, CommandExt};
 use serde_derive::Deserialize;
-use std::{collections::HashMap, ffi::OsStr, fs::File, io::Write, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::OsStr,
+    fs::File,
+    io::Write,
+    path::PathBuf,
+};
 
 #[derive(Deserialize, Default)]
 struct Cases {
     base: Option<u64>,
     step: Option<u64>,
+    /// 应用程序所在内存的上界，每个程序的 `address..address + step` 都不能越过它，
+    /// 不指定时只检查目标架构的地址宽度
+    ceiling: Option<u64>,
     pub cases: Option<Vec<String>>,
+    /// 第六章起额外打包的文件系统镜像，名字到其中程序的映射，程序必须出现在 `cases` 里
+    images: Option<BTreeMap<String, Vec<String>>>,
+    /// 编译这一章的用户程序时打开的 `user_lib` 特性
+    features: Option<Vec<String>>,
 }
 
 pub struct CasesInfo {
@@ -21,10 +34,36 @@ impl Cases {
         if let Some(names) = &self.cases {
             let base = self.base.unwrap_or(0);
             let step = self.step.filter(|_| self.base.is_some()).unwrap_or(0);
+            let features = self.features.as_deref().unwrap_or_default();
+            // 先检查所有基址，避免编译了一半才发现配置错误
+            let width_limit = if target_arch.starts_with("riscv32") {
+                1 << 32
+            } else {
+                u64::MAX
+            };
+            let ceiling = self.ceiling.unwrap_or(width_limit);
+            let addresses = (0..names.len() as u64)
+                .map(|i| {
+                    i.checked_mul(step)
+                        .and_then(|offset| base.checked_add(offset))
+                        .filter(|&address| {
+                            address < ceiling
+                                && address.checked_add(step).is_some_and(|end| end <= ceiling)
+                        })
+                        .unwrap_or_else(|| {
+                            panic!(
+                                "slot of {:?} ({base:#x} + {i} * {step:#x}, {step:#x} bytes) exceeds ceiling {ceiling:#x}, check user/cases.toml",
+                                names[i as usize],
+                            )
+                        })
+                })
+                .collect::<Vec<_>>();
             let cases = names
                 .into_iter()
-                .enumerate()
-                .map(|(i, name)| build_one(name, release, base + i as u64 * step, target_arch))
+                .zip(addresses)
+                .map(|(name, base_address)| {
+                    build_one(name, release, base_address, target_arch, features)
+                })
                 .collect();
             CasesInfo {
                 base,
@@ -41,7 +80,13 @@ impl Cases {
     }
 }
 
-fn build_one(name: impl AsRef<OsStr>, release: bool, base_address: u64, target_arch: &str) -> PathBuf {
+fn build_one(
+    name: impl AsRef<OsStr>,
+    release: bool,
+    base_address: u64,
+    target_arch: &str,
+    features: &[String],
+) -> PathBuf {
     let name = name.as_ref();
     let binary = base_address != 0;
     if binary {
@@ -52,6 +97,9 @@ fn build_one(name: impl AsRef<OsStr>, release: bool, base_address: u64, target_a
         .target(target_arch)
         .arg("--bin")
         .arg(name)
+        .conditional(!features.is_empty(), |cargo| {
+            cargo.features(false, features.iter().map(String::as_str));
+        })
         .conditional(release, |cargo| {
             cargo.release();
         })
@@ -63,22 +111,57 @@ fn build_one(name: impl AsRef<OsStr>, release: bool, base_address: u64, target_a
         .join(if release { "release" } else { "debug" })
         .join(name);
     if binary {
-        objcopy(elf, binary)
+        // 同一个程序可以在用例列表中出现多次，按基址区分生成的二进制文件
+        let bin = objcopy(elf, binary);
+        let unique = bin.with_extension(format!("{base_address:x}.bin"));
+        std::fs::rename(&bin, &unique).unwrap();
+        unique
     } else {
         elf
     }
 }
 
-pub fn build_for(ch: u8, release: bool, kernel_arch: Arch) {
+fn load_cases(ch: u8) -> Cases {
+    let cfg = std::fs::read_to_string(PROJECT.join("user/cases.toml")).unwrap();
+    toml::from_str::<HashMap<String, Cases>>(&cfg)
+        .unwrap()
+        .remove(&format!("ch{ch}"))
+        .unwrap_or_default()
+}
+
+/// 除 `fs.img` 之外额外打包的文件系统镜像，按名字排序，也就是挂载到 virtio 槽位上的顺序。
+///
+/// 镜像只在第六章起、没有用 `--case` 只链接一个程序时打包。
+pub fn fs_images(ch: u8, case: Option<&str>) -> Vec<(String, Vec<String>)> {
+    if ch < 6 || case.is_some() {
+        return vec![];
+    }
+    load_cases(ch)
+        .images
+        .unwrap_or_default()
+        .into_iter()
+        .collect()
+}
+
+pub fn build_for(ch: u8, release: bool, kernel_arch: Arch, case: Option<&str>) {
     // 用户程序的目标架构与内核保持一致
     let target_arch = kernel_arch.target();
     let target_dir = get_target_dir(target_arch);
     
-    let cfg = std::fs::read_to_string(PROJECT.join("user/cases.toml")).unwrap();
-    let mut cases = toml::from_str::<HashMap<String, Cases>>(&cfg)
-        .unwrap()
-        .remove(&format!("ch{ch}"))
-        .unwrap_or_default();
+    let mut cases = load_cases(ch);
+    // 只链接指定的程序，基址沿用这一章的配置。
+    // 从第五章开始内核先启动 initproc，再由 user_shell 运行程序，所以保留这两个
+    if let Some(case) = case {
+        let mut names = vec![case.to_string()];
+        if ch >= 5 {
+            for boot in ["user_shell", "initproc"] {
+                if case != boot {
+                    names.push(boot.to_string());
+                }
+            }
+        }
+        cases.cases = Some(names);
+    }
     let CasesInfo { base, step, bins } = cases.build(release, target_arch);
     if bins.is_empty() {
         return;
@@ -105,7 +188,8 @@ pub fn build_for(ch: u8, release: bool, kernel_arch: Arch) {
 apps:
     {data_directive} {base:#x}
     {data_directive} {step:#x}
-    {data_directive} {}",
+    {data_directive} {}
+    {data_directive} app_names",
         bins.len(),
     )
     .unwrap();
@@ -125,29 +209,33 @@ app_{i}_end:",
         .unwrap();
     });
 
-    if ch == 5 {
-        writeln!(
-            ld,
-            "
-    .align {align_size}
+    // 名字表与应用程序一一对应，同一个程序出现多次时名字也重复
+    writeln!(
+        ld,
+        "
     .section .data
-    .global app_names
 app_names:"
-        )
-        .unwrap();
-        bins.iter().enumerate().for_each(|(_, path)| {
-            writeln!(ld, "    .string {:?}", path.file_name().unwrap()).unwrap();
-        });
-    } else if ch >= 6 {
-        easy_fs_pack(
-            &cases.cases.unwrap(),
-            target_dir
-                .join(if release { "release" } else { "debug" })
-                .into_os_string()
-                .into_string()
-                .unwrap()
-                .as_str(),
-        )
-        .unwrap();
+    )
+    .unwrap();
+    for name in cases.cases.as_ref().unwrap() {
+        writeln!(ld, "    .string {name:?}").unwrap();
+    }
+
+    if ch >= 6 {
+        let target = target_dir
+            .join(if release { "release" } else { "debug" })
+            .into_os_string()
+            .into_string()
+            .unwrap();
+        let names = cases.cases.unwrap();
+        easy_fs_pack(&names, &target, "fs.img").unwrap();
+        for (image, apps) in fs_images(ch, case) {
+            if let Some(app) = apps.iter().find(|app| !names.contains(app)) {
+                panic!(
+                    "{app:?} in fs image {image:?} is not a case of ch{ch}, check user/cases.toml"
+                );
+            }
+            easy_fs_pack(&apps, &target, &format!("fs-{image}.img")).unwrap();
+        }
     }
 }